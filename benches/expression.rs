@@ -0,0 +1,303 @@
+// Compares the Pratt expression parser with the eight-level pest cascade it replaced on a
+// generated query corpus. benches/cascade.pest is a frozen copy of the grammar before the
+// rewrite, and build_cascade is the expression builder of that version adapted to the
+// current AST; neither follows later changes to the parser.
+//
+//     cargo bench --bench expression
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use pest::iterators::Pair;
+use pest::Parser;
+use rust_sql::ast::{BinaryOperator, Expression, Ident, Literal, Span, UnaryOperator};
+use rust_sql::expression::parse_expression;
+use rust_sql::parser::{
+    parse_sql, parse_statement_with_options, ParseOptions, Rule as SqlRule, SQLParser,
+};
+
+mod cascade {
+    #[derive(pest_derive::Parser)]
+    #[grammar = "../benches/cascade.pest"]
+    pub struct CascadeParser;
+}
+
+use cascade::{CascadeParser, Rule};
+
+// The expression builder before the Pratt parser: one recursive call per grammar level
+fn build_cascade(pair: Pair<Rule>) -> Expression {
+    match pair.as_rule() {
+        Rule::expression | Rule::primary_expression => {
+            build_cascade(pair.into_inner().next().unwrap())
+        }
+        Rule::or_expression
+        | Rule::and_expression
+        | Rule::equality_expression
+        | Rule::comparison_expression
+        | Rule::additive_expression
+        | Rule::multiplicative_expression => {
+            let mut inner = pair.into_inner();
+            let mut expr = build_cascade(inner.next().unwrap());
+            while let Some(op_pair) = inner.next() {
+                let operator = match op_pair.as_rule() {
+                    Rule::OR => BinaryOperator::Or,
+                    Rule::AND => BinaryOperator::And,
+                    Rule::EQUAL => BinaryOperator::Equal,
+                    Rule::NOT_EQUAL => BinaryOperator::NotEqual,
+                    Rule::LESS_THAN => BinaryOperator::LessThan,
+                    Rule::LESS_THAN_OR_EQUAL => BinaryOperator::LessThanOrEqual,
+                    Rule::GREATER_THAN => BinaryOperator::GreaterThan,
+                    Rule::GREATER_THAN_OR_EQUAL => BinaryOperator::GreaterThanOrEqual,
+                    Rule::ADD => BinaryOperator::Add,
+                    Rule::SUBTRACT => BinaryOperator::Subtract,
+                    Rule::MULTIPLY => BinaryOperator::Multiply,
+                    Rule::DIVIDE => BinaryOperator::Divide,
+                    rule => unreachable!("Unexpected operator rule: {:?}", rule),
+                };
+                let right = build_cascade(inner.next().unwrap());
+                expr = Expression::Binary {
+                    left: Box::new(expr),
+                    operator,
+                    right: Box::new(right),
+                    span: Span::default(),
+                };
+            }
+            expr
+        }
+        Rule::unary_expression => {
+            let mut inner = pair.into_inner();
+            let first = inner.next().unwrap();
+            let operator = match first.as_rule() {
+                Rule::NOT => UnaryOperator::Not,
+                Rule::MINUS => UnaryOperator::Minus,
+                _ => return build_cascade(first),
+            };
+            Expression::Unary {
+                operator,
+                operand: Box::new(build_cascade(inner.next().unwrap())),
+                span: Span::default(),
+            }
+        }
+        Rule::string_literal => literal(Literal::String(
+            pair.as_str().trim_matches('\'').to_string(),
+        )),
+        Rule::number_literal => literal(Literal::Number(pair.as_str().parse().unwrap())),
+        Rule::float_literal => literal(Literal::Float(pair.as_str().parse().unwrap())),
+        Rule::boolean_literal => literal(Literal::Boolean(pair.as_str().to_uppercase() == "TRUE")),
+        Rule::null_literal => literal(Literal::Null),
+        Rule::identifier => Expression::Column(Ident::new(pair.as_str())),
+        rule => unreachable!("Unexpected rule: {:?}", rule),
+    }
+}
+
+fn literal(value: Literal) -> Expression {
+    Expression::Literal {
+        value,
+        span: Span::default(),
+    }
+}
+
+fn parse_cascade(sql: &str) -> Expression {
+    let pair = CascadeParser::parse(Rule::expression, sql)
+        .unwrap()
+        .next()
+        .unwrap();
+    build_cascade(pair)
+}
+
+// Deterministic pseudo-random generator so that every run sees the same corpus. Qualified
+// columns and function calls are only generated when `extended` is set, because the cascade
+// grammar predates them
+struct Generator {
+    state: u64,
+    extended: bool,
+}
+
+impl Generator {
+    fn new(seed: u64, extended: bool) -> Self {
+        Generator {
+            state: seed,
+            extended,
+        }
+    }
+
+    fn next(&mut self, bound: u64) -> u64 {
+        self.state = self
+            .state
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        (self.state >> 33) % bound
+    }
+
+    fn operand(&mut self, depth: u32) -> String {
+        match self.next(if depth == 0 { 5 } else { 7 }) {
+            0 => self.next(10_000).to_string(),
+            1 => format!("{}.{}", self.next(100), self.next(100)),
+            2 => format!("'value_{}'", self.next(1000)),
+            4 if self.extended => format!("t{}.column_{}", self.next(3), self.next(20)),
+            6 if self.extended => format!(
+                "f{}({}, {})",
+                self.next(5),
+                self.expression(depth - 1),
+                self.operand(depth - 1)
+            ),
+            3 | 4 => format!("column_{}", self.next(20)),
+            _ => format!("({})", self.expression(depth - 1)),
+        }
+    }
+
+    fn expression(&mut self, depth: u32) -> String {
+        const OPERATORS: [&str; 12] = [
+            "OR", "AND", "=", "<>", "<", "<=", ">", ">=", "+", "-", "*", "/",
+        ];
+        let mut expr = self.operand(depth);
+        for _ in 0..self.next(6) {
+            let operator = OPERATORS[self.next(OPERATORS.len() as u64) as usize];
+            let prefix = match self.next(8) {
+                0 => "NOT ",
+                1 => "-",
+                _ => "",
+            };
+            expr = format!("{} {} {}{}", expr, operator, prefix, self.operand(depth));
+        }
+        expr
+    }
+}
+
+// Corpora in the syntax both parsers accept
+fn corpus() -> Vec<(&'static str, Vec<String>)> {
+    let mut generator = Generator::new(0x5eed, false);
+    let literals = (0..200).map(|i| i.to_string()).collect();
+    let flat = (0..200).map(|_| generator.expression(0)).collect();
+    let nested = (0..200).map(|_| generator.expression(3)).collect();
+    vec![("literal", literals), ("flat", flat), ("nested", nested)]
+}
+
+// Sanity check that both parsers agree on the corpus before timing them
+fn check_corpus() {
+    for (_, queries) in corpus() {
+        for sql in &queries {
+            assert_eq!(
+                parse_cascade(sql),
+                parse_expression(sql).unwrap(),
+                "{}",
+                sql
+            );
+        }
+    }
+}
+
+fn bench_expressions(c: &mut Criterion) {
+    let mut group = c.benchmark_group("expression");
+    for (name, queries) in corpus() {
+        let bytes: usize = queries.iter().map(String::len).sum();
+        group.throughput(Throughput::Bytes(bytes as u64));
+        group.bench_with_input(BenchmarkId::new("cascade", name), &queries, |b, queries| {
+            b.iter(|| {
+                for sql in queries {
+                    black_box(parse_cascade(sql));
+                }
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("pratt", name), &queries, |b, queries| {
+            b.iter(|| {
+                for sql in queries {
+                    black_box(parse_expression(sql).unwrap());
+                }
+            })
+        });
+    }
+    // Qualified columns and function calls, which only the Pratt parser handles
+    let mut generator = Generator::new(0x5eed, true);
+    let extended: Vec<String> = (0..200).map(|_| generator.expression(3)).collect();
+    let bytes: usize = extended.iter().map(String::len).sum();
+    group.throughput(Throughput::Bytes(bytes as u64));
+    group.bench_with_input(
+        BenchmarkId::new("pratt", "extended"),
+        &extended,
+        |b, queries| {
+            b.iter(|| {
+                for sql in queries {
+                    black_box(parse_expression(sql).unwrap());
+                }
+            })
+        },
+    );
+    group.finish();
+}
+
+fn bench_statements(c: &mut Criterion) {
+    // No ORDER BY: the cascade grammar reads the OR of ORDER as an operator after WHERE
+    let mut generator = Generator::new(0xc0ffee, false);
+    let queries: Vec<String> = (0..200)
+        .map(|i| {
+            format!(
+                "SELECT * FROM table_{} WHERE {} LIMIT {};",
+                i % 10,
+                generator.expression(2),
+                generator.next(100)
+            )
+        })
+        .collect();
+    let bytes: usize = queries.iter().map(String::len).sum();
+
+    let mut group = c.benchmark_group("statement");
+    group.throughput(Throughput::Bytes(bytes as u64));
+    // Grammar only, without building the AST
+    group.bench_function("cascade_grammar", |b| {
+        b.iter(|| {
+            for sql in &queries {
+                black_box(CascadeParser::parse(Rule::statement, sql).unwrap());
+            }
+        })
+    });
+    group.bench_function("flat_grammar", |b| {
+        b.iter(|| {
+            for sql in &queries {
+                black_box(SQLParser::parse(SqlRule::statement, sql).unwrap());
+            }
+        })
+    });
+    group.bench_function("parse_sql", |b| {
+        b.iter(|| {
+            for sql in &queries {
+                black_box(parse_sql(sql).unwrap());
+            }
+        })
+    });
+    group.finish();
+}
+
+// Single expressions of growing length, spread over many lines, where the time per byte
+// should stay flat. Each operator nests the tree one level deeper, so the limit is raised to
+// the chain length; the release build's main thread has the stack to drop such trees
+fn bench_long_expressions(c: &mut Criterion) {
+    let mut group = c.benchmark_group("long_expression");
+    for terms in [100, 1_000, 10_000] {
+        let options = ParseOptions {
+            max_depth: terms,
+            ..ParseOptions::default()
+        };
+        let sum = vec!["1"; terms].join(" +\n");
+        let disjunction = (0..terms)
+            .map(|i| format!("id = {}", i))
+            .collect::<Vec<_>>()
+            .join("\nOR ");
+        for (name, sql) in [("sum", sum), ("or", disjunction)] {
+            let statement = format!("SELECT * FROM t WHERE {};", sql);
+            group.throughput(Throughput::Bytes(statement.len() as u64));
+            group.bench_with_input(BenchmarkId::new(name, terms), &statement, |b, sql| {
+                b.iter(|| black_box(parse_statement_with_options(sql, &options).unwrap()))
+            });
+        }
+    }
+    group.finish();
+}
+
+fn benches(c: &mut Criterion) {
+    check_corpus();
+    bench_expressions(c);
+    bench_statements(c);
+    bench_long_expressions(c);
+}
+
+criterion_group!(expression_benches, benches);
+criterion_main!(expression_benches);
@@ -1,43 +1,331 @@
-#[derive(Debug, PartialEq)]
+// ソース上の位置。start と end はバイトオフセット、line と column は1始まり。
+// AST の構造比較に位置が影響しないよう、PartialEq は常に等しいとみなす。
+#[derive(Debug, Clone, Copy, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Span {
+    // 二つの範囲を覆う範囲を返す
+    pub fn union(&self, other: &Span) -> Span {
+        let first = if self.start <= other.start {
+            self
+        } else {
+            other
+        };
+        Span {
+            start: first.start,
+            end: self.end.max(other.end),
+            line: first.line,
+            column: first.column,
+        }
+    }
+}
+
+impl PartialEq for Span {
+    fn eq(&self, _other: &Span) -> bool {
+        true
+    }
+}
+
+impl Eq for Span {}
+
+// 解析した識別子は空にならない。空の名前を組み立てた AST は "" と出力され、再び解析すると
+// エラーになる（MySQL では空文字列になる）
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Ident {
+    pub value: String,
+    pub span: Span,
+}
+
+impl Ident {
+    pub fn new(value: impl Into<String>) -> Self {
+        Ident {
+            value: value.into(),
+            span: Span::default(),
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.value
+    }
+}
+
+impl From<&str> for Ident {
+    fn from(value: &str) -> Self {
+        Ident::new(value)
+    }
+}
+
+impl From<String> for Ident {
+    fn from(value: String) -> Self {
+        Ident::new(value)
+    }
+}
+
+impl PartialEq<str> for Ident {
+    fn eq(&self, other: &str) -> bool {
+        self.value == other
+    }
+}
+
+impl PartialEq<&str> for Ident {
+    fn eq(&self, other: &&str) -> bool {
+        self.value == *other
+    }
+}
+
+// スキーマで修飾できるテーブル名（schema.table）。
+// serde 機能では修飾のない名前が Ident と同じ JSON になるよう、name を展開して schema を省略する。
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ObjectName {
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub schema: Option<Ident>,
+    #[cfg_attr(feature = "serde", serde(flatten))]
+    pub name: Ident,
+}
+
+impl ObjectName {
+    pub fn new(name: impl Into<Ident>) -> Self {
+        ObjectName {
+            schema: None,
+            name: name.into(),
+        }
+    }
+
+    pub fn qualified(schema: impl Into<Ident>, name: impl Into<Ident>) -> Self {
+        ObjectName {
+            schema: Some(schema.into()),
+            name: name.into(),
+        }
+    }
+
+    pub fn span(&self) -> Span {
+        match &self.schema {
+            Some(schema) => schema.span.union(&self.name.span),
+            None => self.name.span,
+        }
+    }
+
+    // name がこのテーブルを指すか。スキーマのない name はどのスキーマの同名のテーブルも指す
+    pub fn is_named(&self, name: &ObjectName) -> bool {
+        let same_schema = match (&self.schema, &name.schema) {
+            (_, None) => true,
+            (Some(schema), Some(other)) => same_name(&schema.value, &other.value),
+            (None, Some(_)) => false,
+        };
+        same_schema && same_name(&self.name.value, &name.name.value)
+    }
+}
+
+// 名前の比較規則。AST は引用符の有無を持たないため、ASCII の大文字小文字を区別しない。
+// カタログ、別名の解決、書き換えはすべてこの規則で名前を比べる
+pub fn same_name(a: &str, b: &str) -> bool {
+    a.eq_ignore_ascii_case(b)
+}
+
+impl From<Ident> for ObjectName {
+    fn from(name: Ident) -> Self {
+        ObjectName::new(name)
+    }
+}
+
+impl From<&str> for ObjectName {
+    fn from(name: &str) -> Self {
+        ObjectName::new(name)
+    }
+}
+
+impl From<String> for ObjectName {
+    fn from(name: String) -> Self {
+        ObjectName::new(name)
+    }
+}
+
+impl PartialEq<str> for ObjectName {
+    fn eq(&self, other: &str) -> bool {
+        self.schema.is_none() && self.name == *other
+    }
+}
+
+impl PartialEq<&str> for ObjectName {
+    fn eq(&self, other: &&str) -> bool {
+        self.schema.is_none() && self.name == *other
+    }
+}
+
+// serde 機能を有効にすると、列挙型は変種名を "type" に持つ JSON オブジェクトになる。
+// 値を1つだけ持つ変種（Literal や DataType など）は、その値を "value" に持つ。
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "type"))]
 pub enum Statement {
     Select {
-        table: String,
+        table: ObjectName,
         where_clause: Option<Expression>,
         order_by: Option<OrderBy>,
         group_by: Option<GroupBy>,
-        limit: Option<u64>,
+        // 数値リテラルかパラメータ
+        limit: Option<Expression>,
+        offset: Option<Expression>,
+        span: Span,
     },
     Insert {
-        table: String,
+        table: ObjectName,
         values: Vec<String>,
+        on_conflict: Option<OnConflict>,
+        span: Span,
     },
     Update {
-        table: String,
-        set: Vec<(String, String)>,
+        table: ObjectName,
+        set: Vec<(Ident, String)>,
         where_clause: Option<Expression>,
+        span: Span,
     },
     Delete {
-        table: String,
+        table: ObjectName,
         where_clause: Option<Expression>,
+        span: Span,
+    },
+    Merge {
+        table: ObjectName,
+        alias: Option<Ident>,
+        source: MergeSource,
+        on: Expression,
+        clauses: Vec<MergeClause>,
+        span: Span,
+    },
+    Prepare {
+        name: Ident,
+        parameter_types: Vec<DataType>,
+        statement: Box<Statement>,
+        span: Span,
+    },
+    Execute {
+        name: Ident,
+        parameters: Vec<Expression>,
+        span: Span,
+    },
+    // name が None の場合は DEALLOCATE ALL
+    Deallocate {
+        name: Option<Ident>,
+        span: Span,
+    },
+    CreateTable {
+        table: ObjectName,
+        columns: Vec<ColumnDefinition>,
+        span: Span,
     },
 }
 
-#[derive(Debug, PartialEq)]
+impl Statement {
+    pub fn span(&self) -> Span {
+        match self {
+            Statement::Select { span, .. }
+            | Statement::Insert { span, .. }
+            | Statement::Update { span, .. }
+            | Statement::Delete { span, .. }
+            | Statement::Merge { span, .. }
+            | Statement::Prepare { span, .. }
+            | Statement::Execute { span, .. }
+            | Statement::Deallocate { span, .. }
+            | Statement::CreateTable { span, .. } => *span,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "type"))]
 pub enum Expression {
-    Literal(Literal),
-    Column(String),
+    Literal {
+        value: Literal,
+        span: Span,
+    },
+    // 列名の位置は Ident が保持する
+    Column(Ident),
+    QualifiedColumn {
+        table: Ident,
+        column: Ident,
+    },
+    Function {
+        name: Ident,
+        args: Vec<Expression>,
+        span: Span,
+    },
+    Grouping {
+        args: Vec<Expression>,
+        span: Span,
+    },
+    // $1 や ? などのパラメータプレースホルダ
+    Parameter {
+        name: String,
+        span: Span,
+    },
     Binary {
         left: Box<Expression>,
         operator: BinaryOperator,
         right: Box<Expression>,
+        span: Span,
     },
     Unary {
         operator: UnaryOperator,
         operand: Box<Expression>,
+        span: Span,
+    },
+    // CAST(expression AS type)。PostgreSQL の expression::type も同じ形になる
+    Cast {
+        expression: Box<Expression>,
+        data_type: DataType,
+        span: Span,
+    },
+    // expression [NOT] IN (list)。list は空でない
+    InList {
+        expression: Box<Expression>,
+        list: Vec<Expression>,
+        negated: bool,
+        span: Span,
     },
 }
 
-#[derive(Debug, PartialEq)]
+impl Expression {
+    pub fn span(&self) -> Span {
+        match self {
+            Expression::Column(ident) => ident.span,
+            Expression::QualifiedColumn { table, column } => table.span.union(&column.span),
+            Expression::Literal { span, .. }
+            | Expression::Function { span, .. }
+            | Expression::Grouping { span, .. }
+            | Expression::Parameter { span, .. }
+            | Expression::Binary { span, .. }
+            | Expression::Unary { span, .. }
+            | Expression::Cast { span, .. }
+            | Expression::InList { span, .. } => *span,
+        }
+    }
+
+    // 演算子の結合の強さ。IN は LIKE と同じ強さで結合する
+    pub fn precedence(&self) -> Option<u8> {
+        match self {
+            Expression::Binary { operator, .. } => Some(operator.precedence()),
+            Expression::InList { .. } => Some(BinaryOperator::Like.precedence()),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "type", content = "value"))]
 pub enum Literal {
     String(String),
     Number(i64),
@@ -47,6 +335,7 @@ pub enum Literal {
 }
 
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum BinaryOperator {
     // 比較演算子
     Equal,
@@ -55,6 +344,9 @@ pub enum BinaryOperator {
     LessThanOrEqual,
     GreaterThan,
     GreaterThanOrEqual,
+    Like,
+    // 大文字と小文字を区別しない LIKE（PostgreSQL）
+    ILike,
     // 論理演算子
     And,
     Or,
@@ -65,30 +357,253 @@ pub enum BinaryOperator {
     Divide,
 }
 
-#[derive(Debug, PartialEq)]
+impl BinaryOperator {
+    // 結合の強さ。値が大きいほど強く結合し、すべて左結合。単項演算子はこれらより強い
+    pub fn precedence(&self) -> u8 {
+        match self {
+            BinaryOperator::Or => 1,
+            BinaryOperator::And => 2,
+            BinaryOperator::Equal | BinaryOperator::NotEqual => 3,
+            BinaryOperator::LessThan
+            | BinaryOperator::LessThanOrEqual
+            | BinaryOperator::GreaterThan
+            | BinaryOperator::GreaterThanOrEqual
+            | BinaryOperator::Like
+            | BinaryOperator::ILike => 4,
+            BinaryOperator::Add | BinaryOperator::Subtract => 5,
+            BinaryOperator::Multiply | BinaryOperator::Divide => 6,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum UnaryOperator {
     Not,
     Minus,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct OrderBy {
     pub items: Vec<OrderByItem>,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct OrderByItem {
-    pub column: String,
+    pub column: Ident,
     pub direction: OrderDirection,
+    pub span: Span,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum OrderDirection {
     Asc,
     Desc,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct GroupBy {
-    pub columns: Vec<String>,
+    pub items: Vec<GroupingElement>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "type", content = "value"))]
+pub enum GroupingElement {
+    Expression(Expression),
+    // (a, b) のような列の組。空の組 () は空のVecで表す
+    Set(Vec<Expression>),
+    Rollup(Vec<GroupingElement>),
+    Cube(Vec<GroupingElement>),
+    GroupingSets(Vec<GroupingElement>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "type"))]
+pub enum OnConflict {
+    // PostgreSQL: ON CONFLICT [target] DO NOTHING
+    DoNothing {
+        target: Option<ConflictTarget>,
+    },
+    // PostgreSQL: ON CONFLICT target DO UPDATE SET ... [WHERE ...]
+    DoUpdate {
+        target: ConflictTarget,
+        set: Vec<(Ident, Expression)>,
+        where_clause: Option<Expression>,
+    },
+    // MySQL: ON DUPLICATE KEY UPDATE ...
+    DuplicateKeyUpdate {
+        set: Vec<(Ident, Expression)>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "type", content = "value"))]
+pub enum ConflictTarget {
+    Columns(Vec<Ident>),
+    Constraint(Ident),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "type"))]
+pub enum MergeSource {
+    Table {
+        name: ObjectName,
+        alias: Option<Ident>,
+    },
+    Subquery {
+        query: Box<Statement>,
+        alias: Option<Ident>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MergeClause {
+    // WHEN MATCHED なら true、WHEN NOT MATCHED なら false
+    pub matched: bool,
+    pub condition: Option<Expression>,
+    pub action: MergeAction,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "type"))]
+pub enum MergeAction {
+    Update {
+        set: Vec<(Ident, Expression)>,
+    },
+    Delete,
+    Insert {
+        columns: Vec<Ident>,
+        values: Vec<Expression>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "type", content = "value"))]
+pub enum DataType {
+    SmallInt,
+    Integer,
+    BigInt,
+    Real,
+    DoublePrecision,
+    Float(Option<u64>),
+    Decimal {
+        precision: Option<u64>,
+        scale: Option<u64>,
+    },
+    Char(Option<u64>),
+    Varchar(Option<u64>),
+    Text,
+    Boolean,
+    Date,
+    Time,
+    Timestamp,
+    // PostgreSQL の連番型
+    SmallSerial,
+    Serial,
+    BigSerial,
+    Custom(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ColumnDefinition {
+    pub name: Ident,
+    pub data_type: DataType,
+    pub options: Vec<ColumnOption>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "type", content = "value"))]
+pub enum ColumnOption {
+    NotNull,
+    Null,
+    PrimaryKey,
+    Unique,
+    Default(Expression),
+    // MySQL の AUTO_INCREMENT と SQLite の AUTOINCREMENT
+    AutoIncrement,
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod tests {
+    use super::{DataType, GroupingElement, Statement};
+    use crate::corpus;
+    use crate::parser::{parse_script, parse_sql};
+    use serde_json::json;
+
+    #[test]
+    fn test_json_shape() {
+        let statement = parse_sql("DELETE FROM users WHERE NOT active;").unwrap();
+        assert_eq!(
+            serde_json::to_value(&statement).unwrap(),
+            json!({
+                "type": "Delete",
+                "table": {
+                    "value": "users",
+                    "span": {"start": 12, "end": 17, "line": 1, "column": 13}
+                },
+                "where_clause": {
+                    "type": "Unary",
+                    "operator": "Not",
+                    "operand": {
+                        "type": "Column",
+                        "value": "active",
+                        "span": {"start": 28, "end": 34, "line": 1, "column": 29}
+                    },
+                    "span": {"start": 24, "end": 34, "line": 1, "column": 25}
+                },
+                "span": {"start": 0, "end": 35, "line": 1, "column": 1}
+            })
+        );
+
+        // Variants holding a single value keep it under "value"
+        assert_eq!(
+            serde_json::to_value(DataType::Varchar(Some(20))).unwrap(),
+            json!({"type": "Varchar", "value": 20})
+        );
+        assert_eq!(
+            serde_json::to_value(DataType::Text).unwrap(),
+            json!({"type": "Text"})
+        );
+        assert_eq!(
+            serde_json::to_value(GroupingElement::Set(Vec::new())).unwrap(),
+            json!({"type": "Set", "value": []})
+        );
+    }
+
+    #[test]
+    fn test_json_round_trip_corpus() {
+        for statement in corpus::statements() {
+            let json = serde_json::to_string(&statement).unwrap();
+            let decoded: Statement = serde_json::from_str(&json).unwrap();
+            assert_eq!(decoded, statement, "{}", json);
+            // Spans are not compared by PartialEq, so compare the encodings as well
+            assert_eq!(serde_json::to_string(&decoded).unwrap(), json);
+        }
+    }
+
+    #[test]
+    fn test_parse_errors_serialize() {
+        let script = parse_script("SELEC x;");
+        let json = serde_json::to_value(&script).unwrap();
+        assert_eq!(json["statements"], json!([]));
+        assert_eq!(json["diagnostics"][0]["type"], "Syntax");
+        assert_eq!(
+            json["diagnostics"][0]["location"],
+            json!({"offset": 0, "line": 1, "column": 1})
+        );
+    }
 }
@@ -0,0 +1,805 @@
+use crate::ast::{
+    same_name, BinaryOperator, ConflictTarget, DataType, Expression, GroupingElement, Ident,
+    Literal, MergeAction, MergeSource, ObjectName, OnConflict, Span, Statement, UnaryOperator,
+};
+use crate::catalog::{Catalog, Table};
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum BindError {
+    UnknownTable {
+        name: String,
+        span: Span,
+    },
+    UnknownColumn {
+        name: String,
+        span: Span,
+    },
+    // A table name without a schema that tables in more than one schema have
+    AmbiguousTable {
+        name: String,
+        tables: Vec<String>,
+        span: Span,
+    },
+    // An unqualified column that more than one table of a MERGE provides
+    AmbiguousColumn {
+        name: String,
+        tables: Vec<String>,
+        span: Span,
+    },
+}
+
+impl BindError {
+    pub fn span(&self) -> Span {
+        match self {
+            BindError::UnknownTable { span, .. }
+            | BindError::UnknownColumn { span, .. }
+            | BindError::AmbiguousTable { span, .. }
+            | BindError::AmbiguousColumn { span, .. } => *span,
+        }
+    }
+}
+
+impl fmt::Display for BindError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BindError::UnknownTable { name, .. } => write!(f, "unknown table {}", name)?,
+            BindError::UnknownColumn { name, .. } => write!(f, "unknown column {}", name)?,
+            BindError::AmbiguousTable { name, tables, .. } => write!(
+                f,
+                "table {} is ambiguous between {}",
+                name,
+                tables.join(" and ")
+            )?,
+            BindError::AmbiguousColumn { name, tables, .. } => write!(
+                f,
+                "column {} is ambiguous between {}",
+                name,
+                tables.join(" and ")
+            )?,
+        }
+        let span = self.span();
+        write!(f, " at line {}, column {}", span.line, span.column)
+    }
+}
+
+impl std::error::Error for BindError {}
+
+// A node of the bound statement, compared and hashed by its address: two equal expressions
+// at different places in the statement are different nodes
+#[derive(Debug, Clone, Copy)]
+pub struct Node<'a, T>(pub &'a T);
+
+impl<T> PartialEq for Node<'_, T> {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self.0, other.0)
+    }
+}
+
+impl<T> Eq for Node<'_, T> {}
+
+impl<T> Hash for Node<'_, T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        std::ptr::hash(self.0, state);
+    }
+}
+
+// The result of binding a statement. Nodes are looked up by identity, so the annotations
+// only apply to the statement that was bound
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Binding<'a> {
+    // Every expression with its inferred type, or None where it cannot be inferred
+    pub types: HashMap<Node<'a, Expression>, Option<DataType>>,
+    // Every column name that was resolved, with the table it belongs to
+    pub columns: HashMap<Node<'a, Ident>, String>,
+    pub errors: Vec<BindError>,
+}
+
+impl Binding<'_> {
+    // The expression is only compared by address, but has to outlive the borrow of the map
+    pub fn type_of<'s>(&'s self, expression: &'s Expression) -> Option<&'s DataType> {
+        self.types.get(&Node(expression))?.as_ref()
+    }
+
+    pub fn table_of<'s>(&'s self, column: &'s Ident) -> Option<&'s str> {
+        self.columns.get(&Node(column)).map(String::as_str)
+    }
+
+    pub fn is_ok(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
+// Resolves the tables and columns of a statement against a catalog and infers the type of
+// every expression. Columns of a table that is not in the catalog are not reported again
+pub fn bind<'a>(statement: &'a Statement, catalog: &dyn Catalog) -> Binding<'a> {
+    let mut binder = Binder {
+        catalog,
+        parameters: &[],
+        binding: Binding::default(),
+    };
+    binder.statement(statement);
+    binder.binding
+}
+
+// A table that names in a statement can refer to
+struct Source<'c> {
+    // The alias, or the table name without its schema
+    qualifier: String,
+    table: Option<&'c Table>,
+}
+
+struct Binder<'a, 'c> {
+    catalog: &'c dyn Catalog,
+    // The parameter types of the enclosing PREPARE
+    parameters: &'a [DataType],
+    binding: Binding<'a>,
+}
+
+impl<'a, 'c> Binder<'a, 'c> {
+    fn statement(&mut self, statement: &'a Statement) {
+        match statement {
+            Statement::Select {
+                table,
+                where_clause,
+                order_by,
+                group_by,
+                ..
+            } => {
+                let sources = [self.source(table, None)];
+                if let Some(where_clause) = where_clause {
+                    self.expression(where_clause, &sources);
+                }
+                for element in group_by.iter().flat_map(|group_by| &group_by.items) {
+                    self.grouping_element(element, &sources);
+                }
+                for item in order_by.iter().flat_map(|order_by| &order_by.items) {
+                    self.column(&item.column, &sources);
+                }
+            }
+            Statement::Insert {
+                table, on_conflict, ..
+            } => {
+                let target = self.source(table, None);
+                match on_conflict {
+                    Some(OnConflict::DoNothing {
+                        target: Some(ConflictTarget::Columns(columns)),
+                    }) => self.columns(columns, &target),
+                    Some(OnConflict::DoUpdate {
+                        target: conflict,
+                        set,
+                        where_clause,
+                    }) => {
+                        if let ConflictTarget::Columns(columns) = conflict {
+                            self.columns(columns, &target);
+                        }
+                        // EXCLUDED is the row that was proposed for insertion
+                        let excluded = Source {
+                            qualifier: "EXCLUDED".to_string(),
+                            table: target.table,
+                        };
+                        let sources = [target, excluded];
+                        self.assignments(set, &sources);
+                        if let Some(where_clause) = where_clause {
+                            self.expression(where_clause, &sources);
+                        }
+                    }
+                    Some(OnConflict::DuplicateKeyUpdate { set }) => {
+                        self.assignments(set, std::slice::from_ref(&target));
+                    }
+                    Some(OnConflict::DoNothing { .. }) | None => {}
+                }
+            }
+            Statement::Update {
+                table,
+                set,
+                where_clause,
+                ..
+            } => {
+                let sources = [self.source(table, None)];
+                for (column, _) in set {
+                    self.column(column, &sources);
+                }
+                if let Some(where_clause) = where_clause {
+                    self.expression(where_clause, &sources);
+                }
+            }
+            Statement::Delete {
+                table,
+                where_clause,
+                ..
+            } => {
+                let sources = [self.source(table, None)];
+                if let Some(where_clause) = where_clause {
+                    self.expression(where_clause, &sources);
+                }
+            }
+            Statement::Merge {
+                table,
+                alias,
+                source,
+                on,
+                clauses,
+                ..
+            } => {
+                let target = self.source(table, alias.as_ref());
+                let source = match source {
+                    MergeSource::Table { name, alias } => self.source(name, alias.as_ref()),
+                    MergeSource::Subquery { query, alias } => {
+                        self.statement(query);
+                        // The subquery selects every column of its table
+                        let table = match query.as_ref() {
+                            Statement::Select { table, .. } => Some(table),
+                            _ => None,
+                        };
+                        Source {
+                            qualifier: alias
+                                .as_ref()
+                                .or(table.map(|table| &table.name))
+                                .map(|name| name.value.clone())
+                                .unwrap_or_default(),
+                            // The subquery has reported a table that does not resolve
+                            table: table.and_then(|table| {
+                                match self.catalog.tables_named(table)[..] {
+                                    [table] => Some(table),
+                                    _ => None,
+                                }
+                            }),
+                        }
+                    }
+                };
+                let sources = [target, source];
+                self.expression(on, &sources);
+                for clause in clauses {
+                    if let Some(condition) = &clause.condition {
+                        self.expression(condition, &sources);
+                    }
+                    match &clause.action {
+                        MergeAction::Update { set } => self.assignments(set, &sources),
+                        MergeAction::Insert { columns, values } => {
+                            self.columns(columns, &sources[0]);
+                            for value in values {
+                                self.expression(value, &sources);
+                            }
+                        }
+                        MergeAction::Delete => {}
+                    }
+                }
+            }
+            Statement::Prepare {
+                parameter_types,
+                statement,
+                ..
+            } => {
+                let enclosing = std::mem::replace(&mut self.parameters, parameter_types);
+                self.statement(statement);
+                self.parameters = enclosing;
+            }
+            Statement::Execute { parameters, .. } => {
+                for parameter in parameters {
+                    self.expression(parameter, &[]);
+                }
+            }
+            Statement::Deallocate { .. } => {}
+            // The table is being defined, so only the defaults are bound
+            Statement::CreateTable { columns, .. } => {
+                for column in columns {
+                    for option in &column.options {
+                        if let crate::ast::ColumnOption::Default(default) = option {
+                            self.expression(default, &[]);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn source(&mut self, name: &'a ObjectName, alias: Option<&'a Ident>) -> Source<'c> {
+        let tables = self.catalog.tables_named(name);
+        let table = match tables[..] {
+            [table] => Some(table),
+            [] => {
+                self.binding.errors.push(BindError::UnknownTable {
+                    name: crate::references::table_name(name),
+                    span: name.span(),
+                });
+                None
+            }
+            _ => {
+                self.binding.errors.push(BindError::AmbiguousTable {
+                    name: crate::references::table_name(name),
+                    tables: tables
+                        .iter()
+                        .map(|table| crate::references::table_name(&table.name))
+                        .collect(),
+                    span: name.span(),
+                });
+                None
+            }
+        };
+        Source {
+            qualifier: alias.unwrap_or(&name.name).value.clone(),
+            table,
+        }
+    }
+
+    // Assignment targets belong to the first source, the table being written
+    fn assignments(&mut self, set: &'a [(Ident, Expression)], sources: &[Source<'c>]) {
+        for (column, value) in set {
+            self.columns(std::slice::from_ref(column), &sources[0]);
+            self.expression(value, sources);
+        }
+    }
+
+    fn columns(&mut self, columns: &'a [Ident], target: &Source<'c>) {
+        for column in columns {
+            self.column(column, std::slice::from_ref(target));
+        }
+    }
+
+    fn grouping_element(&mut self, element: &'a GroupingElement, sources: &[Source<'c>]) {
+        match element {
+            GroupingElement::Expression(expression) => {
+                self.expression(expression, sources);
+            }
+            GroupingElement::Set(expressions) => {
+                for expression in expressions {
+                    self.expression(expression, sources);
+                }
+            }
+            GroupingElement::Rollup(elements)
+            | GroupingElement::Cube(elements)
+            | GroupingElement::GroupingSets(elements) => {
+                for element in elements {
+                    self.grouping_element(element, sources);
+                }
+            }
+        }
+    }
+
+    fn column(&mut self, column: &'a Ident, sources: &[Source<'c>]) -> Option<DataType> {
+        let matches: Vec<_> = sources
+            .iter()
+            .filter_map(|source| {
+                let found = source.table?.column(&column.value)?;
+                Some((source, found))
+            })
+            .collect();
+        match matches.as_slice() {
+            [(source, found)] => {
+                let table = crate::references::table_name(&source.table?.name);
+                self.binding.columns.insert(Node(column), table);
+                Some(found.data_type.clone())
+            }
+            [] => {
+                if sources.iter().all(|source| source.table.is_some()) {
+                    self.binding.errors.push(BindError::UnknownColumn {
+                        name: column.value.clone(),
+                        span: column.span,
+                    });
+                }
+                None
+            }
+            _ => {
+                self.binding.errors.push(BindError::AmbiguousColumn {
+                    name: column.value.clone(),
+                    tables: matches
+                        .iter()
+                        .map(|(source, _)| source.qualifier.clone())
+                        .collect(),
+                    span: column.span,
+                });
+                None
+            }
+        }
+    }
+
+    fn qualified_column(
+        &mut self,
+        table: &'a Ident,
+        column: &'a Ident,
+        sources: &[Source<'c>],
+    ) -> Option<DataType> {
+        let Some(source) = sources
+            .iter()
+            .find(|source| same_name(&source.qualifier, &table.value))
+        else {
+            self.binding.errors.push(BindError::UnknownTable {
+                name: table.value.clone(),
+                span: table.span,
+            });
+            return None;
+        };
+        source.table?;
+        self.column(column, std::slice::from_ref(source))
+    }
+
+    fn expression(
+        &mut self,
+        expression: &'a Expression,
+        sources: &[Source<'c>],
+    ) -> Option<DataType> {
+        let data_type = match expression {
+            Expression::Literal { value, .. } => literal_type(value),
+            Expression::Column(column) => self.column(column, sources),
+            Expression::QualifiedColumn { table, column } => {
+                self.qualified_column(table, column, sources)
+            }
+            Expression::Function { name, args, .. } => {
+                let args: Vec<_> = args
+                    .iter()
+                    .map(|arg| self.expression(arg, sources))
+                    .collect();
+                function_type(&name.value, &args)
+            }
+            Expression::Grouping { args, .. } => {
+                for arg in args {
+                    self.expression(arg, sources);
+                }
+                Some(DataType::Integer)
+            }
+            Expression::Parameter { name, .. } => name
+                .strip_prefix('$')
+                .and_then(|index| index.parse::<usize>().ok())
+                .and_then(|index| self.parameters.get(index.checked_sub(1)?))
+                .cloned(),
+            Expression::Binary {
+                left,
+                operator,
+                right,
+                ..
+            } => {
+                let left = self.expression(left, sources);
+                let right = self.expression(right, sources);
+                binary_type(operator, left, right)
+            }
+            Expression::InList {
+                expression: inner,
+                list,
+                ..
+            } => {
+                self.expression(inner, sources);
+                for item in list {
+                    self.expression(item, sources);
+                }
+                Some(DataType::Boolean)
+            }
+            Expression::Unary {
+                operator, operand, ..
+            } => {
+                let operand = self.expression(operand, sources);
+                match operator {
+                    UnaryOperator::Not => Some(DataType::Boolean),
+                    UnaryOperator::Minus => {
+                        operand.filter(|operand| numeric_rank(operand).is_some())
+                    }
+                }
+            }
+            Expression::Cast {
+                expression: inner,
+                data_type,
+                ..
+            } => {
+                self.expression(inner, sources);
+                Some(data_type.clone())
+            }
+        };
+        self.binding
+            .types
+            .insert(Node(expression), data_type.clone());
+        data_type
+    }
+}
+
+// Numbers without a fraction are INTEGER when they fit and BIGINT otherwise; numbers with one
+// are NUMERIC, as in PostgreSQL. NULL has no type of its own
+fn literal_type(literal: &Literal) -> Option<DataType> {
+    match literal {
+        Literal::Number(value) if i32::try_from(*value).is_ok() => Some(DataType::Integer),
+        Literal::Number(_) => Some(DataType::BigInt),
+        Literal::Float(_) => Some(DataType::Decimal {
+            precision: None,
+            scale: None,
+        }),
+        Literal::String(_) => Some(DataType::Text),
+        Literal::Boolean(_) => Some(DataType::Boolean),
+        Literal::Null => None,
+    }
+}
+
+fn function_type(name: &str, args: &[Option<DataType>]) -> Option<DataType> {
+    match name.to_ascii_lowercase().as_str() {
+        "count" => Some(DataType::BigInt),
+        "lower" | "upper" => Some(DataType::Text),
+        "length" => Some(DataType::Integer),
+        "abs" | "min" | "max" | "coalesce" => args.iter().flatten().next().cloned(),
+        _ => None,
+    }
+}
+
+fn binary_type(
+    operator: &BinaryOperator,
+    left: Option<DataType>,
+    right: Option<DataType>,
+) -> Option<DataType> {
+    match operator {
+        BinaryOperator::Add
+        | BinaryOperator::Subtract
+        | BinaryOperator::Multiply
+        | BinaryOperator::Divide => match (left, right) {
+            (Some(left), Some(right)) => {
+                let wider = if numeric_rank(&left)? >= numeric_rank(&right)? {
+                    left
+                } else {
+                    right
+                };
+                Some(without_serial(wider))
+            }
+            (Some(known), None) | (None, Some(known)) => {
+                numeric_rank(&known).map(|_| without_serial(known))
+            }
+            (None, None) => None,
+        },
+        _ => Some(DataType::Boolean),
+    }
+}
+
+// The order in which numeric types widen in arithmetic, or None for other types
+pub(crate) fn numeric_rank(data_type: &DataType) -> Option<u8> {
+    match data_type {
+        DataType::SmallInt | DataType::SmallSerial => Some(1),
+        DataType::Integer | DataType::Serial => Some(2),
+        DataType::BigInt | DataType::BigSerial => Some(3),
+        DataType::Decimal { .. } => Some(4),
+        DataType::Real => Some(5),
+        DataType::Float(_) | DataType::DoublePrecision => Some(6),
+        _ => None,
+    }
+}
+
+fn without_serial(data_type: DataType) -> DataType {
+    match data_type {
+        DataType::SmallSerial => DataType::SmallInt,
+        DataType::Serial => DataType::Integer,
+        DataType::BigSerial => DataType::BigInt,
+        other => other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::catalog::MemoryCatalog;
+    use crate::parser::parse_sql;
+
+    fn catalog() -> MemoryCatalog {
+        MemoryCatalog::from_sql(
+            "CREATE TABLE users (id SERIAL PRIMARY KEY, name TEXT, age SMALLINT, score REAL);
+             CREATE TABLE staging.users (id INTEGER, name TEXT);
+             CREATE TABLE orders (id BIGINT, user_id INTEGER, total DECIMAL(10, 2));",
+        )
+        .unwrap()
+    }
+
+    fn where_clause(statement: &Statement) -> &Expression {
+        match statement {
+            Statement::Select {
+                where_clause: Some(where_clause),
+                ..
+            }
+            | Statement::Delete {
+                where_clause: Some(where_clause),
+                ..
+            } => where_clause,
+            _ => panic!("no WHERE clause"),
+        }
+    }
+
+    #[test]
+    fn test_infers_types() {
+        let catalog = catalog();
+        let statement = parse_sql("SELECT * FROM users WHERE age + 1 > score * 2;").unwrap();
+        let binding = bind(&statement, &catalog);
+        assert!(binding.is_ok(), "{:?}", binding.errors);
+
+        let Expression::Binary { left, right, .. } = where_clause(&statement) else {
+            panic!("expected a comparison");
+        };
+        assert_eq!(
+            binding.type_of(where_clause(&statement)),
+            Some(&DataType::Boolean)
+        );
+        assert_eq!(binding.type_of(left), Some(&DataType::Integer));
+        assert_eq!(binding.type_of(right), Some(&DataType::Real));
+        assert_eq!(binding.types.len(), 7);
+        let mut columns: Vec<_> = binding
+            .columns
+            .iter()
+            .map(|(column, table)| (column.0.as_str(), table.as_str()))
+            .collect();
+        columns.sort();
+        assert_eq!(columns, [("age", "users"), ("score", "users")]);
+    }
+
+    #[test]
+    fn test_infers_function_cast_and_parameter_types() {
+        let catalog = catalog();
+        let statement = parse_sql(
+            "PREPARE q (bigint) AS DELETE FROM orders \
+             WHERE user_id = $1 OR total - CAST(id AS DOUBLE PRECISION) < ABS(total);",
+        )
+        .unwrap();
+        let binding = bind(&statement, &catalog);
+        assert!(binding.is_ok(), "{:?}", binding.errors);
+
+        let Statement::Prepare { statement, .. } = &statement else {
+            panic!("expected PREPARE");
+        };
+        let Expression::Binary { left, right, .. } = where_clause(statement) else {
+            panic!("expected OR");
+        };
+        let (
+            Expression::Binary {
+                right: parameter, ..
+            },
+            Expression::Binary {
+                left: difference,
+                right: abs,
+                ..
+            },
+        ) = (left.as_ref(), right.as_ref())
+        else {
+            panic!("expected comparisons");
+        };
+        assert_eq!(binding.type_of(parameter), Some(&DataType::BigInt));
+        assert_eq!(
+            binding.type_of(difference),
+            Some(&DataType::DoublePrecision)
+        );
+        assert_eq!(
+            binding.type_of(abs),
+            Some(&DataType::Decimal {
+                precision: Some(10),
+                scale: Some(2),
+            })
+        );
+    }
+
+    #[test]
+    fn test_unknown_names() {
+        let catalog = catalog();
+        let statement = parse_sql("SELECT * FROM users WHERE nickname = 'x' ORDER BY id;").unwrap();
+        let binding = bind(&statement, &catalog);
+        assert_eq!(
+            binding.errors,
+            [BindError::UnknownColumn {
+                name: "nickname".to_string(),
+                span: Span::default(),
+            }]
+        );
+        assert_eq!(
+            binding.errors[0].to_string(),
+            "unknown column nickname at line 1, column 27"
+        );
+
+        // The columns of an unknown table are not reported again
+        let statement = parse_sql("DELETE FROM accounts WHERE id = 1;").unwrap();
+        let binding = bind(&statement, &catalog);
+        assert_eq!(binding.errors.len(), 1);
+        assert_eq!(
+            binding.errors[0].to_string(),
+            "unknown table accounts at line 1, column 13"
+        );
+
+        let statement = parse_sql("DELETE FROM audit.users WHERE id = 1;").unwrap();
+        assert_eq!(
+            bind(&statement, &catalog).errors[0].to_string(),
+            "unknown table audit.users at line 1, column 13"
+        );
+    }
+
+    #[test]
+    fn test_ambiguous_table() {
+        let catalog = MemoryCatalog::from_sql(
+            "CREATE TABLE a.events (id BIGINT); CREATE TABLE b.events (id BIGINT, kind TEXT);",
+        )
+        .unwrap();
+        let statement = parse_sql("SELECT * FROM events WHERE id = 1;").unwrap();
+        let binding = bind(&statement, &catalog);
+        assert_eq!(
+            binding.errors,
+            [BindError::AmbiguousTable {
+                name: "events".to_string(),
+                tables: vec!["a.events".to_string(), "b.events".to_string()],
+                span: Span::default(),
+            }]
+        );
+        assert_eq!(
+            binding.errors[0].to_string(),
+            "table events is ambiguous between a.events and b.events at line 1, column 15"
+        );
+
+        // A schema picks one of them
+        let statement = parse_sql("SELECT * FROM b.events WHERE kind = 'x';").unwrap();
+        assert!(bind(&statement, &catalog).is_ok());
+
+        // The subquery of a MERGE reports its table once
+        let statement = parse_sql(
+            "MERGE INTO a.events t USING (SELECT * FROM events) s ON t.id = s.id \
+             WHEN MATCHED THEN DELETE;",
+        )
+        .unwrap();
+        assert_eq!(bind(&statement, &catalog).errors.len(), 1);
+    }
+
+    #[test]
+    fn test_merge_scopes() {
+        let catalog = catalog();
+        let statement = parse_sql(
+            "MERGE INTO users AS t USING staging.users s ON t.id = s.id \
+             WHEN MATCHED AND age > 30 THEN UPDATE SET name = s.name \
+             WHEN NOT MATCHED THEN INSERT (id, name) VALUES (s.id, name);",
+        )
+        .unwrap();
+        let binding = bind(&statement, &catalog);
+        assert_eq!(
+            binding.errors,
+            [BindError::AmbiguousColumn {
+                name: "name".to_string(),
+                tables: vec!["t".to_string(), "s".to_string()],
+                span: Span::default(),
+            }]
+        );
+        assert!(binding.errors[0]
+            .to_string()
+            .starts_with("column name is ambiguous between t and s at line 1"));
+        assert!(binding
+            .columns
+            .iter()
+            .any(|(column, table)| *column.0 == "age" && table == "users"));
+        assert!(binding
+            .columns
+            .iter()
+            .any(|(column, table)| *column.0 == "id" && table == "staging.users"));
+
+        let statement = parse_sql(
+            "MERGE INTO orders USING (SELECT * FROM users) AS u ON orders.user_id = u.id \
+             WHEN MATCHED THEN DELETE;",
+        )
+        .unwrap();
+        assert!(bind(&statement, &catalog).is_ok());
+
+        let statement = parse_sql(
+            "MERGE INTO orders USING users ON orders.user_id = x.id WHEN MATCHED THEN DELETE;",
+        )
+        .unwrap();
+        assert_eq!(
+            bind(&statement, &catalog).errors[0].to_string(),
+            "unknown table x at line 1, column 51"
+        );
+    }
+
+    #[test]
+    fn test_upsert_columns() {
+        let catalog = catalog();
+        let statement = parse_sql(
+            "INSERT INTO users VALUES ('1', 'foo') ON CONFLICT (id) \
+             DO UPDATE SET name = EXCLUDED.name, nickname = 'x' WHERE users.age < 18;",
+        )
+        .unwrap();
+        let binding = bind(&statement, &catalog);
+        assert_eq!(binding.errors.len(), 1);
+        assert!(binding.errors[0]
+            .to_string()
+            .starts_with("unknown column nickname"));
+        assert_eq!(binding.columns.len(), 4);
+
+        let statement =
+            parse_sql("UPDATE users SET name = 'x', email = 'y' WHERE id = 1;").unwrap();
+        let binding = bind(&statement, &catalog);
+        assert_eq!(binding.errors.len(), 1);
+        assert_eq!(binding.table_of(&Ident::new("name")), None);
+    }
+}
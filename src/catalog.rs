@@ -0,0 +1,207 @@
+use crate::ast::{same_name, ColumnOption, DataType, ObjectName, Statement};
+use crate::error::ParseError;
+use crate::parser::parse_script;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Column {
+    pub name: String,
+    pub data_type: DataType,
+    pub nullable: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Table {
+    pub name: ObjectName,
+    pub columns: Vec<Column>,
+}
+
+impl Table {
+    // The table that a CREATE TABLE statement defines
+    pub fn from_statement(statement: &Statement) -> Option<Table> {
+        let Statement::CreateTable { table, columns, .. } = statement else {
+            return None;
+        };
+        let columns = columns
+            .iter()
+            .map(|column| Column {
+                name: column.name.value.clone(),
+                data_type: column.data_type.clone(),
+                nullable: !column.options.iter().any(|option| {
+                    matches!(option, ColumnOption::NotNull | ColumnOption::PrimaryKey)
+                }),
+            })
+            .collect();
+        Some(Table {
+            name: table.clone(),
+            columns,
+        })
+    }
+
+    pub fn column(&self, name: &str) -> Option<&Column> {
+        self.columns
+            .iter()
+            .find(|column| same_name(&column.name, name))
+    }
+}
+
+// The tables that statements are checked against
+pub trait Catalog {
+    // Every table that the name may refer to. More than one means the name is ambiguous
+    fn tables_named(&self, name: &ObjectName) -> Vec<&Table>;
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MemoryCatalog {
+    tables: Vec<Table>,
+}
+
+impl MemoryCatalog {
+    pub fn new() -> Self {
+        MemoryCatalog::default()
+    }
+
+    // Reads the CREATE TABLE statements of a script and ignores the others.
+    // Fails with the first diagnostic if any statement does not parse
+    pub fn from_sql(sql: &str) -> Result<Self, ParseError> {
+        let script = parse_script(sql);
+        if let Some(error) = script.diagnostics.into_iter().next() {
+            return Err(error);
+        }
+        let mut catalog = MemoryCatalog::new();
+        for statement in &script.statements {
+            if let Some(table) = Table::from_statement(statement) {
+                catalog.add_table(table);
+            }
+        }
+        Ok(catalog)
+    }
+
+    // Replaces any table of the same name
+    pub fn add_table(&mut self, table: Table) {
+        self.tables
+            .retain(|known| !same_table(&known.name, &table.name));
+        self.tables.push(table);
+    }
+
+    pub fn tables(&self) -> &[Table] {
+        &self.tables
+    }
+}
+
+// Both names are qualified by the same schema, or neither is
+fn same_table(a: &ObjectName, b: &ObjectName) -> bool {
+    a.schema.is_some() == b.schema.is_some() && a.is_named(b)
+}
+
+impl Catalog for MemoryCatalog {
+    // A name without a schema also finds the tables of that name in every schema,
+    // unless a table was defined without one
+    fn tables_named(&self, name: &ObjectName) -> Vec<&Table> {
+        match self
+            .tables
+            .iter()
+            .find(|table| same_table(&table.name, name))
+        {
+            Some(table) => vec![table],
+            None => self
+                .tables
+                .iter()
+                .filter(|table| table.name.is_named(name))
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCHEMA: &str = "
+        CREATE TABLE users (id INTEGER PRIMARY KEY, name VARCHAR(40) NOT NULL, email TEXT);
+        CREATE TABLE audit.events (id BIGINT, payload TEXT);
+        DELETE FROM users WHERE id = 1;
+    ";
+
+    #[test]
+    fn test_catalog_from_create_table() {
+        let catalog = MemoryCatalog::from_sql(SCHEMA).unwrap();
+        assert_eq!(catalog.tables().len(), 2);
+
+        let users = catalog.tables_named(&"users".into())[0];
+        assert_eq!(
+            users.columns,
+            [
+                Column {
+                    name: "id".to_string(),
+                    data_type: DataType::Integer,
+                    nullable: false,
+                },
+                Column {
+                    name: "name".to_string(),
+                    data_type: DataType::Varchar(Some(40)),
+                    nullable: false,
+                },
+                Column {
+                    name: "email".to_string(),
+                    data_type: DataType::Text,
+                    nullable: true,
+                },
+            ]
+        );
+        assert_eq!(users.column("EMAIL").unwrap().name, "email");
+        assert!(users.column("missing").is_none());
+    }
+
+    #[test]
+    fn test_lookup_by_schema() {
+        let catalog = MemoryCatalog::from_sql(SCHEMA).unwrap();
+        let named = |name: &ObjectName| catalog.tables_named(name).len();
+        assert_eq!(named(&ObjectName::qualified("audit", "events")), 1);
+        assert_eq!(named(&"events".into()), 1);
+        assert_eq!(named(&ObjectName::qualified("public", "events")), 0);
+        assert_eq!(named(&"missing".into()), 0);
+    }
+
+    #[test]
+    fn test_lookup_across_schemas() {
+        let catalog = MemoryCatalog::from_sql(
+            "CREATE TABLE a.events (id BIGINT); CREATE TABLE b.events (id BIGINT);",
+        )
+        .unwrap();
+        let names = |name: &ObjectName| {
+            catalog
+                .tables_named(name)
+                .iter()
+                .map(|table| table.name.clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            names(&"events".into()),
+            [
+                ObjectName::qualified("a", "events"),
+                ObjectName::qualified("b", "events")
+            ]
+        );
+        assert_eq!(
+            names(&ObjectName::qualified("B", "events")),
+            [ObjectName::qualified("b", "events")]
+        );
+        // A table without a schema is the one an unqualified name means
+        let mut catalog = catalog.clone();
+        catalog.add_table(Table {
+            name: "events".into(),
+            columns: Vec::new(),
+        });
+        assert_eq!(catalog.tables_named(&"events".into()).len(), 1);
+    }
+
+    #[test]
+    fn test_add_table_replaces_same_name() {
+        let mut catalog = MemoryCatalog::from_sql(SCHEMA).unwrap();
+        let statement = crate::parser::parse_statement("CREATE TABLE Users (id BIGINT);").unwrap();
+        catalog.add_table(Table::from_statement(&statement).unwrap());
+        assert_eq!(catalog.tables().len(), 2);
+        assert_eq!(catalog.tables_named(&"users".into())[0].columns.len(), 1);
+        assert!(MemoryCatalog::from_sql("CREATE TABLE t (id INTEGER").is_err());
+    }
+}
@@ -0,0 +1,20 @@
+use crate::ast::Statement;
+use crate::parser::parse_script;
+
+// The statements and expressions that the round-trip tests of the parser, the renderer, the
+// formatter and the JSON encoding all run over. New syntax belongs in these files
+const STATEMENTS: &str = include_str!("../tests/corpus.sql");
+const EXPRESSIONS: &str = include_str!("../tests/expressions.sql");
+
+pub(crate) fn statements() -> Vec<Statement> {
+    let script = parse_script(STATEMENTS);
+    assert!(script.is_ok(), "{:?}", script.diagnostics);
+    script.statements
+}
+
+// One expression per line, skipping blank lines and comments
+pub(crate) fn expressions() -> impl Iterator<Item = &'static str> {
+    EXPRESSIONS
+        .lines()
+        .filter(|line| !line.trim().is_empty() && !line.starts_with("--"))
+}
@@ -0,0 +1,526 @@
+use crate::keywords::{keyword_kind, KeywordKind};
+use std::borrow::Cow;
+use std::fmt;
+use std::str::FromStr;
+use unicode_normalization::UnicodeNormalization;
+
+// The SQL dialect the parser follows. Generic accepts the syntax of the other dialects where
+// they do not conflict, and reserves only the words that all of them reserve
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Dialect {
+    #[default]
+    Generic,
+    PostgreSql,
+    MySql,
+    Sqlite,
+}
+
+impl Dialect {
+    // `name` quotes an identifier in MySQL and SQLite
+    pub fn supports_backtick_identifiers(&self) -> bool {
+        !matches!(self, Dialect::PostgreSql)
+    }
+
+    // In MySQL "text" is a string literal rather than an identifier
+    pub fn double_quoted_strings(&self) -> bool {
+        matches!(self, Dialect::MySql)
+    }
+
+    // In MySQL a backslash escapes the next character of a string literal
+    pub fn backslash_escapes(&self) -> bool {
+        matches!(self, Dialect::MySql)
+    }
+
+    // LIMIT offset, count
+    pub fn supports_limit_comma(&self) -> bool {
+        !matches!(self, Dialect::PostgreSql)
+    }
+
+    pub fn supports_offset_without_limit(&self) -> bool {
+        matches!(self, Dialect::Generic | Dialect::PostgreSql)
+    }
+
+    pub fn supports_ilike(&self) -> bool {
+        matches!(self, Dialect::Generic | Dialect::PostgreSql)
+    }
+
+    // expression::type casts. CAST(expression AS type) is accepted by every dialect
+    pub fn supports_double_colon_cast(&self) -> bool {
+        matches!(self, Dialect::Generic | Dialect::PostgreSql)
+    }
+
+    // SMALLSERIAL, SERIAL and BIGSERIAL column types. Elsewhere they are custom type names
+    pub fn supports_serial_types(&self) -> bool {
+        matches!(self, Dialect::Generic | Dialect::PostgreSql)
+    }
+
+    // The spellings of the column option that generates keys
+    pub fn auto_increment_keywords(&self) -> &'static [&'static str] {
+        match self {
+            Dialect::Generic => &["AUTO_INCREMENT", "AUTOINCREMENT"],
+            Dialect::PostgreSql => &[],
+            Dialect::MySql => &["AUTO_INCREMENT"],
+            Dialect::Sqlite => &["AUTOINCREMENT"],
+        }
+    }
+
+    pub fn is_reserved(&self, word: &str) -> bool {
+        keyword_kind(*self, word) == Some(KeywordKind::Reserved)
+    }
+
+    // Rewrites the quoting of the dialect into the form the grammar reads: '...' strings with
+    // doubled quotes and "..." identifiers. Only ASCII bytes are replaced by other ASCII bytes,
+    // so offsets into the result are offsets into the input. Characters inside quotes may be
+    // replaced, which is why values are read from the input rather than from the result
+    pub(crate) fn normalize<'a>(&self, sql: &'a str) -> Cow<'a, str> {
+        let mut bytes = sql.as_bytes().to_vec();
+        let mut index = 0;
+        while index < bytes.len() {
+            index = match bytes[index] {
+                b'-' if bytes.get(index + 1) == Some(&b'-') => {
+                    match bytes[index..].iter().position(|&b| b == b'\n') {
+                        Some(length) => index + length,
+                        None => bytes.len(),
+                    }
+                }
+                b'\'' => self.requote(&mut bytes, index, b'\''),
+                b'"' if self.double_quoted_strings() => self.requote(&mut bytes, index, b'\''),
+                b'"' => Dialect::Generic.requote(&mut bytes, index, b'"'),
+                b'`' if self.supports_backtick_identifiers() => {
+                    Dialect::Generic.requote(&mut bytes, index, b'"')
+                }
+                _ => index + 1,
+            };
+        }
+        if bytes == sql.as_bytes() {
+            Cow::Borrowed(sql)
+        } else {
+            Cow::Owned(String::from_utf8(bytes).expect("only ASCII bytes are replaced"))
+        }
+    }
+
+    // Rewrites the quoted token starting at `start` to use `quote`, and returns the offset
+    // after it. Doubled delimiters become doubled quotes; a quote inside the token or after
+    // a backslash escape is replaced by '_'
+    fn requote(&self, bytes: &mut [u8], start: usize, quote: u8) -> usize {
+        let delimiter = bytes[start];
+        bytes[start] = quote;
+        let mut index = start + 1;
+        while index < bytes.len() {
+            let byte = bytes[index];
+            if byte == delimiter {
+                bytes[index] = quote;
+                if bytes.get(index + 1) != Some(&delimiter) {
+                    return index + 1;
+                }
+                bytes[index + 1] = quote;
+                index += 2;
+            } else if byte == b'\\' && self.backslash_escapes() && index + 1 < bytes.len() {
+                if bytes[index + 1] == delimiter || bytes[index + 1] == quote {
+                    bytes[index + 1] = b'_';
+                }
+                index += 2;
+            } else {
+                if byte == quote {
+                    bytes[index] = b'_';
+                }
+                index += 1;
+            }
+        }
+        bytes.len()
+    }
+
+    // The value of a string literal as written in the input, without its quotes
+    pub(crate) fn string_value(&self, text: &str) -> String {
+        let Some(quote) = text.chars().next() else {
+            return String::new();
+        };
+        let body = &text[1..text.len() - 1];
+        if !self.backslash_escapes() {
+            return undouble(body, quote);
+        }
+        let mut value = String::with_capacity(body.len());
+        let mut chars = body.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => match chars.next() {
+                    Some('0') => value.push('\0'),
+                    Some('b') => value.push('\x08'),
+                    Some('n') => value.push('\n'),
+                    Some('r') => value.push('\r'),
+                    Some('t') => value.push('\t'),
+                    Some('Z') => value.push('\x1a'),
+                    // Kept escaped, as LIKE patterns rely on them
+                    Some(c @ ('%' | '_')) => {
+                        value.push('\\');
+                        value.push(c);
+                    }
+                    Some(c) => value.push(c),
+                    None => value.push('\\'),
+                },
+                // The first of two doubled quotes
+                c if c == quote => {
+                    chars.next();
+                    value.push(c);
+                }
+                c => value.push(c),
+            }
+        }
+        value
+    }
+}
+
+// The name of a quoted identifier, or None for a bare one
+pub(crate) fn unquote_identifier(text: &str) -> Option<String> {
+    let quote = text.chars().next().filter(|c| matches!(c, '"' | '`'))?;
+    let body = text.get(1..text.len() - 1)?;
+    Some(undouble(body, quote))
+}
+
+// The name of a bare identifier: NFC-normalized, which leaves ASCII unchanged
+pub(crate) fn normalize_identifier(text: &str) -> String {
+    if text.is_ascii() {
+        text.to_string()
+    } else {
+        text.nfc().collect()
+    }
+}
+
+// Replaces each doubled quote in the body of a quoted token with a single one
+fn undouble(body: &str, quote: char) -> String {
+    if !body.contains(quote) {
+        return body.to_string();
+    }
+    let single = quote.to_string();
+    body.replace(&single.repeat(2), &single)
+}
+
+impl fmt::Display for Dialect {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Dialect::Generic => "generic SQL",
+            Dialect::PostgreSql => "PostgreSQL",
+            Dialect::MySql => "MySQL",
+            Dialect::Sqlite => "SQLite",
+        })
+    }
+}
+
+impl FromStr for Dialect {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Ok(match name.to_lowercase().as_str() {
+            "generic" => Dialect::Generic,
+            "postgres" | "postgresql" => Dialect::PostgreSql,
+            "mysql" => Dialect::MySql,
+            "sqlite" => Dialect::Sqlite,
+            _ => return Err(format!("unknown dialect: {}", name)),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Dialect;
+    use crate::ast::{BinaryOperator, ColumnOption, DataType, Expression, Literal, Statement};
+    use crate::parser::{parse_expression_with_options, parse_sql_with, ParseOptions};
+
+    fn expression(dialect: Dialect, input: &str) -> Expression {
+        let options = ParseOptions {
+            dialect,
+            ..ParseOptions::default()
+        };
+        parse_expression_with_options(input, &options)
+            .unwrap_or_else(|error| panic!("{}: {}\n{}", dialect, input, error))
+    }
+
+    fn string(dialect: Dialect, input: &str) -> String {
+        match expression(dialect, input) {
+            Expression::Literal {
+                value: Literal::String(value),
+                ..
+            } => value,
+            other => panic!(
+                "{}: {} is not a string literal: {:?}",
+                dialect, input, other
+            ),
+        }
+    }
+
+    fn error(dialect: Dialect, sql: &str) -> String {
+        match parse_sql_with(dialect, sql) {
+            Ok(statement) => panic!("{}: {} parsed as {:?}", dialect, sql, statement),
+            Err(error) => error.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_normalize_keeps_offsets() {
+        let sql = "SELECT * FROM `a\"b` WHERE x = \"it's \\\"q\\\"\" AND y = 'é\\'' -- \"c";
+        let normalized = Dialect::MySql.normalize(sql);
+        assert_eq!(normalized.len(), sql.len());
+        assert_eq!(
+            normalized,
+            "SELECT * FROM \"a_b\" WHERE x = 'it_s \\_q\\_' AND y = 'é\\_' -- \"c"
+        );
+        // Input already in the generic form is not copied
+        let sql = "SELECT * FROM \"users\" WHERE name = 'O''Brien';";
+        assert!(matches!(
+            Dialect::PostgreSql.normalize(sql),
+            std::borrow::Cow::Borrowed(_)
+        ));
+    }
+
+    #[test]
+    fn test_identifier_quoting() {
+        for dialect in [Dialect::Generic, Dialect::MySql, Dialect::Sqlite] {
+            let statement = parse_sql_with(dialect, "SELECT * FROM `order ``items```;").unwrap();
+            assert!(
+                matches!(statement, Statement::Select { table, .. } if table == "order `items`")
+            );
+        }
+        let statement = parse_sql_with(Dialect::PostgreSql, "SELECT * FROM \"Users\";").unwrap();
+        assert!(matches!(statement, Statement::Select { table, .. } if table == "Users"));
+        assert!(parse_sql_with(Dialect::PostgreSql, "SELECT * FROM `users`;").is_err());
+
+        // In MySQL double quotes make a string, not an identifier
+        assert!(parse_sql_with(Dialect::MySql, "SELECT * FROM \"users\";").is_err());
+        assert_eq!(string(Dialect::MySql, "\"say \"\"hi\"\"\""), "say \"hi\"");
+        assert!(matches!(
+            expression(Dialect::PostgreSql, "\"say\""),
+            Expression::Column(column) if column == "say"
+        ));
+    }
+
+    #[test]
+    fn test_string_escapes() {
+        assert_eq!(string(Dialect::MySql, r"'it\'s'"), "it's");
+        assert_eq!(string(Dialect::MySql, r"'a\nb\tc\\d'"), "a\nb\tc\\d");
+        assert_eq!(string(Dialect::MySql, r"'100\%'"), r"100\%");
+        assert_eq!(string(Dialect::MySql, "'it''s'"), "it's");
+        // Elsewhere a backslash is an ordinary character
+        for dialect in [Dialect::Generic, Dialect::PostgreSql, Dialect::Sqlite] {
+            assert_eq!(string(dialect, r"'C:\'"), r"C:\");
+            assert_eq!(string(dialect, "'it''s'"), "it's");
+        }
+        // The escaped quote does not end the literal
+        let statement = parse_sql_with(
+            Dialect::MySql,
+            r"INSERT INTO t VALUES ('a\'b', 'c\\', ')');",
+        )
+        .unwrap();
+        assert!(matches!(
+            statement,
+            Statement::Insert { values, .. } if values == ["a'b", "c\\", ")"]
+        ));
+    }
+
+    #[test]
+    fn test_limit_forms() {
+        let limits = |dialect, sql| match parse_sql_with(dialect, sql) {
+            Ok(Statement::Select { limit, offset, .. }) => (
+                limit.map(|limit| limit.to_string()),
+                offset.map(|offset| offset.to_string()),
+            ),
+            other => panic!("{}: {} parsed as {:?}", dialect, sql, other),
+        };
+        for dialect in [Dialect::Generic, Dialect::MySql, Dialect::Sqlite] {
+            assert_eq!(
+                limits(dialect, "SELECT * FROM t LIMIT 20, 10;"),
+                (Some("10".into()), Some("20".into()))
+            );
+        }
+        for dialect in [
+            Dialect::Generic,
+            Dialect::PostgreSql,
+            Dialect::MySql,
+            Dialect::Sqlite,
+        ] {
+            assert_eq!(
+                limits(dialect, "SELECT * FROM t LIMIT 10 OFFSET 20;"),
+                (Some("10".into()), Some("20".into()))
+            );
+        }
+        assert_eq!(
+            limits(Dialect::PostgreSql, "SELECT * FROM t OFFSET 5;"),
+            (None, Some("5".into()))
+        );
+        assert!(error(Dialect::PostgreSql, "SELECT * FROM t LIMIT 20, 10;")
+            .contains("LIMIT offset, count is not supported by PostgreSQL"));
+        assert!(error(Dialect::MySql, "SELECT * FROM t OFFSET 5;")
+            .contains("OFFSET without LIMIT is not supported by MySQL"));
+        assert!(
+            error(Dialect::Generic, "SELECT * FROM t LIMIT 1, 2 OFFSET 3;")
+                .contains("OFFSET cannot follow LIMIT offset, count")
+        );
+    }
+
+    #[test]
+    fn test_like_and_ilike() {
+        for dialect in [
+            Dialect::Generic,
+            Dialect::PostgreSql,
+            Dialect::MySql,
+            Dialect::Sqlite,
+        ] {
+            assert!(matches!(
+                expression(dialect, "name LIKE 'a%' AND b"),
+                Expression::Binary {
+                    operator: BinaryOperator::And,
+                    left,
+                    ..
+                } if matches!(*left, Expression::Binary { operator: BinaryOperator::Like, .. })
+            ));
+        }
+        assert!(matches!(
+            expression(Dialect::PostgreSql, "name ILIKE 'a%'"),
+            Expression::Binary {
+                operator: BinaryOperator::ILike,
+                ..
+            }
+        ));
+        assert!(
+            error(Dialect::MySql, "SELECT * FROM t WHERE name ILIKE 'a%';")
+                .contains("ILIKE is not supported by MySQL")
+        );
+    }
+
+    #[test]
+    fn test_casts() {
+        let cast = expression(Dialect::PostgreSql, "-price::numeric(10, 2)");
+        let Expression::Unary { operand, .. } = cast else {
+            panic!("the cast binds tighter than the minus: {:?}", cast);
+        };
+        assert!(matches!(
+            *operand,
+            Expression::Cast {
+                data_type: DataType::Decimal {
+                    precision: Some(10),
+                    scale: Some(2)
+                },
+                ..
+            }
+        ));
+        for dialect in [
+            Dialect::Generic,
+            Dialect::PostgreSql,
+            Dialect::MySql,
+            Dialect::Sqlite,
+        ] {
+            assert_eq!(
+                expression(dialect, "CAST(id AS TEXT)"),
+                expression(Dialect::PostgreSql, "id::text")
+            );
+        }
+        assert!(
+            error(Dialect::Sqlite, "SELECT * FROM t WHERE id::text = '1';")
+                .contains("the :: cast is not supported by SQLite")
+        );
+    }
+
+    #[test]
+    fn test_auto_increment_and_serial() {
+        let options = |dialect, sql| match parse_sql_with(dialect, sql) {
+            Ok(Statement::CreateTable { columns, .. }) => columns
+                .into_iter()
+                .map(|column| (column.data_type, column.options))
+                .collect::<Vec<_>>(),
+            other => panic!("{}: {} parsed as {:?}", dialect, sql, other),
+        };
+        assert_eq!(
+            options(
+                Dialect::PostgreSql,
+                "CREATE TABLE t (id SERIAL PRIMARY KEY, n BIGSERIAL);"
+            ),
+            [
+                (DataType::Serial, vec![ColumnOption::PrimaryKey]),
+                (DataType::BigSerial, vec![])
+            ]
+        );
+        assert_eq!(
+            options(
+                Dialect::MySql,
+                "CREATE TABLE t (id INT NOT NULL AUTO_INCREMENT PRIMARY KEY);"
+            ),
+            [(
+                DataType::Integer,
+                vec![
+                    ColumnOption::NotNull,
+                    ColumnOption::AutoIncrement,
+                    ColumnOption::PrimaryKey
+                ]
+            )]
+        );
+        assert_eq!(
+            options(
+                Dialect::Sqlite,
+                "CREATE TABLE t (id INTEGER PRIMARY KEY AUTOINCREMENT);"
+            ),
+            [(
+                DataType::Integer,
+                vec![ColumnOption::PrimaryKey, ColumnOption::AutoIncrement]
+            )]
+        );
+        // SERIAL is only a type name in PostgreSQL
+        assert_eq!(
+            options(Dialect::MySql, "CREATE TABLE t (id SERIAL);"),
+            [(DataType::Custom("SERIAL".to_string()), vec![])]
+        );
+        assert!(error(
+            Dialect::PostgreSql,
+            "CREATE TABLE t (id INT AUTO_INCREMENT);"
+        )
+        .contains("AUTO_INCREMENT is not supported by PostgreSQL"));
+        assert!(
+            error(Dialect::MySql, "CREATE TABLE t (id INT AUTOINCREMENT);")
+                .contains("AUTOINCREMENT is not supported by MySQL")
+        );
+    }
+
+    #[test]
+    fn test_reserved_keywords() {
+        // KEY is reserved in MySQL but not in PostgreSQL; ORDER in both
+        assert!(parse_sql_with(Dialect::PostgreSql, "DELETE FROM t WHERE key = 1;").is_ok());
+        assert!(error(Dialect::MySql, "DELETE FROM t WHERE key = 1;")
+            .contains("key is a reserved keyword in MySQL; quote it to use it as an identifier"));
+        assert!(parse_sql_with(Dialect::MySql, "DELETE FROM t WHERE `key` = 1;").is_ok());
+        assert!(error(Dialect::PostgreSql, "SELECT * FROM order;")
+            .contains("order is a reserved keyword in PostgreSQL"));
+        assert!(parse_sql_with(Dialect::PostgreSql, "SELECT * FROM \"order\";").is_ok());
+        // Function names may be reserved words
+        assert!(parse_sql_with(
+            Dialect::MySql,
+            "INSERT INTO t VALUES ('1') ON DUPLICATE KEY UPDATE n = VALUES(n);"
+        )
+        .is_ok());
+        // The generic dialect reserves only the words that PostgreSQL, MySQL and SQLite all
+        // reserve: ORDER is one of them, OFFSET is reserved by PostgreSQL alone
+        assert!(error(Dialect::Generic, "SELECT * FROM order;")
+            .contains("order is a reserved keyword in generic SQL"));
+        assert!(parse_sql_with(Dialect::Generic, "SELECT * FROM offset;").is_ok());
+    }
+
+    #[test]
+    fn test_errors_show_the_input_as_written() {
+        let message = error(
+            Dialect::MySql,
+            "SELECT * FROM t WHERE a = \"x\" AND key = 'y';",
+        );
+        assert!(
+            message.contains("1 | SELECT * FROM t WHERE a = \"x\" AND key = 'y';"),
+            "{}",
+            message
+        );
+    }
+
+    #[test]
+    fn test_dialect_names() {
+        assert_eq!("postgres".parse(), Ok(Dialect::PostgreSql));
+        assert_eq!("MySQL".parse(), Ok(Dialect::MySql));
+        assert_eq!("sqlite".parse(), Ok(Dialect::Sqlite));
+        assert_eq!("generic".parse(), Ok(Dialect::Generic));
+        assert!("oracle".parse::<Dialect>().is_err());
+        assert_eq!(Dialect::PostgreSql.to_string(), "PostgreSQL");
+    }
+}
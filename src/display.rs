@@ -0,0 +1,711 @@
+use crate::ast::{
+    BinaryOperator, ColumnDefinition, ColumnOption, ConflictTarget, DataType, Expression, GroupBy,
+    GroupingElement, Ident, Literal, MergeAction, MergeClause, MergeSource, ObjectName, OnConflict,
+    OrderBy, OrderByItem, OrderDirection, Span, Statement, UnaryOperator,
+};
+use crate::keywords::is_keyword;
+use pest::unicode::{XID_CONTINUE, XID_START};
+use std::fmt;
+use unicode_normalization::is_nfc;
+
+// Bare names are read back in NFC, so others keep their spelling only when quoted
+fn is_bare_identifier(value: &str) -> bool {
+    let mut chars = value.chars();
+    matches!(chars.next(), Some(c) if XID_START(c) || c == '_')
+        && chars.all(XID_CONTINUE)
+        && is_nfc(value)
+}
+
+fn write_quoted_identifier(f: &mut fmt::Formatter<'_>, value: &str) -> fmt::Result {
+    write!(f, "\"{}\"", value.replace('"', "\"\""))
+}
+
+// Writes items separated by ", "
+struct List<'a, T>(&'a [T]);
+
+impl<T: fmt::Display> fmt::Display for List<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, item) in self.0.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            write!(f, "{}", item)?;
+        }
+        Ok(())
+    }
+}
+
+// A string literal with embedded quotes doubled
+pub(crate) struct QuotedString<'a>(pub(crate) &'a str);
+
+impl fmt::Display for QuotedString<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "'{}'", self.0.replace('\'', "''"))
+    }
+}
+
+// Writes "column = value" pairs separated by ", "
+fn write_assignments<'a, T: fmt::Display>(
+    f: &mut fmt::Formatter<'_>,
+    assignments: impl Iterator<Item = (&'a Ident, T)>,
+) -> fmt::Result {
+    for (i, (column, value)) in assignments.enumerate() {
+        if i > 0 {
+            f.write_str(", ")?;
+        }
+        write!(f, "{} = {}", column, value)?;
+    }
+    Ok(())
+}
+
+impl fmt::Display for Span {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+impl fmt::Display for Ident {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Keywords of any dialect are quoted so that they read back as identifiers. An empty
+        // name is written as "", which fails to parse (MySQL reads it as an empty string), so
+        // such an AST does not round-trip
+        if is_bare_identifier(&self.value) && !is_keyword(&self.value) {
+            f.write_str(&self.value)
+        } else {
+            write_quoted_identifier(f, &self.value)
+        }
+    }
+}
+
+impl fmt::Display for ObjectName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(schema) = &self.schema {
+            write!(f, "{}.", schema)?;
+        }
+        write!(f, "{}", self.name)
+    }
+}
+
+// The SELECT query without its terminating semicolon, as used in MERGE sources
+fn write_query(f: &mut fmt::Formatter<'_>, statement: &Statement) -> fmt::Result {
+    match statement {
+        Statement::Select {
+            table,
+            where_clause,
+            order_by,
+            group_by,
+            limit,
+            offset,
+            ..
+        } => {
+            write!(f, "SELECT * FROM {}", table)?;
+            if let Some(where_clause) = where_clause {
+                write!(f, " WHERE {}", where_clause)?;
+            }
+            if let Some(group_by) = group_by {
+                write!(f, " {}", group_by)?;
+            }
+            if let Some(order_by) = order_by {
+                write!(f, " {}", order_by)?;
+            }
+            if let Some(limit) = limit {
+                write!(f, " LIMIT {}", limit)?;
+            }
+            if let Some(offset) = offset {
+                write!(f, " OFFSET {}", offset)?;
+            }
+            Ok(())
+        }
+        other => {
+            let text = other.to_string();
+            f.write_str(text.strip_suffix(';').unwrap_or(&text))
+        }
+    }
+}
+
+impl fmt::Display for Statement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Statement::Select { .. } => write_query(f, self)?,
+            Statement::Insert {
+                table,
+                values,
+                on_conflict,
+                ..
+            } => {
+                let values: Vec<_> = values.iter().map(|value| QuotedString(value)).collect();
+                write!(f, "INSERT INTO {} VALUES ({})", table, List(&values))?;
+                if let Some(on_conflict) = on_conflict {
+                    write!(f, " {}", on_conflict)?;
+                }
+            }
+            Statement::Update {
+                table,
+                set,
+                where_clause,
+                ..
+            } => {
+                write!(f, "UPDATE {} SET ", table)?;
+                write_assignments(
+                    f,
+                    set.iter()
+                        .map(|(column, value)| (column, QuotedString(value))),
+                )?;
+                if let Some(where_clause) = where_clause {
+                    write!(f, " WHERE {}", where_clause)?;
+                }
+            }
+            Statement::Delete {
+                table,
+                where_clause,
+                ..
+            } => {
+                write!(f, "DELETE FROM {}", table)?;
+                if let Some(where_clause) = where_clause {
+                    write!(f, " WHERE {}", where_clause)?;
+                }
+            }
+            Statement::Merge {
+                table,
+                alias,
+                source,
+                on,
+                clauses,
+                ..
+            } => {
+                write!(f, "MERGE INTO {}", table)?;
+                if let Some(alias) = alias {
+                    write!(f, " AS {}", alias)?;
+                }
+                write!(f, " USING {} ON {}", source, on)?;
+                for clause in clauses {
+                    write!(f, " {}", clause)?;
+                }
+            }
+            // The prepared statement carries its own semicolon
+            Statement::Prepare {
+                name,
+                parameter_types,
+                statement,
+                ..
+            } => {
+                write!(f, "PREPARE {}", name)?;
+                if !parameter_types.is_empty() {
+                    write!(f, " ({})", List(parameter_types))?;
+                }
+                return write!(f, " AS {}", statement);
+            }
+            Statement::Execute {
+                name, parameters, ..
+            } => {
+                write!(f, "EXECUTE {}", name)?;
+                if !parameters.is_empty() {
+                    write!(f, " ({})", List(parameters))?;
+                }
+            }
+            Statement::Deallocate { name, .. } => match name {
+                Some(name) => write!(f, "DEALLOCATE PREPARE {}", name)?,
+                None => f.write_str("DEALLOCATE ALL")?,
+            },
+            Statement::CreateTable { table, columns, .. } => {
+                write!(f, "CREATE TABLE {} ({})", table, List(columns))?;
+            }
+        }
+        f.write_str(";")
+    }
+}
+
+// Function names are only quoted where a bare name would read as something else,
+// so that calls such as VALUES(column) stay as written. ROLLUP(...) and CUBE(...) would
+// read as grouping elements in GROUP BY
+pub(crate) struct FunctionName<'a>(pub(crate) &'a Ident);
+
+impl fmt::Display for FunctionName<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let is_reserved = ["NOT", "GROUPING", "ROLLUP", "CUBE"]
+            .iter()
+            .any(|keyword| keyword.eq_ignore_ascii_case(&self.0.value));
+        if is_bare_identifier(&self.0.value) && !is_reserved {
+            f.write_str(&self.0.value)
+        } else {
+            write_quoted_identifier(f, &self.0.value)
+        }
+    }
+}
+
+// Parenthesizes a binary operand only when its operator binds looser than the context requires
+struct Operand<'a> {
+    expression: &'a Expression,
+    min_precedence: u8,
+}
+
+impl fmt::Display for Operand<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.expression.precedence() {
+            Some(precedence) if precedence < self.min_precedence => {
+                write!(f, "({})", self.expression)
+            }
+            _ => write!(f, "{}", self.expression),
+        }
+    }
+}
+
+impl fmt::Display for Expression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expression::Literal { value, .. } => write!(f, "{}", value),
+            Expression::Column(column) => write!(f, "{}", column),
+            Expression::QualifiedColumn { table, column } => write!(f, "{}.{}", table, column),
+            Expression::Function { name, args, .. } => {
+                write!(f, "{}({})", FunctionName(name), List(args))
+            }
+            Expression::Grouping { args, .. } => write!(f, "GROUPING({})", List(args)),
+            Expression::Parameter { name, .. } => f.write_str(name),
+            // Operators are left-associative, so only the right operand needs parentheses
+            // at equal precedence
+            Expression::Binary {
+                left,
+                operator,
+                right,
+                ..
+            } => write!(
+                f,
+                "{} {} {}",
+                Operand {
+                    expression: left,
+                    min_precedence: operator.precedence(),
+                },
+                operator,
+                Operand {
+                    expression: right,
+                    min_precedence: operator.precedence() + 1,
+                }
+            ),
+            // Prefix operators bind tighter than any binary operator
+            Expression::Unary {
+                operator, operand, ..
+            } => {
+                let operand = Operand {
+                    expression: operand,
+                    min_precedence: u8::MAX,
+                }
+                .to_string();
+                match operator {
+                    UnaryOperator::Not => write!(f, "NOT {}", operand),
+                    // "--" would start a comment
+                    UnaryOperator::Minus if operand.starts_with('-') => write!(f, "- {}", operand),
+                    UnaryOperator::Minus => write!(f, "-{}", operand),
+                }
+            }
+            Expression::Cast {
+                expression,
+                data_type,
+                ..
+            } => write!(f, "CAST({} AS {})", expression, data_type),
+            Expression::InList {
+                expression,
+                list,
+                negated,
+                ..
+            } => {
+                let operand = Operand {
+                    expression,
+                    min_precedence: self.precedence().unwrap_or(u8::MAX),
+                };
+                let not = if *negated { "NOT " } else { "" };
+                write!(f, "{} {}IN ({})", operand, not, List(list))
+            }
+        }
+    }
+}
+
+impl fmt::Display for Literal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Literal::String(value) => write!(f, "{}", QuotedString(value)),
+            Literal::Number(value) => write!(f, "{}", value),
+            // Keep the decimal point so that the value reads back as a float
+            Literal::Float(value) => {
+                let text = value.to_string();
+                if text.contains('.') || !value.is_finite() {
+                    f.write_str(&text)
+                } else {
+                    write!(f, "{}.0", text)
+                }
+            }
+            Literal::Boolean(true) => f.write_str("TRUE"),
+            Literal::Boolean(false) => f.write_str("FALSE"),
+            Literal::Null => f.write_str("NULL"),
+        }
+    }
+}
+
+impl fmt::Display for BinaryOperator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            BinaryOperator::Equal => "=",
+            BinaryOperator::NotEqual => "<>",
+            BinaryOperator::LessThan => "<",
+            BinaryOperator::LessThanOrEqual => "<=",
+            BinaryOperator::GreaterThan => ">",
+            BinaryOperator::GreaterThanOrEqual => ">=",
+            BinaryOperator::Like => "LIKE",
+            BinaryOperator::ILike => "ILIKE",
+            BinaryOperator::And => "AND",
+            BinaryOperator::Or => "OR",
+            BinaryOperator::Add => "+",
+            BinaryOperator::Subtract => "-",
+            BinaryOperator::Multiply => "*",
+            BinaryOperator::Divide => "/",
+        })
+    }
+}
+
+impl fmt::Display for UnaryOperator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            UnaryOperator::Not => "NOT",
+            UnaryOperator::Minus => "-",
+        })
+    }
+}
+
+impl fmt::Display for OrderBy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "ORDER BY {}", List(&self.items))
+    }
+}
+
+impl fmt::Display for OrderByItem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.direction {
+            OrderDirection::Asc => write!(f, "{}", self.column),
+            OrderDirection::Desc => write!(f, "{} DESC", self.column),
+        }
+    }
+}
+
+impl fmt::Display for OrderDirection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            OrderDirection::Asc => "ASC",
+            OrderDirection::Desc => "DESC",
+        })
+    }
+}
+
+impl fmt::Display for GroupBy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "GROUP BY {}", List(&self.items))
+    }
+}
+
+impl fmt::Display for GroupingElement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GroupingElement::Expression(expression) => write!(f, "{}", expression),
+            GroupingElement::Set(expressions) => write!(f, "({})", List(expressions)),
+            GroupingElement::Rollup(elements) => write!(f, "ROLLUP({})", List(elements)),
+            GroupingElement::Cube(elements) => write!(f, "CUBE({})", List(elements)),
+            GroupingElement::GroupingSets(elements) => {
+                write!(f, "GROUPING SETS ({})", List(elements))
+            }
+        }
+    }
+}
+
+impl fmt::Display for OnConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OnConflict::DoNothing { target } => {
+                f.write_str("ON CONFLICT")?;
+                if let Some(target) = target {
+                    write!(f, " {}", target)?;
+                }
+                f.write_str(" DO NOTHING")
+            }
+            OnConflict::DoUpdate {
+                target,
+                set,
+                where_clause,
+            } => {
+                write!(f, "ON CONFLICT {} DO UPDATE SET ", target)?;
+                write_assignments(f, set.iter().map(|(column, value)| (column, value)))?;
+                if let Some(where_clause) = where_clause {
+                    write!(f, " WHERE {}", where_clause)?;
+                }
+                Ok(())
+            }
+            OnConflict::DuplicateKeyUpdate { set } => {
+                f.write_str("ON DUPLICATE KEY UPDATE ")?;
+                write_assignments(f, set.iter().map(|(column, value)| (column, value)))
+            }
+        }
+    }
+}
+
+impl fmt::Display for ConflictTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConflictTarget::Columns(columns) => write!(f, "({})", List(columns)),
+            ConflictTarget::Constraint(name) => write!(f, "ON CONSTRAINT {}", name),
+        }
+    }
+}
+
+impl fmt::Display for MergeSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let alias = match self {
+            MergeSource::Table { name, alias } => {
+                write!(f, "{}", name)?;
+                alias
+            }
+            MergeSource::Subquery { query, alias } => {
+                f.write_str("(")?;
+                write_query(f, query)?;
+                f.write_str(")")?;
+                alias
+            }
+        };
+        if let Some(alias) = alias {
+            write!(f, " AS {}", alias)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for MergeClause {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(if self.matched {
+            "WHEN MATCHED"
+        } else {
+            "WHEN NOT MATCHED"
+        })?;
+        if let Some(condition) = &self.condition {
+            write!(f, " AND {}", condition)?;
+        }
+        write!(f, " THEN {}", self.action)
+    }
+}
+
+impl fmt::Display for MergeAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MergeAction::Update { set } => {
+                f.write_str("UPDATE SET ")?;
+                write_assignments(f, set.iter().map(|(column, value)| (column, value)))
+            }
+            MergeAction::Delete => f.write_str("DELETE"),
+            MergeAction::Insert { columns, values } => {
+                f.write_str("INSERT")?;
+                if !columns.is_empty() {
+                    write!(f, " ({})", List(columns))?;
+                }
+                write!(f, " VALUES ({})", List(values))
+            }
+        }
+    }
+}
+
+impl fmt::Display for DataType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (name, length) = match self {
+            DataType::SmallInt => ("SMALLINT", None),
+            DataType::Integer => ("INTEGER", None),
+            DataType::BigInt => ("BIGINT", None),
+            DataType::Real => ("REAL", None),
+            DataType::DoublePrecision => ("DOUBLE PRECISION", None),
+            DataType::Float(length) => ("FLOAT", *length),
+            DataType::Decimal { precision, scale } => {
+                f.write_str("DECIMAL")?;
+                return match (precision, scale) {
+                    (Some(precision), Some(scale)) => write!(f, "({}, {})", precision, scale),
+                    (Some(precision), None) => write!(f, "({})", precision),
+                    _ => Ok(()),
+                };
+            }
+            DataType::Char(length) => ("CHAR", *length),
+            DataType::Varchar(length) => ("VARCHAR", *length),
+            DataType::Text => ("TEXT", None),
+            DataType::Boolean => ("BOOLEAN", None),
+            DataType::Date => ("DATE", None),
+            DataType::Time => ("TIME", None),
+            DataType::Timestamp => ("TIMESTAMP", None),
+            DataType::SmallSerial => ("SMALLSERIAL", None),
+            DataType::Serial => ("SERIAL", None),
+            DataType::BigSerial => ("BIGSERIAL", None),
+            DataType::Custom(name) => (name.as_str(), None),
+        };
+        f.write_str(name)?;
+        if let Some(length) = length {
+            write!(f, "({})", length)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for ColumnDefinition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.name, self.data_type)?;
+        for option in &self.options {
+            write!(f, " {}", option)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for ColumnOption {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ColumnOption::NotNull => f.write_str("NOT NULL"),
+            ColumnOption::Null => f.write_str("NULL"),
+            ColumnOption::PrimaryKey => f.write_str("PRIMARY KEY"),
+            ColumnOption::Unique => f.write_str("UNIQUE"),
+            ColumnOption::Default(expression) => write!(f, "DEFAULT {}", expression),
+            ColumnOption::AutoIncrement => f.write_str("AUTO_INCREMENT"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ast::{Expression, Ident, Literal, Span, Statement, UnaryOperator};
+    use crate::corpus;
+    use crate::parser::{parse_data_type, parse_expression, parse_sql};
+
+    fn assert_statement_round_trip(statement: &Statement) {
+        let rendered = statement.to_string();
+        let reparsed = parse_sql(&rendered)
+            .unwrap_or_else(|error| panic!("failed to re-parse {}\n{}", rendered, error));
+        assert_eq!(&reparsed, statement, "{}", rendered);
+        assert_eq!(reparsed.to_string(), rendered);
+    }
+
+    fn assert_expression_round_trip(expression: &Expression) {
+        let rendered = expression.to_string();
+        let reparsed = parse_expression(&rendered)
+            .unwrap_or_else(|error| panic!("failed to re-parse {}\n{}", rendered, error));
+        assert_eq!(&reparsed, expression, "{}", rendered);
+        assert_eq!(reparsed.to_string(), rendered);
+    }
+
+    #[test]
+    fn test_round_trip_statement_corpus() {
+        for statement in corpus::statements() {
+            assert_statement_round_trip(&statement);
+        }
+    }
+
+    #[test]
+    fn test_round_trip_expression_corpus() {
+        for input in corpus::expressions() {
+            assert_expression_round_trip(&parse_expression(input).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_parentheses_only_where_precedence_requires() {
+        let cases = [
+            ("a + b * c", "a + b * c"),
+            ("(a + b) * c", "(a + b) * c"),
+            ("((a * b)) + c", "a * b + c"),
+            ("a - (b - c)", "a - (b - c)"),
+            ("(a - b) - c", "a - b - c"),
+            ("(a OR b) AND NOT (c = d)", "(a OR b) AND NOT (c = d)"),
+            ("a OR (b AND c)", "a OR b AND c"),
+            ("- (1 + 2)", "-(1 + 2)"),
+            ("- -1", "- -1"),
+            ("f((a), (b + c) * 2)", "f(a, (b + c) * 2)"),
+        ];
+        for (input, expected) in cases {
+            let expression = parse_expression(input).unwrap();
+            assert_eq!(expression.to_string(), expected, "{}", input);
+            assert_expression_round_trip(&expression);
+        }
+    }
+
+    #[test]
+    fn test_quote_identifiers_and_escape_strings() {
+        let statement = parse_sql(
+            "UPDATE \"order items\" SET \"select\" = 'it''s', note = '\"' WHERE \"Key\" = 'a''''b';",
+        )
+        .unwrap();
+        assert_eq!(
+            statement.to_string(),
+            "UPDATE \"order items\" SET \"select\" = 'it''s', note = '\"' WHERE \"Key\" = 'a''''b';"
+        );
+        assert_statement_round_trip(&statement);
+
+        let column = Expression::Column(Ident::new("say \"hi\""));
+        assert_eq!(column.to_string(), "\"say \"\"hi\"\"\"");
+        assert_expression_round_trip(&column);
+
+        // Unicode names are bare unless they would change under NFC
+        for (name, expected) in [
+            ("顧客", "顧客"),
+            ("年齢_2", "年齢_2"),
+            ("cafe\u{301}", "\"cafe\u{301}\""),
+            ("顧客、注文", "\"顧客、注文\""),
+        ] {
+            let column = Expression::Column(Ident::new(name));
+            assert_eq!(column.to_string(), expected);
+            assert_expression_round_trip(&column);
+        }
+    }
+
+    #[test]
+    fn test_render_constructed_ast() {
+        // A float without a fractional part keeps its decimal point
+        let float = Expression::Literal {
+            value: Literal::Float(2.0),
+            span: Span::default(),
+        };
+        assert_eq!(float.to_string(), "2.0");
+        assert_expression_round_trip(&float);
+
+        let negated = Expression::Unary {
+            operator: UnaryOperator::Minus,
+            operand: Box::new(Expression::Unary {
+                operator: UnaryOperator::Minus,
+                operand: Box::new(Expression::Column("x".into())),
+                span: Span::default(),
+            }),
+            span: Span::default(),
+        };
+        assert_eq!(negated.to_string(), "- -x");
+    }
+
+    #[test]
+    fn test_empty_identifier_does_not_round_trip() {
+        let column = Expression::Column(Ident::new(""));
+        assert_eq!(column.to_string(), "\"\"");
+        assert!(parse_expression(&column.to_string()).is_err());
+        let sql = format!("SELECT * FROM t WHERE {} = 1;", column);
+        assert_eq!(
+            crate::parser::parse_sql_with(crate::Dialect::MySql, &sql)
+                .unwrap()
+                .to_string(),
+            "SELECT * FROM t WHERE '' = 1;"
+        );
+    }
+
+    #[test]
+    fn test_render_data_types() {
+        for input in [
+            "INTEGER",
+            "DOUBLE PRECISION",
+            "VARCHAR(20)",
+            "DECIMAL(10, 2)",
+            "FLOAT",
+            "uuid",
+        ] {
+            let data_type = parse_data_type(input).unwrap();
+            assert_eq!(data_type.to_string(), input);
+        }
+        assert_eq!(
+            parse_data_type("character varying(5)").unwrap().to_string(),
+            "VARCHAR(5)"
+        );
+        assert_eq!(parse_data_type("serial8").unwrap().to_string(), "BIGSERIAL");
+    }
+}
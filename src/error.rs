@@ -0,0 +1,341 @@
+use crate::parser::Rule;
+use pest::error::{ErrorVariant, InputLocation, LineColLocation};
+use pest::{Position, Span};
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Location {
+    // 入力先頭からのバイトオフセット
+    pub offset: usize,
+    // 1始まりの行番号と列番号
+    pub line: usize,
+    pub column: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "type"))]
+pub enum ParseError {
+    // 文法に合わない入力
+    Syntax {
+        location: Location,
+        expected: Vec<String>,
+        source_line: String,
+    },
+    // 文法上は正しいが値として解釈できない入力（範囲外の数値など）
+    Semantic {
+        location: Location,
+        message: String,
+        source_line: String,
+    },
+}
+
+impl ParseError {
+    pub fn semantic(message: impl Into<String>, span: Span) -> Self {
+        let start = span.start_pos();
+        let (line, column) = start.line_col();
+        ParseError::Semantic {
+            location: Location {
+                offset: start.pos(),
+                line,
+                column,
+            },
+            message: message.into(),
+            source_line: trim_line_ending(start.line_of()).to_string(),
+        }
+    }
+
+    pub fn location(&self) -> Location {
+        match self {
+            ParseError::Syntax { location, .. } | ParseError::Semantic { location, .. } => {
+                *location
+            }
+        }
+    }
+
+    pub fn expected(&self) -> &[String] {
+        match self {
+            ParseError::Syntax { expected, .. } => expected,
+            ParseError::Semantic { .. } => &[],
+        }
+    }
+
+    // 入力の一部（input[base..]）を解析して得たエラーを、入力全体での位置に移す
+    pub(crate) fn relocate(self, input: &str, base: usize) -> Self {
+        let offset = (base + self.location().offset).min(input.len());
+        let position = Position::new(input, offset).unwrap_or_else(|| Position::from_start(input));
+        let (line, column) = position.line_col();
+        let location = Location {
+            offset,
+            line,
+            column,
+        };
+        let source_line = trim_line_ending(position.line_of()).to_string();
+        match self {
+            ParseError::Syntax { expected, .. } => ParseError::Syntax {
+                location,
+                expected,
+                source_line,
+            },
+            ParseError::Semantic { message, .. } => ParseError::Semantic {
+                location,
+                message,
+                source_line,
+            },
+        }
+    }
+
+    // 長い入力の途中から切り出した断片で得たエラーを、入力全体での位置に移す。
+    // 断片の1行目では列も columns 文字ずらし、同じ行にある断片より前の部分は空白で表す
+    pub(crate) fn shift(self, offset: usize, lines: usize, columns: usize) -> Self {
+        let mut location = self.location();
+        let first_line = location.line == 1;
+        location.offset += offset;
+        location.line += lines;
+        if first_line {
+            location.column += columns;
+        }
+        let source_line = if first_line {
+            format!("{}{}", " ".repeat(columns), self.source_line())
+        } else {
+            self.source_line().to_string()
+        };
+        match self {
+            ParseError::Syntax { expected, .. } => ParseError::Syntax {
+                location,
+                expected,
+                source_line,
+            },
+            ParseError::Semantic { message, .. } => ParseError::Semantic {
+                location,
+                message,
+                source_line,
+            },
+        }
+    }
+
+    fn source_line(&self) -> &str {
+        match self {
+            ParseError::Syntax { source_line, .. } | ParseError::Semantic { source_line, .. } => {
+                source_line
+            }
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let location = self.location();
+        match self {
+            ParseError::Syntax { expected, .. } if expected.is_empty() => write!(
+                f,
+                "syntax error at line {}, column {}",
+                location.line, location.column
+            )?,
+            ParseError::Syntax { expected, .. } => write!(
+                f,
+                "syntax error at line {}, column {}: expected {}",
+                location.line,
+                location.column,
+                expected.join(", ")
+            )?,
+            ParseError::Semantic { message, .. } => write!(
+                f,
+                "error at line {}, column {}: {}",
+                location.line, location.column, message
+            )?,
+        }
+
+        // ソース行を表示し、エラー位置をキャレットで示す
+        let source_line = self.source_line();
+        let gutter = " ".repeat(location.line.to_string().len());
+        let caret_indent: String = source_line
+            .chars()
+            .take(location.column.saturating_sub(1))
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect();
+        write!(
+            f,
+            "\n{gutter} |\n{} | {}\n{gutter} | {}^",
+            location.line, source_line, caret_indent
+        )
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+impl From<pest::error::Error<Rule>> for ParseError {
+    fn from(error: pest::error::Error<Rule>) -> Self {
+        let offset = match error.location {
+            InputLocation::Pos(pos) => pos,
+            InputLocation::Span((start, _)) => start,
+        };
+        let (line, column) = match error.line_col {
+            LineColLocation::Pos(line_col) => line_col,
+            LineColLocation::Span(line_col, _) => line_col,
+        };
+        let location = Location {
+            offset,
+            line,
+            column,
+        };
+        let source_line = trim_line_ending(error.line()).to_string();
+
+        match error.variant {
+            ErrorVariant::ParsingError { positives, .. } => ParseError::Syntax {
+                location,
+                expected: positives.iter().fold(Vec::new(), |mut expected, rule| {
+                    if let Some(token) = token(*rule) {
+                        if !expected.contains(&token) {
+                            expected.push(token);
+                        }
+                    }
+                    expected
+                }),
+                source_line,
+            },
+            ErrorVariant::CustomError { message } => ParseError::Semantic {
+                location,
+                message,
+                source_line,
+            },
+        }
+    }
+}
+
+// 期待していた規則を利用者向けの字句で表す。キーワードの規則は名前がそのまま字句になる。
+// in_list や cast_suffix のような補助の規則は、ほかの候補と重なるか内部の都合なので出さない
+fn token(rule: Rule) -> Option<String> {
+    let token = match rule {
+        Rule::EOI => "end of input",
+        Rule::statement
+        | Rule::select_statement
+        | Rule::insert_statement
+        | Rule::update_statement
+        | Rule::delete_statement
+        | Rule::merge_statement
+        | Rule::create_table_statement => "statement",
+        Rule::expression
+        | Rule::expression_list
+        | Rule::operand
+        | Rule::primary_expression
+        | Rule::grouping_element
+        | Rule::grouping_set_item => "expression",
+        Rule::identifier
+        | Rule::quoted_identifier
+        | Rule::qualified_identifier
+        | Rule::object_name
+        | Rule::identifier_list
+        | Rule::column_definition => "identifier",
+        Rule::assignment | Rule::expression_assignment => "assignment",
+        Rule::data_type | Rule::data_type_name => "data type",
+        Rule::string_literal | Rule::value_list => "string",
+        Rule::number_literal | Rule::float_literal | Rule::row_count => "number",
+        Rule::boolean_literal => "boolean",
+        Rule::null_literal | Rule::column_null => "NULL",
+        Rule::parameter => "parameter",
+        Rule::data_type_modifiers | Rule::grouping_set => "(",
+        Rule::semicolon => ";",
+        Rule::EQUAL => "=",
+        Rule::NOT_EQUAL => "<>",
+        Rule::LESS_THAN_OR_EQUAL => "<=",
+        Rule::LESS_THAN => "<",
+        Rule::GREATER_THAN_OR_EQUAL => ">=",
+        Rule::GREATER_THAN => ">",
+        Rule::ADD => "+",
+        Rule::SUBTRACT | Rule::MINUS => "-",
+        Rule::MULTIPLY => "*",
+        Rule::DIVIDE => "/",
+        Rule::column_not_null => "NOT",
+        Rule::column_primary_key => "PRIMARY",
+        Rule::column_unique => "UNIQUE",
+        Rule::column_default => "DEFAULT",
+        Rule::column_auto_increment => "AUTO_INCREMENT",
+        Rule::where_clause => "WHERE",
+        Rule::group_by_clause => "GROUP",
+        Rule::order_by_clause => "ORDER",
+        Rule::limit_clause => "LIMIT",
+        Rule::offset_clause => "OFFSET",
+        Rule::rollup => "ROLLUP",
+        Rule::cube => "CUBE",
+        Rule::grouping_sets | Rule::grouping_function => "GROUPING",
+        Rule::cast_function => "CAST",
+        Rule::on_conflict_clause | Rule::on_duplicate_key_clause => "ON",
+        Rule::merge_when_clause => "WHEN",
+        _ => {
+            let name = format!("{:?}", rule);
+            let keyword = name.chars().all(|c| c.is_ascii_uppercase() || c == '_');
+            return keyword.then_some(name);
+        }
+    };
+    Some(token.to_string())
+}
+
+fn trim_line_ending(line: &str) -> &str {
+    line.trim_end_matches(['\r', '\n'])
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::parse_sql;
+
+    #[test]
+    fn test_syntax_error_location_and_expected_tokens() {
+        let error = parse_sql("SELECT * FROM ;").unwrap_err();
+        let location = error.location();
+        assert_eq!(
+            (location.offset, location.line, location.column),
+            (14, 1, 15)
+        );
+        assert_eq!(error.expected(), ["identifier"]);
+    }
+
+    #[test]
+    fn test_expected_tokens_are_user_facing() {
+        let error = parse_sql("SELECT * FROM t WHERE a").unwrap_err();
+        assert_eq!(
+            error.expected(),
+            [
+                "=", "<>", "<=", "<", ">=", ">", "+", "-", "*", "/", ";", "AND", "OR", "ORDER",
+                "GROUP", "LIMIT", "OFFSET", "LIKE", "ILIKE"
+            ]
+        );
+        let error = parse_sql("SELECT * FROM t WHERE a = ").unwrap_err();
+        assert_eq!(error.expected(), ["expression", "-", "NOT"]);
+        let error = parse_sql("SELECT * FROM t WHERE a::").unwrap_err();
+        assert_eq!(error.expected(), ["data type"]);
+        let error = parse_sql("CREATE TABLE t (a INT").unwrap_err();
+        assert_eq!(
+            error.expected(),
+            [
+                "AUTO_INCREMENT",
+                "(",
+                "NOT",
+                "NULL",
+                "PRIMARY",
+                "UNIQUE",
+                "DEFAULT"
+            ]
+        );
+        assert_eq!(parse_sql("").unwrap_err().expected(), ["statement"]);
+    }
+
+    #[test]
+    fn test_syntax_error_display_with_caret() {
+        let error = parse_sql("SELECT *\nFROM ;").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "syntax error at line 2, column 6: expected identifier\n  |\n2 | FROM ;\n  |      ^"
+        );
+    }
+
+    #[test]
+    fn test_semantic_error_display() {
+        let error = parse_sql("SELECT * FROM users LIMIT 99999999999999999999;").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "error at line 1, column 27: LIMIT value 99999999999999999999 is out of range\n  |\n1 | SELECT * FROM users LIMIT 99999999999999999999;\n  |                           ^"
+        );
+    }
+}
@@ -1,188 +1,21 @@
-use crate::ast::{BinaryOperator, Expression, Literal, UnaryOperator};
-use pest::{iterators::Pair, Parser};
+// Expressions are parsed by the same core as statements; this module keeps the
+// expression-only entry points at their original path
+use crate::ast::Expression;
+use crate::error::ParseError;
+use crate::parser::{Context, LineIndex, Rule};
+use pest::iterators::Pair;
 
-#[derive(pest_derive::Parser)]
-#[grammar = "sql.pest"]
-pub struct ExpressionParser;
+pub use crate::parser::{parse_expression, parse_expression_with_options};
 
-pub fn parse_expression(expression_str: &str) -> Result<Expression, Box<pest::error::Error<Rule>>> {
-    let pairs = ExpressionParser::parse(Rule::expression, expression_str)?;
-    let expr_pair = pairs.into_iter().next().unwrap();
-    Ok(build_expression(expr_pair))
-}
-
-pub fn build_expression(pair: Pair<Rule>) -> Expression {
-    match pair.as_rule() {
-        Rule::expression => {
-            let inner = pair.into_inner().next().unwrap();
-            build_expression(inner)
-        }
-        Rule::or_expression => {
-            let mut inner = pair.into_inner();
-            let mut expr = build_expression(inner.next().unwrap());
-
-            while let Some(keyword) = inner.next() {
-                if keyword.as_rule() == Rule::OR {
-                    let right = build_expression(inner.next().unwrap());
-                    expr = Expression::Binary {
-                        left: Box::new(expr),
-                        operator: BinaryOperator::Or,
-                        right: Box::new(right),
-                    };
-                }
-            }
-            expr
-        }
-        Rule::and_expression => {
-            let mut inner = pair.into_inner();
-            let mut expr = build_expression(inner.next().unwrap());
-
-            while let Some(keyword) = inner.next() {
-                if keyword.as_rule() == Rule::AND {
-                    let right = build_expression(inner.next().unwrap());
-                    expr = Expression::Binary {
-                        left: Box::new(expr),
-                        operator: BinaryOperator::And,
-                        right: Box::new(right),
-                    };
-                }
-            }
-            expr
-        }
-        Rule::equality_expression => {
-            let mut inner = pair.into_inner();
-            let mut expr = build_expression(inner.next().unwrap());
-
-            while let Some(op_pair) = inner.next() {
-                let operator = match op_pair.as_rule() {
-                    Rule::EQUAL => BinaryOperator::Equal,
-                    Rule::NOT_EQUAL => BinaryOperator::NotEqual,
-                    _ => unreachable!("Unexpected operator rule: {:?}", op_pair.as_rule()),
-                };
-                let right = build_expression(inner.next().unwrap());
-                expr = Expression::Binary {
-                    left: Box::new(expr),
-                    operator,
-                    right: Box::new(right),
-                };
-            }
-            expr
-        }
-        Rule::comparison_expression => {
-            let mut inner = pair.into_inner();
-            let mut expr = build_expression(inner.next().unwrap());
-
-            while let Some(op_pair) = inner.next() {
-                let operator = match op_pair.as_rule() {
-                    Rule::LESS_THAN => BinaryOperator::LessThan,
-                    Rule::LESS_THAN_OR_EQUAL => BinaryOperator::LessThanOrEqual,
-                    Rule::GREATER_THAN => BinaryOperator::GreaterThan,
-                    Rule::GREATER_THAN_OR_EQUAL => BinaryOperator::GreaterThanOrEqual,
-                    _ => unreachable!("Unexpected operator rule: {:?}", op_pair.as_rule()),
-                };
-                let right = build_expression(inner.next().unwrap());
-                expr = Expression::Binary {
-                    left: Box::new(expr),
-                    operator,
-                    right: Box::new(right),
-                };
-            }
-            expr
-        }
-        Rule::additive_expression => {
-            let mut inner = pair.into_inner();
-            let mut expr = build_expression(inner.next().unwrap());
-
-            while let Some(op_pair) = inner.next() {
-                let operator = match op_pair.as_rule() {
-                    Rule::ADD => BinaryOperator::Add,
-                    Rule::SUBTRACT => BinaryOperator::Subtract,
-                    _ => unreachable!("Unexpected operator rule: {:?}", op_pair.as_rule()),
-                };
-                let right = build_expression(inner.next().unwrap());
-                expr = Expression::Binary {
-                    left: Box::new(expr),
-                    operator,
-                    right: Box::new(right),
-                };
-            }
-            expr
-        }
-        Rule::multiplicative_expression => {
-            let mut inner = pair.into_inner();
-            let mut expr = build_expression(inner.next().unwrap());
-
-            while let Some(op_pair) = inner.next() {
-                let operator = match op_pair.as_rule() {
-                    Rule::MULTIPLY => BinaryOperator::Multiply,
-                    Rule::DIVIDE => BinaryOperator::Divide,
-                    _ => unreachable!("Unexpected operator rule: {:?}", op_pair.as_rule()),
-                };
-                let right = build_expression(inner.next().unwrap());
-                expr = Expression::Binary {
-                    left: Box::new(expr),
-                    operator,
-                    right: Box::new(right),
-                };
-            }
-            expr
-        }
-        Rule::unary_expression => {
-            let mut inner = pair.into_inner();
-            let first = inner.next().unwrap();
-
-            match first.as_rule() {
-                Rule::NOT => {
-                    let operand = build_expression(inner.next().unwrap());
-                    Expression::Unary {
-                        operator: UnaryOperator::Not,
-                        operand: Box::new(operand),
-                    }
-                }
-                Rule::MINUS => {
-                    let operand = build_expression(inner.next().unwrap());
-                    Expression::Unary {
-                        operator: UnaryOperator::Minus,
-                        operand: Box::new(operand),
-                    }
-                }
-                _ => build_expression(first),
-            }
-        }
-        Rule::primary_expression => {
-            let inner = pair.into_inner().next().unwrap();
-            match inner.as_rule() {
-                Rule::expression => build_expression(inner),
-                _ => build_expression(inner),
-            }
-        }
-        Rule::string_literal => {
-            let content = pair.as_str();
-            let trimmed = content.trim_matches('\'');
-            Expression::Literal(Literal::String(trimmed.to_string()))
-        }
-        Rule::number_literal => {
-            let num: i64 = pair.as_str().parse().unwrap();
-            Expression::Literal(Literal::Number(num))
-        }
-        Rule::float_literal => {
-            let num: f64 = pair.as_str().parse().unwrap();
-            Expression::Literal(Literal::Float(num))
-        }
-        Rule::boolean_literal => {
-            let is_true = pair.as_str().to_uppercase() == "TRUE";
-            Expression::Literal(Literal::Boolean(is_true))
-        }
-        Rule::null_literal => Expression::Literal(Literal::Null),
-        Rule::identifier => Expression::Column(pair.as_str().to_string()),
-        _ => unreachable!("Unexpected rule: {:?}", pair.as_rule()),
-    }
+pub fn build_expression(pair: Pair<Rule>) -> Result<Expression, ParseError> {
+    let lines = LineIndex::new(pair.as_span().get_input());
+    crate::pratt::build_expression(Context::of(&pair, &lines), pair)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::ast::{BinaryOperator, Expression, Literal, UnaryOperator};
+    use crate::ast::{BinaryOperator, Expression, Literal, Span, UnaryOperator};
 
     #[test]
     fn test_parse_string_literal() {
@@ -191,7 +24,10 @@ mod tests {
         assert!(result.is_ok());
         assert_eq!(
             result.unwrap(),
-            Expression::Literal(Literal::String("hello".to_string()))
+            Expression::Literal {
+                value: Literal::String("hello".to_string()),
+                span: Span::default(),
+            }
         );
     }
 
@@ -200,7 +36,13 @@ mod tests {
         let expr = "42";
         let result = parse_expression(expr);
         assert!(result.is_ok());
-        assert_eq!(result.unwrap(), Expression::Literal(Literal::Number(42)));
+        assert_eq!(
+            result.unwrap(),
+            Expression::Literal {
+                value: Literal::Number(42),
+                span: Span::default(),
+            }
+        );
     }
 
     #[test]
@@ -211,7 +53,10 @@ mod tests {
         let expected = 314_f64 / 100.0;
         assert_eq!(
             result.unwrap(),
-            Expression::Literal(Literal::Float(expected))
+            Expression::Literal {
+                value: Literal::Float(expected),
+                span: Span::default(),
+            }
         );
     }
 
@@ -220,7 +65,13 @@ mod tests {
         let expr = "TRUE";
         let result = parse_expression(expr);
         assert!(result.is_ok());
-        assert_eq!(result.unwrap(), Expression::Literal(Literal::Boolean(true)));
+        assert_eq!(
+            result.unwrap(),
+            Expression::Literal {
+                value: Literal::Boolean(true),
+                span: Span::default(),
+            }
+        );
     }
 
     #[test]
@@ -230,7 +81,10 @@ mod tests {
         assert!(result.is_ok());
         assert_eq!(
             result.unwrap(),
-            Expression::Literal(Literal::Boolean(false))
+            Expression::Literal {
+                value: Literal::Boolean(false),
+                span: Span::default(),
+            }
         );
     }
 
@@ -239,7 +93,13 @@ mod tests {
         let expr = "NULL";
         let result = parse_expression(expr);
         assert!(result.is_ok());
-        assert_eq!(result.unwrap(), Expression::Literal(Literal::Null));
+        assert_eq!(
+            result.unwrap(),
+            Expression::Literal {
+                value: Literal::Null,
+                span: Span::default(),
+            }
+        );
     }
 
     #[test]
@@ -247,7 +107,7 @@ mod tests {
         let expr = "name";
         let result = parse_expression(expr);
         assert!(result.is_ok());
-        assert_eq!(result.unwrap(), Expression::Column("name".to_string()));
+        assert_eq!(result.unwrap(), Expression::Column("name".into()));
     }
 
     #[test]
@@ -258,9 +118,13 @@ mod tests {
         assert_eq!(
             result.unwrap(),
             Expression::Binary {
-                left: Box::new(Expression::Column("name".to_string())),
+                left: Box::new(Expression::Column("name".into())),
                 operator: BinaryOperator::Equal,
-                right: Box::new(Expression::Literal(Literal::String("John".to_string())))
+                right: Box::new(Expression::Literal {
+                    value: Literal::String("John".to_string()),
+                    span: Span::default(),
+                }),
+                span: Span::default(),
             }
         );
     }
@@ -273,9 +137,13 @@ mod tests {
         assert_eq!(
             result.unwrap(),
             Expression::Binary {
-                left: Box::new(Expression::Column("age".to_string())),
+                left: Box::new(Expression::Column("age".into())),
                 operator: BinaryOperator::NotEqual,
-                right: Box::new(Expression::Literal(Literal::Number(25)))
+                right: Box::new(Expression::Literal {
+                    value: Literal::Number(25),
+                    span: Span::default(),
+                }),
+                span: Span::default(),
             }
         );
     }
@@ -288,9 +156,13 @@ mod tests {
         assert_eq!(
             result.unwrap(),
             Expression::Binary {
-                left: Box::new(Expression::Column("score".to_string())),
+                left: Box::new(Expression::Column("score".into())),
                 operator: BinaryOperator::LessThan,
-                right: Box::new(Expression::Literal(Literal::Number(100)))
+                right: Box::new(Expression::Literal {
+                    value: Literal::Number(100),
+                    span: Span::default(),
+                }),
+                span: Span::default(),
             }
         );
     }
@@ -304,16 +176,25 @@ mod tests {
             result.unwrap(),
             Expression::Binary {
                 left: Box::new(Expression::Binary {
-                    left: Box::new(Expression::Column("age".to_string())),
+                    left: Box::new(Expression::Column("age".into())),
                     operator: BinaryOperator::GreaterThan,
-                    right: Box::new(Expression::Literal(Literal::Number(18)))
+                    right: Box::new(Expression::Literal {
+                        value: Literal::Number(18),
+                        span: Span::default(),
+                    }),
+                    span: Span::default(),
                 }),
                 operator: BinaryOperator::And,
                 right: Box::new(Expression::Binary {
-                    left: Box::new(Expression::Column("score".to_string())),
+                    left: Box::new(Expression::Column("score".into())),
                     operator: BinaryOperator::LessThan,
-                    right: Box::new(Expression::Literal(Literal::Number(100)))
-                })
+                    right: Box::new(Expression::Literal {
+                        value: Literal::Number(100),
+                        span: Span::default(),
+                    }),
+                    span: Span::default(),
+                }),
+                span: Span::default(),
             }
         );
     }
@@ -327,16 +208,25 @@ mod tests {
             result.unwrap(),
             Expression::Binary {
                 left: Box::new(Expression::Binary {
-                    left: Box::new(Expression::Column("status".to_string())),
+                    left: Box::new(Expression::Column("status".into())),
                     operator: BinaryOperator::Equal,
-                    right: Box::new(Expression::Literal(Literal::String("active".to_string())))
+                    right: Box::new(Expression::Literal {
+                        value: Literal::String("active".to_string()),
+                        span: Span::default(),
+                    }),
+                    span: Span::default(),
                 }),
                 operator: BinaryOperator::Or,
                 right: Box::new(Expression::Binary {
-                    left: Box::new(Expression::Column("status".to_string())),
+                    left: Box::new(Expression::Column("status".into())),
                     operator: BinaryOperator::Equal,
-                    right: Box::new(Expression::Literal(Literal::String("pending".to_string())))
-                })
+                    right: Box::new(Expression::Literal {
+                        value: Literal::String("pending".to_string()),
+                        span: Span::default(),
+                    }),
+                    span: Span::default(),
+                }),
+                span: Span::default(),
             }
         );
     }
@@ -350,7 +240,8 @@ mod tests {
             result.unwrap(),
             Expression::Unary {
                 operator: UnaryOperator::Not,
-                operand: Box::new(Expression::Column("active".to_string()))
+                operand: Box::new(Expression::Column("active".into())),
+                span: Span::default(),
             }
         );
     }
@@ -363,9 +254,10 @@ mod tests {
         assert_eq!(
             result.unwrap(),
             Expression::Binary {
-                left: Box::new(Expression::Column("price".to_string())),
+                left: Box::new(Expression::Column("price".into())),
                 operator: BinaryOperator::Add,
-                right: Box::new(Expression::Column("tax".to_string()))
+                right: Box::new(Expression::Column("tax".into())),
+                span: Span::default(),
             }
         );
     }
@@ -378,9 +270,10 @@ mod tests {
         assert_eq!(
             result.unwrap(),
             Expression::Binary {
-                left: Box::new(Expression::Column("total".to_string())),
+                left: Box::new(Expression::Column("total".into())),
                 operator: BinaryOperator::Subtract,
-                right: Box::new(Expression::Column("discount".to_string()))
+                right: Box::new(Expression::Column("discount".into())),
+                span: Span::default(),
             }
         );
     }
@@ -393,24 +286,27 @@ mod tests {
         assert_eq!(
             result.unwrap(),
             Expression::Binary {
-                left: Box::new(Expression::Column("quantity".to_string())),
+                left: Box::new(Expression::Column("quantity".into())),
                 operator: BinaryOperator::Multiply,
-                right: Box::new(Expression::Column("price".to_string()))
+                right: Box::new(Expression::Column("price".into())),
+                span: Span::default(),
             }
         );
     }
 
     #[test]
     fn test_parse_arithmetic_division() {
-        let expr = "total / count";
+        // COUNT is reserved in SQL92, so the column name is quoted
+        let expr = "total / \"count\"";
         let result = parse_expression(expr);
         assert!(result.is_ok());
         assert_eq!(
             result.unwrap(),
             Expression::Binary {
-                left: Box::new(Expression::Column("total".to_string())),
+                left: Box::new(Expression::Column("total".into())),
                 operator: BinaryOperator::Divide,
-                right: Box::new(Expression::Column("count".to_string()))
+                right: Box::new(Expression::Column("count".into())),
+                span: Span::default(),
             }
         );
     }
@@ -424,7 +320,8 @@ mod tests {
             result.unwrap(),
             Expression::Unary {
                 operator: UnaryOperator::Minus,
-                operand: Box::new(Expression::Column("amount".to_string()))
+                operand: Box::new(Expression::Column("amount".into())),
+                span: Span::default(),
             }
         );
     }
@@ -442,9 +339,13 @@ mod tests {
         assert_eq!(
             parsed,
             Expression::Binary {
-                left: Box::new(Expression::Column("score".to_string())),
+                left: Box::new(Expression::Column("score".into())),
                 operator: BinaryOperator::GreaterThanOrEqual,
-                right: Box::new(Expression::Literal(Literal::Number(80)))
+                right: Box::new(Expression::Literal {
+                    value: Literal::Number(80),
+                    span: Span::default(),
+                }),
+                span: Span::default(),
             }
         );
     }
@@ -459,4 +360,50 @@ mod tests {
         assert!(result.is_ok());
         // この複雑な式の構造も確認するテスト
     }
+
+    #[test]
+    fn test_parse_qualified_column_reference() {
+        let expr = "users.name";
+        let result = parse_expression(expr);
+        assert!(result.is_ok());
+        assert_eq!(
+            result.unwrap(),
+            Expression::QualifiedColumn {
+                table: "users".into(),
+                column: "name".into(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_function_call() {
+        let expr = "coalesce(nickname, 'anonymous')";
+        let result = parse_expression(expr);
+        assert!(result.is_ok());
+        assert_eq!(
+            result.unwrap(),
+            Expression::Function {
+                name: "coalesce".into(),
+                args: vec![
+                    Expression::Column("nickname".into()),
+                    Expression::Literal {
+                        value: Literal::String("anonymous".to_string()),
+                        span: Span::default(),
+                    },
+                ],
+                span: Span::default(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_unary_span_starts_at_operator() {
+        let expr = parse_expression("NOT - x").unwrap();
+        let span = expr.span();
+        assert_eq!((span.start, span.end), (0, 7));
+
+        let expr = parse_expression("t.id").unwrap();
+        let span = expr.span();
+        assert_eq!((span.start, span.end), (0, 4));
+    }
 }
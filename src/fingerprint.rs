@@ -0,0 +1,194 @@
+use crate::ast::{Expression, Literal, Span, Statement, UnaryOperator};
+use crate::visitor::{walk_statement_mut, Flow, VisitorMut};
+
+// Groups statements that differ only in their constants, like pg_stat_statements does.
+// Returns the normalized text and a hash of it that is stable across runs and releases
+pub fn fingerprint(statement: &Statement) -> (String, u64) {
+    let mut statement = statement.clone();
+    walk_statement_mut(&mut ReplaceLiterals, &mut statement);
+    // Display already upper-cases keywords and separates tokens with single spaces
+    let text = replace_values(&statement.to_string());
+    let hash = fnv1a(text.as_bytes());
+    (text, hash)
+}
+
+struct ReplaceLiterals;
+
+impl VisitorMut for ReplaceLiterals {
+    fn pre_visit_expression(&mut self, expression: &mut Expression) -> Flow {
+        match expression {
+            // -1 is a constant like 1, not the minus operator applied to one
+            Expression::Unary {
+                operator: UnaryOperator::Minus,
+                operand,
+                span,
+            } if matches!(
+                **operand,
+                Expression::Literal {
+                    value: Literal::Number(_) | Literal::Float(_),
+                    ..
+                }
+            ) =>
+            {
+                *expression = placeholder(*span);
+            }
+            Expression::Literal { span, .. } => *expression = placeholder(*span),
+            _ => {}
+        }
+        Flow::Continue
+    }
+
+    // IN lists of constants differ only in their length, so they collapse to a single item
+    fn post_visit_expression(&mut self, expression: &mut Expression) -> Flow {
+        if let Expression::InList { list, .. } = expression {
+            if list.iter().all(is_placeholder) {
+                list.truncate(1);
+            }
+        }
+        Flow::Continue
+    }
+}
+
+fn placeholder(span: Span) -> Expression {
+    Expression::Parameter {
+        name: "?".to_string(),
+        span,
+    }
+}
+
+fn is_placeholder(expression: &Expression) -> bool {
+    matches!(expression, Expression::Parameter { name, .. } if name == "?")
+}
+
+// INSERT and UPDATE values are not expressions in the AST, so they are replaced in the
+// rendered text. Every other string literal is already a placeholder, and quoted identifiers
+// are copied unchanged
+fn replace_values(sql: &str) -> String {
+    let mut out = String::with_capacity(sql.len());
+    let mut chars = sql.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' | '\'' => {
+                let mut quoted = String::from(c);
+                while let Some(next) = chars.next() {
+                    quoted.push(next);
+                    if next == c {
+                        // A doubled quote is an escaped quote inside the token
+                        if chars.peek() == Some(&c) {
+                            quoted.push(chars.next().unwrap());
+                        } else {
+                            break;
+                        }
+                    }
+                }
+                if c == '"' {
+                    out.push_str(&quoted);
+                } else {
+                    out.push('?');
+                }
+            }
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+// 64-bit FNV-1a. std's DefaultHasher is not guaranteed to be stable between Rust releases
+fn fnv1a(bytes: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in bytes {
+        hash ^= u64::from(*byte);
+        hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+    }
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_statement;
+
+    fn fingerprint_of(sql: &str) -> (String, u64) {
+        fingerprint(&parse_statement(sql).unwrap())
+    }
+
+    #[test]
+    fn test_literals_are_replaced_with_placeholders() {
+        let (text, _) = fingerprint_of(
+            "select * from users where id = 42 and name = 'alice' and active = true limit 10 offset 20;",
+        );
+        assert_eq!(
+            text,
+            "SELECT * FROM users WHERE id = ? AND name = ? AND active = ? LIMIT ? OFFSET ?;"
+        );
+    }
+
+    #[test]
+    fn test_constants_whitespace_and_keyword_case_do_not_change_the_fingerprint() {
+        let first = fingerprint_of("SELECT * FROM users WHERE id = 1 AND score > 2.5 LIMIT 5;");
+        let second =
+            fingerprint_of("select  *\n  from users\n where id = 999 and score > 0.1\n limit 50;");
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn test_different_shapes_have_different_fingerprints() {
+        let (_, first) = fingerprint_of("SELECT * FROM users WHERE id = 1;");
+        let (_, second) = fingerprint_of("SELECT * FROM users WHERE name = 1;");
+        let (_, third) = fingerprint_of("SELECT * FROM orders WHERE id = 1;");
+        assert_ne!(first, second);
+        assert_ne!(first, third);
+    }
+
+    #[test]
+    fn test_insert_and_update_values_are_replaced() {
+        let (text, _) = fingerprint_of("INSERT INTO users VALUES ('bob', 'it''s');");
+        assert_eq!(text, "INSERT INTO users VALUES (?, ?);");
+        let (text, _) = fingerprint_of("UPDATE users SET name = 'bob' WHERE id = 7;");
+        assert_eq!(text, "UPDATE users SET name = ? WHERE id = ?;");
+    }
+
+    #[test]
+    fn test_negative_numbers_and_in_lists_are_normalized() {
+        let (first, _) = fingerprint_of("SELECT * FROM t WHERE a = -1 AND b IN (1, 2, 3);");
+        let (second, _) = fingerprint_of("SELECT * FROM t WHERE a = 5 AND b IN (7);");
+        assert_eq!(first, "SELECT * FROM t WHERE a = ? AND b IN (?);");
+        assert_eq!(first, second);
+        let (text, _) = fingerprint_of("SELECT * FROM t WHERE a NOT IN (-1.5, 'x', ?);");
+        assert_eq!(text, "SELECT * FROM t WHERE a NOT IN (?);");
+        // Lists with columns keep their shape
+        let (text, _) = fingerprint_of("SELECT * FROM t WHERE a IN (b, 1, 2);");
+        assert_eq!(text, "SELECT * FROM t WHERE a IN (b, ?, ?);");
+        let (text, _) = fingerprint_of("SELECT * FROM t WHERE a - 1 > 0;");
+        assert_eq!(text, "SELECT * FROM t WHERE a - ? > ?;");
+    }
+
+    #[test]
+    fn test_limit_and_offset_parameters_match_constants() {
+        let first = fingerprint_of("SELECT * FROM t LIMIT 10 OFFSET 20;");
+        let second = fingerprint_of("SELECT * FROM t LIMIT ? OFFSET ?;");
+        assert_eq!(first, second);
+        let (text, _) = fingerprint_of("SELECT * FROM t LIMIT 20, 10;");
+        assert_eq!(text, "SELECT * FROM t LIMIT ? OFFSET ?;");
+    }
+
+    #[test]
+    fn test_type_modifiers_and_quoted_identifiers_are_kept() {
+        let (text, _) =
+            fingerprint_of("SELECT * FROM \"limit 5\" WHERE CAST(name AS VARCHAR(20)) = 'x';");
+        assert_eq!(
+            text,
+            "SELECT * FROM \"limit 5\" WHERE CAST(name AS VARCHAR(20)) = ?;"
+        );
+    }
+
+    #[test]
+    fn test_fingerprint_text_parses_and_is_stable() {
+        let (text, hash) = fingerprint_of("DELETE FROM users WHERE id = 3;");
+        assert_eq!(fingerprint_of(&text), (text.clone(), hash));
+        // The hash is part of the output format, so it must not change between versions
+        assert_eq!(hash, fnv1a(b"DELETE FROM users WHERE id = ?;"));
+        assert_eq!(fnv1a(b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(fnv1a(b"a"), 0xaf63_dc4c_8601_ec8c);
+    }
+}
@@ -0,0 +1,1140 @@
+use crate::ast::{
+    BinaryOperator, ColumnDefinition, ColumnOption, ConflictTarget, DataType, Expression,
+    GroupingElement, Ident, Literal, MergeAction, MergeClause, MergeSource, OnConflict,
+    OrderDirection, Statement, UnaryOperator,
+};
+use crate::display::{FunctionName, QuotedString};
+use crate::error::ParseError;
+use crate::parser::parse_script;
+use std::iter;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeywordCase {
+    Upper,
+    Lower,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CommaPlacement {
+    // a,\n    b
+    Trailing,
+    // a\n    , b
+    Leading,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FormatOptions {
+    pub keyword_case: KeywordCase,
+    // Spaces per indentation level
+    pub indent: usize,
+    // Start every clause on its own line. Otherwise a statement stays on one line
+    // unless it is wider than max_width
+    pub clause_per_line: bool,
+    pub comma_placement: CommaPlacement,
+    // Lists and conditions wider than this are broken over several lines
+    pub max_width: usize,
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        FormatOptions {
+            keyword_case: KeywordCase::Upper,
+            indent: 4,
+            clause_per_line: true,
+            comma_placement: CommaPlacement::Trailing,
+            max_width: 80,
+        }
+    }
+}
+
+// Formats every statement of a script, keeping `--` comments and single blank lines
+// between statements. Fails with the first diagnostic if any statement does not parse
+pub fn format_sql(sql: &str, options: &FormatOptions) -> Result<String, ParseError> {
+    let script = parse_script(sql);
+    if let Some(error) = script.diagnostics.into_iter().next() {
+        return Err(error);
+    }
+
+    let mut comments = comments(sql).into_iter().peekable();
+    let mut output = Output {
+        sql,
+        lines: Vec::new(),
+        end: None,
+    };
+    for statement in &script.statements {
+        let span = statement.span();
+        while let Some(comment) = comments.next_if(|comment| comment.start < span.start) {
+            output.comment(&comment);
+        }
+        let inner: Vec<_> =
+            iter::from_fn(|| comments.next_if(|comment| comment.start < span.end)).collect();
+        output.separate(span.start);
+        for line in layout_statement(statement, &inner, options) {
+            output.lines.push(line.render(options));
+        }
+        output.end = Some(span.end);
+    }
+    for comment in comments {
+        output.comment(&comment);
+    }
+
+    let mut formatted = output.lines.join("\n");
+    if !formatted.is_empty() {
+        formatted.push('\n');
+    }
+    Ok(formatted)
+}
+
+// Formats a single statement, including its terminating semicolon but no trailing newline
+pub fn format_statement(statement: &Statement, options: &FormatOptions) -> String {
+    layout_statement(statement, &[], options)
+        .iter()
+        .map(|line| line.render(options))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+pub(crate) struct Comment<'a> {
+    pub(crate) text: &'a str,
+    pub(crate) start: usize,
+    pub(crate) end: usize,
+    // Nothing but whitespace precedes the comment on its line
+    own_line: bool,
+}
+
+// The grammar skips comments, so they are recovered from the source text
+pub(crate) fn comments(sql: &str) -> Vec<Comment<'_>> {
+    let bytes = sql.as_bytes();
+    let mut comments = Vec::new();
+    let mut index = 0;
+    while index < bytes.len() {
+        match bytes[index] {
+            // A doubled quote closes and reopens the literal, which has the same effect
+            quote @ (b'\'' | b'"' | b'`') => {
+                index += 1;
+                while index < bytes.len() && bytes[index] != quote {
+                    index += 1;
+                }
+            }
+            b'-' if bytes.get(index + 1) == Some(&b'-') => {
+                let start = index;
+                while index < bytes.len() && bytes[index] != b'\n' {
+                    index += 1;
+                }
+                let line_start = sql[..start].rfind('\n').map_or(0, |newline| newline + 1);
+                comments.push(Comment {
+                    text: sql[start..index].trim_end(),
+                    start,
+                    end: index,
+                    own_line: sql[line_start..start].trim().is_empty(),
+                });
+                continue;
+            }
+            _ => {}
+        }
+        index += 1;
+    }
+    comments
+}
+
+struct Output<'a> {
+    sql: &'a str,
+    lines: Vec<String>,
+    // Source offset where the last statement or comment ended
+    end: Option<usize>,
+}
+
+impl Output<'_> {
+    fn comment(&mut self, comment: &Comment) {
+        match self.lines.last_mut() {
+            Some(line) if !comment.own_line => {
+                line.push(' ');
+                line.push_str(comment.text);
+            }
+            _ => {
+                self.separate(comment.start);
+                self.lines.push(comment.text.to_string());
+            }
+        }
+        self.end = Some(comment.end);
+    }
+
+    // Keeps one blank line where the source had at least one
+    fn separate(&mut self, start: usize) {
+        if let Some(end) = self.end {
+            if self.sql[end..start].matches('\n').count() > 1 {
+                self.lines.push(String::new());
+            }
+        }
+    }
+}
+
+struct Line {
+    depth: usize,
+    text: String,
+    // Source offset of the first node on the line, if it has one. Comments inside a statement
+    // are placed by comparing their offsets with it
+    anchor: Option<usize>,
+    commented: bool,
+}
+
+impl Line {
+    fn render(&self, options: &FormatOptions) -> String {
+        if self.text.is_empty() {
+            return String::new();
+        }
+        format!("{}{}", " ".repeat(self.depth * options.indent), self.text)
+    }
+}
+
+fn layout_statement(
+    statement: &Statement,
+    comments: &[Comment],
+    options: &FormatOptions,
+) -> Vec<Line> {
+    let start = statement.span().start;
+    // A statement that carries comments is never joined into one line,
+    // since a comment runs to the end of its line
+    if !options.clause_per_line && comments.is_empty() {
+        let mut layout = Layout::new(options, usize::MAX);
+        layout.statement(statement);
+        let text = format!("{};", layout.joined());
+        if text.chars().count() <= options.max_width {
+            return vec![Line {
+                depth: 0,
+                text,
+                anchor: Some(start),
+                commented: false,
+            }];
+        }
+    }
+
+    let mut layout = Layout::new(options, options.max_width);
+    layout.comments = comments.iter().map(|comment| comment.start).collect();
+    layout.statement(statement);
+    let mut lines = layout.lines;
+    if let Some(last) = lines.last_mut() {
+        last.text.push(';');
+    }
+    for comment in comments {
+        place_comment(&mut lines, comment);
+    }
+    lines
+}
+
+fn place_comment(lines: &mut Vec<Line>, comment: &Comment) {
+    if comment.own_line {
+        // Before the first line that starts after the comment, or else after the last one
+        // that starts before it
+        let index = lines
+            .iter()
+            .position(|line| line.anchor.is_some_and(|anchor| anchor > comment.start))
+            .or_else(|| {
+                lines
+                    .iter()
+                    .rposition(|line| line.anchor.is_some_and(|anchor| anchor <= comment.start))
+                    .map(|index| index + 1)
+            })
+            .unwrap_or(lines.len());
+        let depth = lines
+            .get(index)
+            .or(lines.last())
+            .map_or(0, |line| line.depth);
+        lines.insert(
+            index,
+            Line {
+                depth,
+                text: comment.text.to_string(),
+                anchor: Some(comment.start),
+                commented: true,
+            },
+        );
+        return;
+    }
+
+    // At the end of the last line that starts before the comment
+    let index = lines
+        .iter()
+        .rposition(|line| line.anchor.is_some_and(|anchor| anchor <= comment.start))
+        .unwrap_or(0);
+    let line = &mut lines[index];
+    if line.commented {
+        let depth = line.depth;
+        lines.insert(
+            index + 1,
+            Line {
+                depth,
+                text: comment.text.to_string(),
+                anchor: Some(comment.start),
+                commented: true,
+            },
+        );
+    } else {
+        line.text.push(' ');
+        line.text.push_str(comment.text);
+        line.commented = true;
+    }
+}
+
+// Lays a statement out as indented lines. With an unlimited width nothing is wrapped,
+// so joining the lines with spaces gives the single-line form
+struct Layout<'a> {
+    options: &'a FormatOptions,
+    width: usize,
+    lines: Vec<Line>,
+    depth: usize,
+    // Source offsets of the comments inside the statement. A condition or list with a comment
+    // inside is broken over lines so that the comment stays next to the item it follows
+    comments: Vec<usize>,
+}
+
+impl<'a> Layout<'a> {
+    fn new(options: &'a FormatOptions, width: usize) -> Self {
+        Layout {
+            options,
+            width,
+            lines: Vec::new(),
+            depth: 0,
+            comments: Vec::new(),
+        }
+    }
+
+    fn joined(&self) -> String {
+        self.lines
+            .iter()
+            .map(|line| line.text.as_str())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    fn keyword(&self, keyword: &str) -> String {
+        match self.options.keyword_case {
+            KeywordCase::Upper => keyword.to_uppercase(),
+            KeywordCase::Lower => keyword.to_lowercase(),
+        }
+    }
+
+    fn fits(&self, text: &str) -> bool {
+        let indent = self.depth.saturating_mul(self.options.indent);
+        indent.saturating_add(text.chars().count()) <= self.width
+    }
+
+    fn commented(&self, start: usize, end: usize) -> bool {
+        self.comments
+            .iter()
+            .any(|&comment| start < comment && comment < end)
+    }
+
+    // Whether a comment lies between the first and the last item
+    fn commented_items(&self, items: &[(String, Option<usize>)]) -> bool {
+        match (
+            first_anchor(items),
+            items.last().and_then(|(_, anchor)| *anchor),
+        ) {
+            (Some(first), Some(last)) => self.commented(first, last),
+            _ => false,
+        }
+    }
+
+    fn push(&mut self, text: String, anchor: Option<usize>) {
+        self.lines.push(Line {
+            depth: self.depth,
+            text,
+            anchor,
+            commented: false,
+        });
+    }
+
+    fn indented(&mut self, f: impl FnOnce(&mut Self)) {
+        self.depth += 1;
+        f(self);
+        self.depth -= 1;
+    }
+
+    // One item per line, with the commas placed as configured
+    fn items(&mut self, items: Vec<(String, Option<usize>)>) {
+        let count = items.len();
+        for (i, (text, anchor)) in items.into_iter().enumerate() {
+            let text = match self.options.comma_placement {
+                CommaPlacement::Trailing if i + 1 < count => format!("{},", text),
+                CommaPlacement::Leading if i > 0 => format!(", {}", text),
+                _ => text,
+            };
+            self.push(text, anchor);
+        }
+    }
+
+    // "HEAD a, b" on one line, or the head followed by an indented line per item
+    fn list(&mut self, head: String, items: Vec<(String, Option<usize>)>, anchor: Option<usize>) {
+        let anchor = anchor.or_else(|| first_anchor(&items));
+        let line = format!("{} {}", head, join(&items));
+        if self.fits(&line) && !self.commented_items(&items) {
+            self.push(line, anchor);
+        } else {
+            self.push(head, anchor);
+            self.indented(|layout| layout.items(items));
+        }
+    }
+
+    // "HEAD (a, b) TAIL" on one line, or the items indented between the parentheses
+    fn parenthesized(
+        &mut self,
+        head: String,
+        items: Vec<(String, Option<usize>)>,
+        tail: &str,
+        anchor: Option<usize>,
+    ) {
+        let anchor = anchor.or_else(|| first_anchor(&items));
+        let tail = if tail.is_empty() {
+            String::new()
+        } else {
+            format!(" {}", tail)
+        };
+        let line = format!("{} ({}){}", head, join(&items), tail);
+        if self.fits(&line) && !self.commented_items(&items) {
+            self.push(line, anchor);
+        } else {
+            self.push(format!("{} (", head), anchor);
+            self.indented(|layout| layout.items(items));
+            self.push(format!("){}", tail), None);
+        }
+    }
+
+    // "WHERE a AND b", or one operand per line when a top-level AND/OR chain is too wide
+    fn condition(&mut self, keyword: &str, expression: &Expression) {
+        let keyword = self.keyword(keyword);
+        let anchor = Some(expression.span().start);
+        let line = format!("{} {}", keyword, self.expression(expression));
+        let operator = match expression {
+            Expression::Binary { operator, .. }
+                if matches!(operator, BinaryOperator::And | BinaryOperator::Or) =>
+            {
+                operator
+            }
+            _ => return self.push(line, anchor),
+        };
+        let span = expression.span();
+        if self.fits(&line) && !self.commented(span.start, span.end) {
+            return self.push(line, anchor);
+        }
+
+        let mut operands = Vec::new();
+        let mut left = expression;
+        while let Expression::Binary {
+            left: inner,
+            operator: inner_operator,
+            right,
+            ..
+        } = left
+        {
+            if inner_operator != operator {
+                break;
+            }
+            operands.push(right.as_ref());
+            left = inner;
+        }
+        let precedence = operator.precedence();
+        let first = self.operand(left, precedence);
+        self.push(format!("{} {}", keyword, first), anchor);
+        let operator = self.operator(operator);
+        self.indented(|layout| {
+            for operand in operands.into_iter().rev() {
+                let text = format!("{} {}", operator, layout.operand(operand, precedence + 1));
+                layout.push(text, Some(operand.span().start));
+            }
+        });
+    }
+
+    fn statement(&mut self, statement: &Statement) {
+        match statement {
+            Statement::Select { .. } => self.query(statement),
+            Statement::Insert {
+                table,
+                values,
+                on_conflict,
+                ..
+            } => {
+                let line = format!("{} {}", self.keyword("INSERT INTO"), table);
+                self.push(line, Some(table.span().start));
+                let values = values
+                    .iter()
+                    .map(|value| (QuotedString(value).to_string(), None))
+                    .collect();
+                self.parenthesized(self.keyword("VALUES"), values, "", None);
+                if let Some(on_conflict) = on_conflict {
+                    self.on_conflict(on_conflict);
+                }
+            }
+            Statement::Update {
+                table,
+                set,
+                where_clause,
+                ..
+            } => {
+                let line = format!("{} {}", self.keyword("UPDATE"), table);
+                self.push(line, Some(table.span().start));
+                let set = set
+                    .iter()
+                    .map(|(column, value)| {
+                        let text = format!("{} = {}", column, QuotedString(value));
+                        (text, Some(column.span.start))
+                    })
+                    .collect();
+                self.list(self.keyword("SET"), set, None);
+                if let Some(where_clause) = where_clause {
+                    self.condition("WHERE", where_clause);
+                }
+            }
+            Statement::Delete {
+                table,
+                where_clause,
+                ..
+            } => {
+                let line = format!("{} {}", self.keyword("DELETE FROM"), table);
+                self.push(line, Some(table.span().start));
+                if let Some(where_clause) = where_clause {
+                    self.condition("WHERE", where_clause);
+                }
+            }
+            Statement::Merge {
+                table,
+                alias,
+                source,
+                on,
+                clauses,
+                ..
+            } => {
+                let mut line = format!("{} {}", self.keyword("MERGE INTO"), table);
+                if let Some(alias) = alias {
+                    line.push_str(&format!(" {} {}", self.keyword("AS"), alias));
+                }
+                self.push(line, Some(table.span().start));
+                self.merge_source(source);
+                self.condition("ON", on);
+                for clause in clauses {
+                    self.merge_clause(clause);
+                }
+            }
+            Statement::Prepare {
+                name,
+                parameter_types,
+                statement,
+                ..
+            } => {
+                let head = format!("{} {}", self.keyword("PREPARE"), name);
+                let as_keyword = self.keyword("AS");
+                if parameter_types.is_empty() {
+                    self.push(format!("{} {}", head, as_keyword), Some(name.span.start));
+                } else {
+                    let types = parameter_types
+                        .iter()
+                        .map(|data_type| (self.data_type(data_type), None))
+                        .collect();
+                    self.parenthesized(head, types, &as_keyword, Some(name.span.start));
+                }
+                self.indented(|layout| layout.statement(statement));
+            }
+            Statement::Execute {
+                name, parameters, ..
+            } => {
+                let head = format!("{} {}", self.keyword("EXECUTE"), name);
+                if parameters.is_empty() {
+                    self.push(head, Some(name.span.start));
+                } else {
+                    let parameters = self.expressions(parameters);
+                    self.parenthesized(head, parameters, "", Some(name.span.start));
+                }
+            }
+            Statement::Deallocate { name, .. } => match name {
+                Some(name) => {
+                    let line = format!("{} {}", self.keyword("DEALLOCATE PREPARE"), name);
+                    self.push(line, Some(name.span.start));
+                }
+                None => self.push(self.keyword("DEALLOCATE ALL"), None),
+            },
+            Statement::CreateTable { table, columns, .. } => {
+                let head = format!("{} {}", self.keyword("CREATE TABLE"), table);
+                let columns = columns
+                    .iter()
+                    .map(|column| (self.column_definition(column), Some(column.span.start)))
+                    .collect();
+                self.parenthesized(head, columns, "", Some(table.span().start));
+            }
+        }
+    }
+
+    fn query(&mut self, statement: &Statement) {
+        let Statement::Select {
+            table,
+            where_clause,
+            order_by,
+            group_by,
+            limit,
+            offset,
+            ..
+        } = statement
+        else {
+            return self.statement(statement);
+        };
+        let anchor = Some(statement.span().start);
+        self.push(format!("{} *", self.keyword("SELECT")), anchor);
+        let line = format!("{} {}", self.keyword("FROM"), table);
+        self.push(line, Some(table.span().start));
+        if let Some(where_clause) = where_clause {
+            self.condition("WHERE", where_clause);
+        }
+        if let Some(group_by) = group_by {
+            let items = group_by
+                .items
+                .iter()
+                .map(|element| {
+                    let anchor = match element {
+                        GroupingElement::Expression(expression) => Some(expression.span().start),
+                        _ => None,
+                    };
+                    (self.grouping_element(element), anchor)
+                })
+                .collect();
+            self.list(self.keyword("GROUP BY"), items, None);
+        }
+        if let Some(order_by) = order_by {
+            let items = order_by
+                .items
+                .iter()
+                .map(|item| {
+                    let text = match item.direction {
+                        OrderDirection::Asc => item.column.to_string(),
+                        OrderDirection::Desc => format!("{} {}", item.column, self.keyword("DESC")),
+                    };
+                    (text, Some(item.span.start))
+                })
+                .collect();
+            self.list(self.keyword("ORDER BY"), items, None);
+        }
+        if let Some(limit) = limit {
+            let line = format!("{} {}", self.keyword("LIMIT"), self.expression(limit));
+            self.push(line, Some(limit.span().start));
+        }
+        if let Some(offset) = offset {
+            let line = format!("{} {}", self.keyword("OFFSET"), self.expression(offset));
+            self.push(line, Some(offset.span().start));
+        }
+    }
+
+    fn on_conflict(&mut self, on_conflict: &OnConflict) {
+        match on_conflict {
+            OnConflict::DoNothing { target } => {
+                let mut line = self.keyword("ON CONFLICT");
+                if let Some(target) = target {
+                    line.push_str(&format!(" {}", self.conflict_target(target)));
+                }
+                line.push_str(&format!(" {}", self.keyword("DO NOTHING")));
+                self.push(line, None);
+            }
+            OnConflict::DoUpdate {
+                target,
+                set,
+                where_clause,
+            } => {
+                let line = format!(
+                    "{} {} {}",
+                    self.keyword("ON CONFLICT"),
+                    self.conflict_target(target),
+                    self.keyword("DO UPDATE")
+                );
+                self.push(line, None);
+                let set = self.assignments(set);
+                self.list(self.keyword("SET"), set, None);
+                if let Some(where_clause) = where_clause {
+                    self.condition("WHERE", where_clause);
+                }
+            }
+            OnConflict::DuplicateKeyUpdate { set } => {
+                let set = self.assignments(set);
+                self.list(self.keyword("ON DUPLICATE KEY UPDATE"), set, None);
+            }
+        }
+    }
+
+    fn conflict_target(&self, target: &ConflictTarget) -> String {
+        match target {
+            ConflictTarget::Columns(columns) => format!("({})", join_idents(columns)),
+            ConflictTarget::Constraint(name) => {
+                format!("{} {}", self.keyword("ON CONSTRAINT"), name)
+            }
+        }
+    }
+
+    fn merge_source(&mut self, source: &MergeSource) {
+        let using = self.keyword("USING");
+        let alias = match source {
+            MergeSource::Table { name, alias } => {
+                self.push(format!("{} {}", using, name), Some(name.span().start));
+                alias
+            }
+            MergeSource::Subquery { query, alias } => {
+                let mut flat = Layout::new(self.options, usize::MAX);
+                flat.query(query);
+                let line = format!("{} ({})", using, flat.joined());
+                let anchor = Some(query.span().start);
+                if self.fits(&line) {
+                    self.push(line, anchor);
+                } else {
+                    self.push(format!("{} (", using), anchor);
+                    self.indented(|layout| layout.query(query));
+                    self.push(")".to_string(), None);
+                }
+                alias
+            }
+        };
+        // The alias follows the source, or the closing parenthesis of a broken subquery
+        if let Some(alias) = alias {
+            let alias = format!(" {} {}", self.keyword("AS"), alias);
+            if let Some(line) = self.lines.last_mut() {
+                line.text.push_str(&alias);
+            }
+        }
+    }
+
+    fn merge_clause(&mut self, clause: &MergeClause) {
+        let mut line = self.keyword(if clause.matched {
+            "WHEN MATCHED"
+        } else {
+            "WHEN NOT MATCHED"
+        });
+        let anchor = clause.condition.as_ref().map(|condition| {
+            line.push_str(&format!(
+                " {} {}",
+                self.keyword("AND"),
+                self.expression(condition)
+            ));
+            condition.span().start
+        });
+        line.push_str(&format!(" {}", self.keyword("THEN")));
+        self.push(line, anchor);
+        self.indented(|layout| match &clause.action {
+            MergeAction::Update { set } => {
+                let set = layout.assignments(set);
+                layout.list(layout.keyword("UPDATE SET"), set, None);
+            }
+            MergeAction::Delete => layout.push(layout.keyword("DELETE"), None),
+            MergeAction::Insert { columns, values } => {
+                let insert = layout.keyword("INSERT");
+                if columns.is_empty() {
+                    layout.push(insert, None);
+                } else {
+                    let columns = columns
+                        .iter()
+                        .map(|column| (column.to_string(), Some(column.span.start)))
+                        .collect();
+                    layout.parenthesized(insert, columns, "", None);
+                }
+                let values = layout.expressions(values);
+                layout.parenthesized(layout.keyword("VALUES"), values, "", None);
+            }
+        });
+    }
+
+    fn assignments(&self, set: &[(Ident, Expression)]) -> Vec<(String, Option<usize>)> {
+        set.iter()
+            .map(|(column, value)| {
+                let text = format!("{} = {}", column, self.expression(value));
+                (text, Some(column.span.start))
+            })
+            .collect()
+    }
+
+    fn expressions(&self, expressions: &[Expression]) -> Vec<(String, Option<usize>)> {
+        expressions
+            .iter()
+            .map(|expression| (self.expression(expression), Some(expression.span().start)))
+            .collect()
+    }
+
+    // Same parenthesization as Display, with the keywords in the configured case
+    fn expression(&self, expression: &Expression) -> String {
+        match expression {
+            Expression::Literal { value, .. } => match value {
+                Literal::Boolean(true) => self.keyword("TRUE"),
+                Literal::Boolean(false) => self.keyword("FALSE"),
+                Literal::Null => self.keyword("NULL"),
+                value => value.to_string(),
+            },
+            Expression::Function { name, args, .. } => {
+                format!("{}({})", FunctionName(name), join(&self.expressions(args)))
+            }
+            Expression::Grouping { args, .. } => format!(
+                "{}({})",
+                self.keyword("GROUPING"),
+                join(&self.expressions(args))
+            ),
+            Expression::Binary {
+                left,
+                operator,
+                right,
+                ..
+            } => format!(
+                "{} {} {}",
+                self.operand(left, operator.precedence()),
+                self.operator(operator),
+                self.operand(right, operator.precedence() + 1)
+            ),
+            Expression::Unary {
+                operator, operand, ..
+            } => {
+                let operand = self.operand(operand, u8::MAX);
+                match operator {
+                    UnaryOperator::Not => format!("{} {}", self.keyword("NOT"), operand),
+                    UnaryOperator::Minus if operand.starts_with('-') => format!("- {}", operand),
+                    UnaryOperator::Minus => format!("-{}", operand),
+                }
+            }
+            Expression::Cast {
+                expression,
+                data_type,
+                ..
+            } => format!(
+                "{}({} {} {})",
+                self.keyword("CAST"),
+                self.expression(expression),
+                self.keyword("AS"),
+                self.data_type(data_type)
+            ),
+            Expression::InList {
+                expression: operand,
+                list,
+                negated,
+                ..
+            } => {
+                let operand = self.operand(operand, expression.precedence().unwrap_or(u8::MAX));
+                let in_keyword = self.keyword(if *negated { "NOT IN" } else { "IN" });
+                format!(
+                    "{} {} ({})",
+                    operand,
+                    in_keyword,
+                    join(&self.expressions(list))
+                )
+            }
+            Expression::Column(_)
+            | Expression::QualifiedColumn { .. }
+            | Expression::Parameter { .. } => expression.to_string(),
+        }
+    }
+
+    fn operand(&self, expression: &Expression, min_precedence: u8) -> String {
+        match expression.precedence() {
+            Some(precedence) if precedence < min_precedence => {
+                format!("({})", self.expression(expression))
+            }
+            _ => self.expression(expression),
+        }
+    }
+
+    fn operator(&self, operator: &BinaryOperator) -> String {
+        match operator {
+            BinaryOperator::And
+            | BinaryOperator::Or
+            | BinaryOperator::Like
+            | BinaryOperator::ILike => self.keyword(&operator.to_string()),
+            operator => operator.to_string(),
+        }
+    }
+
+    fn grouping_element(&self, element: &GroupingElement) -> String {
+        let elements = |elements: &[GroupingElement]| {
+            elements
+                .iter()
+                .map(|element| self.grouping_element(element))
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        match element {
+            GroupingElement::Expression(expression) => self.expression(expression),
+            GroupingElement::Set(expressions) => {
+                format!("({})", join(&self.expressions(expressions)))
+            }
+            GroupingElement::Rollup(items) => {
+                format!("{}({})", self.keyword("ROLLUP"), elements(items))
+            }
+            GroupingElement::Cube(items) => {
+                format!("{}({})", self.keyword("CUBE"), elements(items))
+            }
+            GroupingElement::GroupingSets(items) => {
+                format!("{} ({})", self.keyword("GROUPING SETS"), elements(items))
+            }
+        }
+    }
+
+    fn column_definition(&self, column: &ColumnDefinition) -> String {
+        let mut text = format!("{} {}", column.name, self.data_type(&column.data_type));
+        for option in &column.options {
+            let option = match option {
+                ColumnOption::Default(expression) => {
+                    format!(
+                        "{} {}",
+                        self.keyword("DEFAULT"),
+                        self.expression(expression)
+                    )
+                }
+                option => self.keyword(&option.to_string()),
+            };
+            text.push(' ');
+            text.push_str(&option);
+        }
+        text
+    }
+
+    // Custom type names are kept as written
+    fn data_type(&self, data_type: &DataType) -> String {
+        match data_type {
+            DataType::Custom(name) => name.clone(),
+            data_type => self.keyword(&data_type.to_string()),
+        }
+    }
+}
+
+fn join(items: &[(String, Option<usize>)]) -> String {
+    items
+        .iter()
+        .map(|(text, _)| text.as_str())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn first_anchor(items: &[(String, Option<usize>)]) -> Option<usize> {
+    items.first().and_then(|(_, anchor)| *anchor)
+}
+
+fn join_idents(idents: &[Ident]) -> String {
+    idents
+        .iter()
+        .map(Ident::to_string)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{format_sql, format_statement, CommaPlacement, FormatOptions, KeywordCase};
+    use crate::corpus;
+    use crate::error::ParseError;
+    use crate::parser::{parse_script, parse_sql};
+
+    fn format(sql: &str, options: &FormatOptions) -> String {
+        format_sql(sql, options).unwrap_or_else(|error| panic!("{}\n{}", sql, error))
+    }
+
+    #[test]
+    fn test_format_clause_per_line() {
+        let sql = "select * from users where id = 1 and name = 'foo' group by name order by created_at desc, id limit 10;";
+        assert_eq!(
+            format(sql, &FormatOptions::default()),
+            "SELECT *\nFROM users\nWHERE id = 1 AND name = 'foo'\nGROUP BY name\nORDER BY created_at DESC, id\nLIMIT 10;\n"
+        );
+    }
+
+    #[test]
+    fn test_keyword_case_and_indent() {
+        let sql = "MERGE INTO target AS t USING source AS s ON t.id = s.id \
+                   WHEN MATCHED AND s.deleted = TRUE THEN DELETE \
+                   WHEN NOT MATCHED THEN INSERT (id) VALUES (s.id);";
+        let options = FormatOptions {
+            keyword_case: KeywordCase::Lower,
+            indent: 2,
+            ..FormatOptions::default()
+        };
+        assert_eq!(
+            format(sql, &options),
+            "merge into target as t\n\
+             using source as s\n\
+             on t.id = s.id\n\
+             when matched and s.deleted = true then\n  delete\n\
+             when not matched then\n  insert (id)\n  values (s.id);\n"
+        );
+    }
+
+    #[test]
+    fn test_wrap_lists_at_max_width() {
+        let sql = "INSERT INTO users VALUES ('1', 'alice', 'alice@example.com');";
+        let trailing = FormatOptions {
+            max_width: 30,
+            ..FormatOptions::default()
+        };
+        assert_eq!(
+            format(sql, &trailing),
+            "INSERT INTO users\nVALUES (\n    '1',\n    'alice',\n    'alice@example.com'\n);\n"
+        );
+
+        let leading = FormatOptions {
+            comma_placement: CommaPlacement::Leading,
+            ..trailing
+        };
+        let sql = "UPDATE users SET name = 'alice', email = 'alice@example.com';";
+        assert_eq!(
+            format(sql, &leading),
+            "UPDATE users\nSET\n    name = 'alice'\n    , email = 'alice@example.com';\n"
+        );
+    }
+
+    #[test]
+    fn test_wrap_create_table_columns() {
+        let sql = "create table users (id integer primary key auto_increment, name varchar(20) not null default 'x');";
+        assert_eq!(
+            format(sql, &FormatOptions::default()),
+            "CREATE TABLE users (\n    id INTEGER PRIMARY KEY AUTO_INCREMENT,\n    name VARCHAR(20) NOT NULL DEFAULT 'x'\n);\n"
+        );
+        let sql = "create table t (id integer);";
+        assert_eq!(
+            format(sql, &FormatOptions::default()),
+            "CREATE TABLE t (id INTEGER);\n"
+        );
+    }
+
+    #[test]
+    fn test_break_long_conditions_at_top_level_operator() {
+        let sql = "DELETE FROM sessions WHERE expires_at < 100 AND (user_id = 1 OR user_id = 2) AND active = FALSE;";
+        let options = FormatOptions {
+            max_width: 40,
+            ..FormatOptions::default()
+        };
+        assert_eq!(
+            format(sql, &options),
+            "DELETE FROM sessions\nWHERE expires_at < 100\n    AND (user_id = 1 OR user_id = 2)\n    AND active = FALSE;\n"
+        );
+    }
+
+    #[test]
+    fn test_compact_layout_breaks_only_wide_statements() {
+        let options = FormatOptions {
+            clause_per_line: false,
+            max_width: 40,
+            ..FormatOptions::default()
+        };
+        let sql = "select * from users where id = 1;\nselect * from users where id = 1 order by name, created_at desc;";
+        assert_eq!(
+            format(sql, &options),
+            "SELECT * FROM users WHERE id = 1;\n\
+             SELECT *\nFROM users\nWHERE id = 1\nORDER BY name, created_at DESC;\n"
+        );
+    }
+
+    #[test]
+    fn test_preserve_comments_and_blank_lines() {
+        let sql = "-- users\n\
+                   select * from users where id = 1 -- the first\n\
+                   \x20 -- newest first\n\
+                   \x20 order by created_at desc;\n\
+                   \n\n\
+                   -- cleanup\n\
+                   delete from sessions; -- done\n\
+                   select * from logs\n\
+                   -- a few\n\
+                   limit 5;\n\
+                   -- end\n";
+        let formatted = format(sql, &FormatOptions::default());
+        assert_eq!(
+            formatted,
+            "-- users\n\
+             SELECT *\nFROM users\nWHERE id = 1 -- the first\n-- newest first\nORDER BY created_at DESC;\n\
+             \n\
+             -- cleanup\n\
+             DELETE FROM sessions; -- done\n\
+             SELECT *\nFROM logs\n-- a few\nLIMIT 5;\n\
+             -- end\n"
+        );
+        assert_eq!(format(&formatted, &FormatOptions::default()), formatted);
+    }
+
+    #[test]
+    fn test_comments_inside_where_stay_with_their_operands() {
+        let sql = "select * from users\n\
+                   where id = 1 -- the id\n\
+                   \x20 -- and the name\n\
+                   \x20 and name = 'a' -- quoted\n\
+                   \x20 and active;\n\
+                   select * from t group by a, -- x\n\
+                   \x20 b;\n";
+        for options in [
+            FormatOptions::default(),
+            FormatOptions {
+                clause_per_line: false,
+                ..FormatOptions::default()
+            },
+        ] {
+            let formatted = format(sql, &options);
+            assert_eq!(
+                formatted,
+                "SELECT *\nFROM users\n\
+                 WHERE id = 1 -- the id\n\
+                 \x20   -- and the name\n\
+                 \x20   AND name = 'a' -- quoted\n\
+                 \x20   AND active;\n\
+                 SELECT *\nFROM t\nGROUP BY\n    a, -- x\n    b;\n"
+            );
+            assert_eq!(format(&formatted, &options), formatted);
+            assert_eq!(
+                parse_script(&formatted).statements,
+                parse_script(sql).statements
+            );
+        }
+    }
+
+    #[test]
+    fn test_comment_markers_inside_literals_are_not_comments() {
+        let sql = "UPDATE \"a--b\" SET note = '-- not a comment';";
+        let options = FormatOptions {
+            clause_per_line: false,
+            ..FormatOptions::default()
+        };
+        assert_eq!(format(sql, &options), format!("{}\n", sql));
+    }
+
+    #[test]
+    fn test_format_crlf_input() {
+        let formatted = format_sql(
+            "-- users\r\nselect * from users\r\nwhere id = 1;\r\n",
+            &FormatOptions::default(),
+        )
+        .unwrap();
+        assert!(formatted.contains("SELECT"));
+        assert!(!formatted.contains('\r'));
+    }
+
+    #[test]
+    fn test_invalid_sql_returns_error() {
+        let error = format_sql(
+            "SELECT * FROM users;\nSELEC * FROM orders;",
+            &FormatOptions::default(),
+        )
+        .unwrap_err();
+        assert!(matches!(error, ParseError::Syntax { .. }));
+        assert_eq!(error.location().line, 2);
+    }
+
+    #[test]
+    fn test_formatting_preserves_the_ast() {
+        let option_sets = [
+            FormatOptions::default(),
+            FormatOptions {
+                keyword_case: KeywordCase::Lower,
+                indent: 2,
+                comma_placement: CommaPlacement::Leading,
+                max_width: 20,
+                ..FormatOptions::default()
+            },
+            FormatOptions {
+                clause_per_line: false,
+                ..FormatOptions::default()
+            },
+        ];
+        for statement in corpus::statements() {
+            for options in &option_sets {
+                let formatted = format_statement(&statement, options);
+                let reparsed = parse_sql(&formatted)
+                    .unwrap_or_else(|error| panic!("failed to re-parse {}\n{}", formatted, error));
+                assert_eq!(reparsed, statement, "{}", formatted);
+                assert_eq!(format_statement(&reparsed, options), formatted);
+            }
+        }
+    }
+}
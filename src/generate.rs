@@ -0,0 +1,523 @@
+use crate::ast::{
+    BinaryOperator, ColumnDefinition, ColumnOption, ConflictTarget, DataType, Expression, GroupBy,
+    GroupingElement, Ident, Literal, MergeAction, MergeClause, MergeSource, ObjectName, OnConflict,
+    OrderBy, OrderByItem, OrderDirection, Span, Statement, UnaryOperator,
+};
+use arbitrary::{Arbitrary, Result, Unstructured};
+
+// Limits on the generated trees. Every tree renders with Display to SQL that the parser reads
+// back into an equal tree, as long as the nesting stays within the parser's max_depth
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GenerateOptions {
+    // Operators, function calls and grouping elements nest at most this many levels deep
+    pub max_depth: usize,
+    // Longest list of arguments, columns, assignments, clauses and so on
+    pub max_items: usize,
+}
+
+impl Default for GenerateOptions {
+    fn default() -> Self {
+        GenerateOptions {
+            max_depth: 6,
+            max_items: 4,
+        }
+    }
+}
+
+// Names that exercise quoting: keywords, Japanese, spaces, quotes and a decomposed character
+const NAMES: &[&str] = &[
+    "id",
+    "name",
+    "users",
+    "t",
+    "s",
+    "created_at",
+    "nullable",
+    "顧客",
+    "名前",
+    "select",
+    "order",
+    "rollup",
+    "user id",
+    "say \"hi\"",
+    "cafe\u{301}",
+];
+
+const FUNCTIONS: &[&str] = &[
+    "count", "max", "coalesce", "lower", "values", "cube", "合計",
+];
+
+// Type names the parser keeps as DataType::Custom
+const CUSTOM_TYPES: &[&str] = &["uuid", "jsonb", "money", "金額"];
+
+const BINARY_OPERATORS: &[BinaryOperator] = &[
+    BinaryOperator::Equal,
+    BinaryOperator::NotEqual,
+    BinaryOperator::LessThan,
+    BinaryOperator::LessThanOrEqual,
+    BinaryOperator::GreaterThan,
+    BinaryOperator::GreaterThanOrEqual,
+    BinaryOperator::Like,
+    BinaryOperator::ILike,
+    BinaryOperator::And,
+    BinaryOperator::Or,
+    BinaryOperator::Add,
+    BinaryOperator::Subtract,
+    BinaryOperator::Multiply,
+    BinaryOperator::Divide,
+];
+
+pub fn arbitrary_statement(u: &mut Unstructured, options: &GenerateOptions) -> Result<Statement> {
+    Generator { u, options }.statement(true)
+}
+
+pub fn arbitrary_expression(u: &mut Unstructured, options: &GenerateOptions) -> Result<Expression> {
+    Generator { u, options }.expression(options.max_depth)
+}
+
+impl<'a> Arbitrary<'a> for Statement {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        arbitrary_statement(u, &GenerateOptions::default())
+    }
+}
+
+impl<'a> Arbitrary<'a> for Expression {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        arbitrary_expression(u, &GenerateOptions::default())
+    }
+}
+
+struct Generator<'u, 'a, 'o> {
+    u: &'u mut Unstructured<'a>,
+    options: &'o GenerateOptions,
+}
+
+impl Generator<'_, '_, '_> {
+    // Between min and max_items elements, or exactly min if that is larger
+    fn list<T>(
+        &mut self,
+        min: usize,
+        mut item: impl FnMut(&mut Self) -> Result<T>,
+    ) -> Result<Vec<T>> {
+        let len = self.u.int_in_range(min..=self.options.max_items.max(min))?;
+        (0..len).map(|_| item(self)).collect()
+    }
+
+    fn optional<T>(&mut self, item: impl FnOnce(&mut Self) -> Result<T>) -> Result<Option<T>> {
+        Ok(if self.u.arbitrary()? {
+            Some(item(self)?)
+        } else {
+            None
+        })
+    }
+
+    // Usually a name from the list, sometimes any non-empty string
+    fn ident(&mut self) -> Result<Ident> {
+        let value = if self.u.ratio(3, 4)? {
+            self.u.choose(NAMES)?.to_string()
+        } else {
+            let value: String = self.u.arbitrary()?;
+            if value.is_empty() {
+                "_".to_string()
+            } else {
+                value
+            }
+        };
+        Ok(Ident::new(value))
+    }
+
+    fn object_name(&mut self) -> Result<ObjectName> {
+        let schema = if self.u.ratio(1, 4)? {
+            Some(self.ident()?)
+        } else {
+            None
+        };
+        Ok(ObjectName {
+            schema,
+            name: self.ident()?,
+        })
+    }
+
+    // A statement that can stand on its own; PREPARE only wraps the others
+    fn statement(&mut self, top_level: bool) -> Result<Statement> {
+        let kinds = if top_level { 9 } else { 5 };
+        Ok(match self.u.choose_index(kinds)? {
+            0 => self.select()?,
+            1 => Statement::Insert {
+                table: self.object_name()?,
+                values: self.list(1, |g| g.u.arbitrary())?,
+                on_conflict: self.optional(Self::on_conflict)?,
+                span: Span::default(),
+            },
+            2 => Statement::Update {
+                table: self.object_name()?,
+                set: self.list(1, |g| Ok((g.ident()?, g.u.arbitrary()?)))?,
+                where_clause: self.optional(|g| g.expression(g.options.max_depth))?,
+                span: Span::default(),
+            },
+            3 => Statement::Delete {
+                table: self.object_name()?,
+                where_clause: self.optional(|g| g.expression(g.options.max_depth))?,
+                span: Span::default(),
+            },
+            4 => self.merge()?,
+            5 => Statement::Prepare {
+                name: self.ident()?,
+                parameter_types: self.list(0, Self::data_type)?,
+                statement: Box::new(self.statement(false)?),
+                span: Span::default(),
+            },
+            6 => Statement::Execute {
+                name: self.ident()?,
+                parameters: self.list(0, |g| g.expression(g.options.max_depth))?,
+                span: Span::default(),
+            },
+            7 => Statement::Deallocate {
+                name: self.optional(Self::ident)?,
+                span: Span::default(),
+            },
+            _ => Statement::CreateTable {
+                table: self.object_name()?,
+                columns: self.list(1, Self::column_definition)?,
+                span: Span::default(),
+            },
+        })
+    }
+
+    fn select(&mut self) -> Result<Statement> {
+        let depth = self.options.max_depth;
+        Ok(Statement::Select {
+            table: self.object_name()?,
+            where_clause: self.optional(|g| g.expression(depth))?,
+            order_by: self.optional(|g| {
+                Ok(OrderBy {
+                    items: g.list(1, |g| {
+                        Ok(OrderByItem {
+                            column: g.ident()?,
+                            direction: if g.u.arbitrary()? {
+                                OrderDirection::Asc
+                            } else {
+                                OrderDirection::Desc
+                            },
+                            span: Span::default(),
+                        })
+                    })?,
+                })
+            })?,
+            group_by: self.optional(|g| {
+                Ok(GroupBy {
+                    items: g.list(1, |g| g.grouping_element(depth))?,
+                })
+            })?,
+            limit: self.optional(|g| g.row_count())?,
+            offset: self.optional(|g| g.row_count())?,
+            span: Span::default(),
+        })
+    }
+
+    fn on_conflict(&mut self) -> Result<OnConflict> {
+        let depth = self.options.max_depth;
+        Ok(match self.u.choose_index(3)? {
+            0 => OnConflict::DoNothing {
+                target: self.optional(Self::conflict_target)?,
+            },
+            1 => OnConflict::DoUpdate {
+                target: self.conflict_target()?,
+                set: self.assignments()?,
+                where_clause: self.optional(|g| g.expression(depth))?,
+            },
+            _ => OnConflict::DuplicateKeyUpdate {
+                set: self.assignments()?,
+            },
+        })
+    }
+
+    fn conflict_target(&mut self) -> Result<ConflictTarget> {
+        Ok(if self.u.arbitrary()? {
+            ConflictTarget::Columns(self.list(1, Self::ident)?)
+        } else {
+            ConflictTarget::Constraint(self.ident()?)
+        })
+    }
+
+    fn assignments(&mut self) -> Result<Vec<(Ident, Expression)>> {
+        self.list(1, |g| Ok((g.ident()?, g.expression(g.options.max_depth)?)))
+    }
+
+    fn merge(&mut self) -> Result<Statement> {
+        let depth = self.options.max_depth;
+        let source = if self.u.arbitrary()? {
+            MergeSource::Table {
+                name: self.object_name()?,
+                alias: self.optional(Self::ident)?,
+            }
+        } else {
+            MergeSource::Subquery {
+                query: Box::new(self.select()?),
+                alias: self.optional(Self::ident)?,
+            }
+        };
+        Ok(Statement::Merge {
+            table: self.object_name()?,
+            alias: self.optional(Self::ident)?,
+            source,
+            on: self.expression(depth)?,
+            clauses: self.list(1, |g| {
+                Ok(MergeClause {
+                    matched: g.u.arbitrary()?,
+                    condition: g.optional(|g| g.expression(depth))?,
+                    action: match g.u.choose_index(3)? {
+                        0 => MergeAction::Update {
+                            set: g.assignments()?,
+                        },
+                        1 => MergeAction::Delete,
+                        _ => MergeAction::Insert {
+                            columns: g.list(0, Self::ident)?,
+                            values: g.list(1, |g| g.expression(depth))?,
+                        },
+                    },
+                })
+            })?,
+            span: Span::default(),
+        })
+    }
+
+    fn column_definition(&mut self) -> Result<ColumnDefinition> {
+        Ok(ColumnDefinition {
+            name: self.ident()?,
+            data_type: self.data_type()?,
+            options: self.list(0, |g| {
+                Ok(match g.u.choose_index(6)? {
+                    0 => ColumnOption::NotNull,
+                    1 => ColumnOption::Null,
+                    2 => ColumnOption::PrimaryKey,
+                    3 => ColumnOption::Unique,
+                    4 => ColumnOption::Default(g.expression(g.options.max_depth)?),
+                    _ => ColumnOption::AutoIncrement,
+                })
+            })?,
+            span: Span::default(),
+        })
+    }
+
+    fn data_type(&mut self) -> Result<DataType> {
+        Ok(match self.u.choose_index(18)? {
+            0 => DataType::SmallInt,
+            1 => DataType::Integer,
+            2 => DataType::BigInt,
+            3 => DataType::Real,
+            4 => DataType::DoublePrecision,
+            5 => DataType::Float(self.u.arbitrary()?),
+            6 => {
+                let precision = self.u.arbitrary()?;
+                // A scale needs a precision before it
+                let scale = match precision {
+                    Some(_) => self.u.arbitrary()?,
+                    None => None,
+                };
+                DataType::Decimal { precision, scale }
+            }
+            7 => DataType::Char(self.u.arbitrary()?),
+            8 => DataType::Varchar(self.u.arbitrary()?),
+            9 => DataType::Text,
+            10 => DataType::Boolean,
+            11 => DataType::Date,
+            12 => DataType::Time,
+            13 => DataType::Timestamp,
+            14 => DataType::SmallSerial,
+            15 => DataType::Serial,
+            16 => DataType::BigSerial,
+            _ => DataType::Custom(self.u.choose(CUSTOM_TYPES)?.to_string()),
+        })
+    }
+
+    // At depth 0 only operands without children are generated
+    fn expression(&mut self, depth: usize) -> Result<Expression> {
+        let kinds = if depth == 0 { 4 } else { 10 };
+        Ok(match self.u.choose_index(kinds)? {
+            0 => Expression::Literal {
+                value: self.literal()?,
+                span: Span::default(),
+            },
+            1 => Expression::Column(self.ident()?),
+            2 => Expression::QualifiedColumn {
+                table: self.ident()?,
+                column: self.ident()?,
+            },
+            3 => self.parameter()?,
+            4 => Expression::Function {
+                name: Ident::new(*self.u.choose(FUNCTIONS)?),
+                args: self.list(0, |g| g.expression(depth - 1))?,
+                span: Span::default(),
+            },
+            5 => Expression::Grouping {
+                args: self.list(1, |g| g.expression(depth - 1))?,
+                span: Span::default(),
+            },
+            6 => Expression::Unary {
+                operator: if self.u.arbitrary()? {
+                    UnaryOperator::Not
+                } else {
+                    UnaryOperator::Minus
+                },
+                operand: Box::new(self.expression(depth - 1)?),
+                span: Span::default(),
+            },
+            7 => Expression::Cast {
+                expression: Box::new(self.expression(depth - 1)?),
+                data_type: self.data_type()?,
+                span: Span::default(),
+            },
+            8 => Expression::InList {
+                expression: Box::new(self.expression(depth - 1)?),
+                list: self.list(1, |g| g.expression(depth - 1))?,
+                negated: self.u.arbitrary()?,
+                span: Span::default(),
+            },
+            _ => Expression::Binary {
+                left: Box::new(self.expression(depth - 1)?),
+                operator: self.u.choose(BINARY_OPERATORS)?.clone(),
+                right: Box::new(self.expression(depth - 1)?),
+                span: Span::default(),
+            },
+        })
+    }
+
+    fn parameter(&mut self) -> Result<Expression> {
+        Ok(Expression::Parameter {
+            name: match self.u.arbitrary::<Option<u16>>()? {
+                Some(number) => format!("${}", number),
+                None => "?".to_string(),
+            },
+            span: Span::default(),
+        })
+    }
+
+    // LIMIT and OFFSET take a non-negative number or a parameter
+    fn row_count(&mut self) -> Result<Expression> {
+        if self.u.arbitrary()? {
+            return self.parameter();
+        }
+        Ok(Expression::Literal {
+            value: Literal::Number(self.u.int_in_range(0..=i64::MAX)?),
+            span: Span::default(),
+        })
+    }
+
+    // Numbers are never negative: -1 reads back as the minus operator applied to 1
+    fn literal(&mut self) -> Result<Literal> {
+        Ok(match self.u.choose_index(5)? {
+            0 => Literal::String(self.u.arbitrary()?),
+            1 => Literal::Number(self.u.int_in_range(0..=i64::MAX)?),
+            2 => {
+                let value = self.u.arbitrary::<f64>()?.abs();
+                Literal::Float(if value.is_finite() { value } else { 0.5 })
+            }
+            3 => Literal::Boolean(self.u.arbitrary()?),
+            _ => Literal::Null,
+        })
+    }
+
+    fn grouping_element(&mut self, depth: usize) -> Result<GroupingElement> {
+        if depth == 0 {
+            return Ok(GroupingElement::Expression(self.expression(0)?));
+        }
+        Ok(match self.u.choose_index(5)? {
+            0 => GroupingElement::Expression(self.expression(depth - 1)?),
+            1 => GroupingElement::Set(self.grouping_set(depth - 1)?),
+            2 => GroupingElement::Rollup(self.list(1, |g| g.rollup_item(depth - 1))?),
+            3 => GroupingElement::Cube(self.list(1, |g| g.rollup_item(depth - 1))?),
+            _ => GroupingElement::GroupingSets(self.list(1, |g| g.grouping_element(depth - 1))?),
+        })
+    }
+
+    // ROLLUP and CUBE take expressions and sets, but no nested grouping elements
+    fn rollup_item(&mut self, depth: usize) -> Result<GroupingElement> {
+        Ok(if self.u.arbitrary()? {
+            GroupingElement::Expression(self.expression(depth)?)
+        } else {
+            GroupingElement::Set(self.grouping_set(depth)?)
+        })
+    }
+
+    // (a) is a parenthesized expression, so a set has no element or at least two
+    fn grouping_set(&mut self, depth: usize) -> Result<Vec<Expression>> {
+        let mut expressions = self.list(0, |g| g.expression(depth))?;
+        if expressions.len() == 1 {
+            expressions.push(self.expression(depth)?);
+        }
+        Ok(expressions)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{arbitrary_expression, arbitrary_statement, GenerateOptions};
+    use crate::ast::{Expression, Statement};
+    use crate::format::{format_statement, FormatOptions};
+    use crate::parser::{parse_expression, parse_sql};
+    use arbitrary::{Arbitrary, Unstructured};
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+
+    fn depth(expression: &Expression) -> usize {
+        match expression {
+            Expression::Function { args, .. } | Expression::Grouping { args, .. } => {
+                1 + args.iter().map(depth).max().unwrap_or(0)
+            }
+            Expression::Binary { left, right, .. } => 1 + depth(left).max(depth(right)),
+            Expression::Unary { operand, .. } => 1 + depth(operand),
+            Expression::Cast { expression, .. } => 1 + depth(expression),
+            Expression::InList {
+                expression, list, ..
+            } => {
+                1 + list
+                    .iter()
+                    .map(depth)
+                    .max()
+                    .unwrap_or(0)
+                    .max(depth(expression))
+            }
+            _ => 0,
+        }
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(512))]
+
+        #[test]
+        fn test_statements_read_back(bytes in vec(any::<u8>(), 0..4096)) {
+            let statement = Statement::arbitrary(&mut Unstructured::new(&bytes)).unwrap();
+            let sql = statement.to_string();
+            let reparsed = parse_sql(&sql)
+                .map_err(|error| TestCaseError::fail(format!("{}\n{}", sql, error)))?;
+            prop_assert_eq!(&reparsed, &statement, "{}", sql);
+
+            let formatted = format_statement(&statement, &FormatOptions::default());
+            let reparsed = parse_sql(&formatted)
+                .map_err(|error| TestCaseError::fail(format!("{}\n{}", formatted, error)))?;
+            prop_assert_eq!(&reparsed, &statement, "{}", formatted);
+        }
+
+        #[test]
+        fn test_expressions_read_back(bytes in vec(any::<u8>(), 0..1024)) {
+            let expression = Expression::arbitrary(&mut Unstructured::new(&bytes)).unwrap();
+            let sql = expression.to_string();
+            let reparsed = parse_expression(&sql)
+                .map_err(|error| TestCaseError::fail(format!("{}\n{}", sql, error)))?;
+            prop_assert_eq!(reparsed, expression, "{}", sql);
+        }
+
+        #[test]
+        fn test_limits(bytes in vec(any::<u8>(), 0..1024), max_depth in 0..4usize) {
+            let options = GenerateOptions { max_depth, max_items: 1 };
+            let expression = arbitrary_expression(&mut Unstructured::new(&bytes), &options).unwrap();
+            prop_assert!(depth(&expression) <= max_depth, "{}", expression);
+
+            let statement = arbitrary_statement(&mut Unstructured::new(&bytes), &options).unwrap();
+            if let Statement::Insert { values, .. } = &statement {
+                prop_assert_eq!(values.len(), 1);
+            }
+        }
+    }
+}
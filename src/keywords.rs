@@ -0,0 +1,759 @@
+use crate::dialect::Dialect;
+
+// How a word may be used where the grammar expects an identifier
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeywordKind {
+    // Only as a quoted identifier
+    Reserved,
+    // A keyword somewhere, but still accepted as a bare identifier
+    NonReserved,
+}
+
+// The reserved words of SQL92 (ISO/IEC 9075:1992, 5.2). No dialect here reserves all of them,
+// so they do not decide what parses: each dialect rejects its own reserved words, and generic
+// SQL only those that every dialect rejects. What follows SQL92 is the output: Display quotes
+// these words along with every other keyword, so the SQL it writes is valid SQL92 and reads
+// back in any dialect
+const SQL92_RESERVED: &[&str] = &[
+    "ABSOLUTE",
+    "ACTION",
+    "ADD",
+    "ALL",
+    "ALLOCATE",
+    "ALTER",
+    "AND",
+    "ANY",
+    "ARE",
+    "AS",
+    "ASC",
+    "ASSERTION",
+    "AT",
+    "AUTHORIZATION",
+    "AVG",
+    "BEGIN",
+    "BETWEEN",
+    "BIT",
+    "BIT_LENGTH",
+    "BOTH",
+    "BY",
+    "CASCADE",
+    "CASCADED",
+    "CASE",
+    "CAST",
+    "CATALOG",
+    "CHAR",
+    "CHARACTER",
+    "CHARACTER_LENGTH",
+    "CHAR_LENGTH",
+    "CHECK",
+    "CLOSE",
+    "COALESCE",
+    "COLLATE",
+    "COLLATION",
+    "COLUMN",
+    "COMMIT",
+    "CONNECT",
+    "CONNECTION",
+    "CONSTRAINT",
+    "CONSTRAINTS",
+    "CONTINUE",
+    "CONVERT",
+    "CORRESPONDING",
+    "COUNT",
+    "CREATE",
+    "CROSS",
+    "CURRENT",
+    "CURRENT_DATE",
+    "CURRENT_TIME",
+    "CURRENT_TIMESTAMP",
+    "CURRENT_USER",
+    "CURSOR",
+    "DATE",
+    "DAY",
+    "DEALLOCATE",
+    "DEC",
+    "DECIMAL",
+    "DECLARE",
+    "DEFAULT",
+    "DEFERRABLE",
+    "DEFERRED",
+    "DELETE",
+    "DESC",
+    "DESCRIBE",
+    "DESCRIPTOR",
+    "DIAGNOSTICS",
+    "DISCONNECT",
+    "DISTINCT",
+    "DOMAIN",
+    "DOUBLE",
+    "DROP",
+    "ELSE",
+    "END",
+    "ESCAPE",
+    "EXCEPT",
+    "EXCEPTION",
+    "EXEC",
+    "EXECUTE",
+    "EXISTS",
+    "EXTERNAL",
+    "EXTRACT",
+    "FALSE",
+    "FETCH",
+    "FIRST",
+    "FLOAT",
+    "FOR",
+    "FOREIGN",
+    "FOUND",
+    "FROM",
+    "FULL",
+    "GET",
+    "GLOBAL",
+    "GO",
+    "GOTO",
+    "GRANT",
+    "GROUP",
+    "HAVING",
+    "HOUR",
+    "IDENTITY",
+    "IMMEDIATE",
+    "IN",
+    "INDICATOR",
+    "INITIALLY",
+    "INNER",
+    "INPUT",
+    "INSENSITIVE",
+    "INSERT",
+    "INT",
+    "INTEGER",
+    "INTERSECT",
+    "INTERVAL",
+    "INTO",
+    "IS",
+    "ISOLATION",
+    "JOIN",
+    "KEY",
+    "LANGUAGE",
+    "LAST",
+    "LEADING",
+    "LEFT",
+    "LEVEL",
+    "LIKE",
+    "LOCAL",
+    "LOWER",
+    "MATCH",
+    "MAX",
+    "MIN",
+    "MINUTE",
+    "MODULE",
+    "MONTH",
+    "NAMES",
+    "NATIONAL",
+    "NATURAL",
+    "NCHAR",
+    "NEXT",
+    "NO",
+    "NOT",
+    "NULL",
+    "NULLIF",
+    "NUMERIC",
+    "OCTET_LENGTH",
+    "OF",
+    "ON",
+    "ONLY",
+    "OPEN",
+    "OPTION",
+    "OR",
+    "ORDER",
+    "OUTER",
+    "OUTPUT",
+    "OVERLAPS",
+    "PAD",
+    "PARTIAL",
+    "POSITION",
+    "PRECISION",
+    "PREPARE",
+    "PRIMARY",
+    "PRIOR",
+    "PRIVILEGES",
+    "PROCEDURE",
+    "PUBLIC",
+    "READ",
+    "REAL",
+    "REFERENCES",
+    "RELATIVE",
+    "RESTRICT",
+    "REVOKE",
+    "RIGHT",
+    "ROLLBACK",
+    "ROWS",
+    "SCHEMA",
+    "SCROLL",
+    "SECOND",
+    "SECTION",
+    "SELECT",
+    "SESSION",
+    "SESSION_USER",
+    "SET",
+    "SIZE",
+    "SMALLINT",
+    "SOME",
+    "SPACE",
+    "SQL",
+    "SQLCODE",
+    "SQLERROR",
+    "SQLSTATE",
+    "SUBSTRING",
+    "SUM",
+    "SYSTEM_USER",
+    "TABLE",
+    "TEMPORARY",
+    "THEN",
+    "TIME",
+    "TIMESTAMP",
+    "TIMEZONE_HOUR",
+    "TIMEZONE_MINUTE",
+    "TO",
+    "TRAILING",
+    "TRANSACTION",
+    "TRANSLATE",
+    "TRANSLATION",
+    "TRIM",
+    "TRUE",
+    "UNION",
+    "UNIQUE",
+    "UNKNOWN",
+    "UPDATE",
+    "UPPER",
+    "USAGE",
+    "USER",
+    "USING",
+    "VALUE",
+    "VALUES",
+    "VARCHAR",
+    "VARYING",
+    "VIEW",
+    "WHEN",
+    "WHENEVER",
+    "WHERE",
+    "WITH",
+    "WORK",
+    "WRITE",
+    "YEAR",
+    "ZONE",
+];
+
+// Words with a meaning in this grammar that SQL92 does not reserve
+const NON_RESERVED: &[&str] = &[
+    "AUTOINCREMENT",
+    "AUTO_INCREMENT",
+    "CONFLICT",
+    "CUBE",
+    "DO",
+    "DUPLICATE",
+    "GROUPING",
+    "ILIKE",
+    "LIMIT",
+    "MATCHED",
+    "MERGE",
+    "NOTHING",
+    "OFFSET",
+    "ROLLUP",
+    "SETS",
+];
+
+// Reserved words of each dialect. Every list is sorted so that it can be searched
+const POSTGRESQL_RESERVED: &[&str] = &[
+    "ALL",
+    "ANALYSE",
+    "ANALYZE",
+    "AND",
+    "ANY",
+    "ARRAY",
+    "AS",
+    "ASC",
+    "ASYMMETRIC",
+    "AUTHORIZATION",
+    "BINARY",
+    "BOTH",
+    "CASE",
+    "CAST",
+    "CHECK",
+    "COLLATE",
+    "COLLATION",
+    "COLUMN",
+    "CONCURRENTLY",
+    "CONSTRAINT",
+    "CREATE",
+    "CROSS",
+    "CURRENT_CATALOG",
+    "CURRENT_DATE",
+    "CURRENT_ROLE",
+    "CURRENT_SCHEMA",
+    "CURRENT_TIME",
+    "CURRENT_TIMESTAMP",
+    "CURRENT_USER",
+    "DEFAULT",
+    "DEFERRABLE",
+    "DESC",
+    "DISTINCT",
+    "DO",
+    "ELSE",
+    "END",
+    "EXCEPT",
+    "FALSE",
+    "FETCH",
+    "FOR",
+    "FOREIGN",
+    "FREEZE",
+    "FROM",
+    "FULL",
+    "GRANT",
+    "GROUP",
+    "HAVING",
+    "ILIKE",
+    "IN",
+    "INITIALLY",
+    "INNER",
+    "INTERSECT",
+    "INTO",
+    "IS",
+    "ISNULL",
+    "JOIN",
+    "LATERAL",
+    "LEADING",
+    "LEFT",
+    "LIKE",
+    "LIMIT",
+    "LOCALTIME",
+    "LOCALTIMESTAMP",
+    "NATURAL",
+    "NOT",
+    "NOTNULL",
+    "NULL",
+    "OFFSET",
+    "ON",
+    "ONLY",
+    "OR",
+    "ORDER",
+    "OUTER",
+    "OVERLAPS",
+    "PLACING",
+    "PRIMARY",
+    "REFERENCES",
+    "RETURNING",
+    "RIGHT",
+    "SELECT",
+    "SESSION_USER",
+    "SIMILAR",
+    "SOME",
+    "SYMMETRIC",
+    "TABLE",
+    "TABLESAMPLE",
+    "THEN",
+    "TO",
+    "TRAILING",
+    "TRUE",
+    "UNION",
+    "UNIQUE",
+    "USER",
+    "USING",
+    "VARIADIC",
+    "VERBOSE",
+    "WHEN",
+    "WHERE",
+    "WINDOW",
+    "WITH",
+];
+
+const MYSQL_RESERVED: &[&str] = &[
+    "ADD",
+    "ALL",
+    "ALTER",
+    "ANALYZE",
+    "AND",
+    "AS",
+    "ASC",
+    "AUTO_INCREMENT",
+    "BETWEEN",
+    "BIGINT",
+    "BINARY",
+    "BLOB",
+    "BOTH",
+    "BY",
+    "CALL",
+    "CASCADE",
+    "CASE",
+    "CHANGE",
+    "CHAR",
+    "CHARACTER",
+    "CHECK",
+    "COLLATE",
+    "COLUMN",
+    "CONDITION",
+    "CONSTRAINT",
+    "CONTINUE",
+    "CONVERT",
+    "CREATE",
+    "CROSS",
+    "CUBE",
+    "CURRENT_DATE",
+    "CURRENT_TIME",
+    "CURRENT_TIMESTAMP",
+    "CURRENT_USER",
+    "CURSOR",
+    "DATABASE",
+    "DATABASES",
+    "DECIMAL",
+    "DECLARE",
+    "DEFAULT",
+    "DELETE",
+    "DESC",
+    "DESCRIBE",
+    "DISTINCT",
+    "DIV",
+    "DOUBLE",
+    "DROP",
+    "DUAL",
+    "EACH",
+    "ELSE",
+    "ELSEIF",
+    "EXISTS",
+    "EXIT",
+    "EXPLAIN",
+    "FALSE",
+    "FETCH",
+    "FLOAT",
+    "FOR",
+    "FORCE",
+    "FOREIGN",
+    "FROM",
+    "FULLTEXT",
+    "GRANT",
+    "GROUP",
+    "GROUPING",
+    "HAVING",
+    "IF",
+    "IGNORE",
+    "IN",
+    "INDEX",
+    "INNER",
+    "INSERT",
+    "INT",
+    "INTEGER",
+    "INTERVAL",
+    "INTO",
+    "IS",
+    "ITERATE",
+    "JOIN",
+    "KEY",
+    "KEYS",
+    "KILL",
+    "LEADING",
+    "LEAVE",
+    "LEFT",
+    "LIKE",
+    "LIMIT",
+    "LINES",
+    "LOAD",
+    "LOCK",
+    "LONG",
+    "LOOP",
+    "MATCH",
+    "MOD",
+    "NATURAL",
+    "NOT",
+    "NULL",
+    "NUMERIC",
+    "ON",
+    "OPTION",
+    "OR",
+    "ORDER",
+    "OUT",
+    "OUTER",
+    "PRIMARY",
+    "PROCEDURE",
+    "RANGE",
+    "READ",
+    "REAL",
+    "REFERENCES",
+    "REGEXP",
+    "RENAME",
+    "REPEAT",
+    "REPLACE",
+    "REQUIRE",
+    "RESTRICT",
+    "RETURN",
+    "REVOKE",
+    "RIGHT",
+    "RLIKE",
+    "SCHEMA",
+    "SELECT",
+    "SET",
+    "SHOW",
+    "SMALLINT",
+    "SPATIAL",
+    "SQL",
+    "TABLE",
+    "THEN",
+    "TO",
+    "TRAILING",
+    "TRIGGER",
+    "TRUE",
+    "UNION",
+    "UNIQUE",
+    "UNLOCK",
+    "UNSIGNED",
+    "UPDATE",
+    "USAGE",
+    "USE",
+    "USING",
+    "VALUES",
+    "VARCHAR",
+    "WHEN",
+    "WHERE",
+    "WHILE",
+    "WITH",
+    "WRITE",
+    "XOR",
+    "ZEROFILL",
+];
+
+const SQLITE_RESERVED: &[&str] = &[
+    "ADD",
+    "ALL",
+    "ALTER",
+    "AND",
+    "AS",
+    "AUTOINCREMENT",
+    "BETWEEN",
+    "CASE",
+    "CHECK",
+    "COLLATE",
+    "COMMIT",
+    "CONSTRAINT",
+    "CREATE",
+    "DEFAULT",
+    "DEFERRABLE",
+    "DELETE",
+    "DISTINCT",
+    "DROP",
+    "ELSE",
+    "ESCAPE",
+    "EXCEPT",
+    "EXISTS",
+    "FOREIGN",
+    "FROM",
+    "GROUP",
+    "HAVING",
+    "IN",
+    "INDEX",
+    "INSERT",
+    "INTERSECT",
+    "INTO",
+    "IS",
+    "ISNULL",
+    "JOIN",
+    "LIMIT",
+    "NOT",
+    "NOTHING",
+    "NOTNULL",
+    "NULL",
+    "ON",
+    "OR",
+    "ORDER",
+    "PRIMARY",
+    "REFERENCES",
+    "RETURNING",
+    "SELECT",
+    "SET",
+    "TABLE",
+    "THEN",
+    "TO",
+    "TRANSACTION",
+    "UNION",
+    "UNIQUE",
+    "UPDATE",
+    "USING",
+    "VALUES",
+    "WHEN",
+    "WHERE",
+];
+
+fn contains(words: &[&str], word: &str) -> bool {
+    words
+        .binary_search_by(|keyword| {
+            keyword
+                .bytes()
+                .cmp(word.bytes().map(|b| b.to_ascii_uppercase()))
+        })
+        .is_ok()
+}
+
+// Generic SQL reserves only the words that every dialect reserves, so that it reads the names
+// that any of them accepts bare
+fn is_reserved(dialect: Dialect, word: &str) -> bool {
+    match dialect {
+        Dialect::Generic => [POSTGRESQL_RESERVED, MYSQL_RESERVED, SQLITE_RESERVED]
+            .iter()
+            .all(|words| contains(words, word)),
+        Dialect::PostgreSql => contains(POSTGRESQL_RESERVED, word),
+        Dialect::MySql => contains(MYSQL_RESERVED, word),
+        Dialect::Sqlite => contains(SQLITE_RESERVED, word),
+    }
+}
+
+// Reserved if the dialect reserves the word, non-reserved if it is a keyword of this grammar or
+// of another dialect, and None for any other word
+pub fn keyword_kind(dialect: Dialect, word: &str) -> Option<KeywordKind> {
+    if is_reserved(dialect, word) {
+        Some(KeywordKind::Reserved)
+    } else if is_keyword(word) {
+        Some(KeywordKind::NonReserved)
+    } else {
+        None
+    }
+}
+
+// A keyword in any dialect
+pub fn is_keyword(word: &str) -> bool {
+    [
+        SQL92_RESERVED,
+        NON_RESERVED,
+        POSTGRESQL_RESERVED,
+        MYSQL_RESERVED,
+        SQLITE_RESERVED,
+    ]
+    .iter()
+    .any(|words| contains(words, word))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        is_keyword, keyword_kind, KeywordKind, MYSQL_RESERVED, NON_RESERVED, POSTGRESQL_RESERVED,
+        SQL92_RESERVED, SQLITE_RESERVED,
+    };
+    use crate::ast::{Expression, Ident, Statement};
+    use crate::dialect::Dialect;
+    use crate::error::ParseError;
+    use crate::parser::{parse_expression, parse_sql, parse_statement_with_options, ParseOptions};
+
+    #[test]
+    fn test_lists_are_sorted_upper_case() {
+        for words in [
+            SQL92_RESERVED,
+            NON_RESERVED,
+            POSTGRESQL_RESERVED,
+            MYSQL_RESERVED,
+            SQLITE_RESERVED,
+        ] {
+            assert!(
+                words.windows(2).all(|pair| pair[0] < pair[1]),
+                "{:?}",
+                words
+            );
+            assert!(words.iter().all(|word| *word == word.to_ascii_uppercase()));
+        }
+    }
+
+    #[test]
+    fn test_classify_words() {
+        assert_eq!(
+            keyword_kind(Dialect::Generic, "select"),
+            Some(KeywordKind::Reserved)
+        );
+        // Generic reserves only what every dialect reserves: DATE only SQL92 and MySQL do
+        assert_eq!(
+            keyword_kind(Dialect::Generic, "Date"),
+            Some(KeywordKind::NonReserved)
+        );
+        assert_eq!(
+            keyword_kind(Dialect::MySql, "date"),
+            Some(KeywordKind::NonReserved)
+        );
+        assert_eq!(
+            keyword_kind(Dialect::Generic, "matched"),
+            Some(KeywordKind::NonReserved)
+        );
+        // KEY is reserved by MySQL but not PostgreSQL
+        assert_eq!(
+            keyword_kind(Dialect::PostgreSql, "date"),
+            Some(KeywordKind::NonReserved)
+        );
+        assert_eq!(
+            keyword_kind(Dialect::MySql, "key"),
+            Some(KeywordKind::Reserved)
+        );
+        assert_eq!(
+            keyword_kind(Dialect::PostgreSql, "key"),
+            Some(KeywordKind::NonReserved)
+        );
+        assert_eq!(keyword_kind(Dialect::Generic, "users"), None);
+        assert_eq!(keyword_kind(Dialect::Generic, "sélect"), None);
+        assert!(!is_keyword("select_count"));
+    }
+
+    #[test]
+    fn test_reserved_words_are_not_bare_identifiers() {
+        for sql in [
+            "SELECT * FROM select;",
+            "DELETE FROM t WHERE order = 1;",
+            "SELECT * FROM t ORDER BY from;",
+            "INSERT INTO t VALUES ('1') ON CONFLICT (distinct) DO NOTHING;",
+        ] {
+            let error = parse_sql(sql).unwrap_err();
+            assert!(matches!(error, ParseError::Semantic { .. }), "{}", sql);
+            assert!(
+                error
+                    .to_string()
+                    .contains("is a reserved keyword in generic SQL; quote it"),
+                "{}",
+                error
+            );
+        }
+        let error = parse_sql("DELETE FROM t WHERE a = 1 AND when = 2;").unwrap_err();
+        assert_eq!(error.location().column, 31);
+
+        // Quoted, in any case, they are identifiers
+        let statement = parse_sql("SELECT * FROM \"select\" WHERE \"ORDER\" = 1;").unwrap();
+        assert!(matches!(statement, Statement::Select { table, .. } if table == "select"));
+        // Function names and non-reserved keywords are accepted bare
+        assert!(parse_expression("count(id) = max(rollup)").is_ok());
+        assert!(parse_sql("SELECT * FROM matched ORDER BY offset;").is_ok());
+    }
+
+    #[test]
+    fn test_generic_accepts_names_some_dialect_accepts() {
+        // Reserved by SQL92 or by one dialect, but not by all of them
+        for sql in [
+            "SELECT * FROM t WHERE date = value AND count = level;",
+            "SELECT * FROM t WHERE level = 1 GROUP BY date, value ORDER BY count;",
+            "UPDATE t SET value = 'x', level = 'y' WHERE key = 1;",
+            "INSERT INTO t VALUES ('1') ON CONFLICT (value) DO NOTHING;",
+            "DELETE FROM user WHERE date = 1;",
+        ] {
+            assert!(parse_sql(sql).is_ok(), "{}", sql);
+        }
+        // Each dialect still rejects its own reserved words
+        let mysql = ParseOptions {
+            dialect: Dialect::MySql,
+            ..ParseOptions::default()
+        };
+        assert!(parse_statement_with_options("DELETE FROM t WHERE key = 1;", &mysql).is_err());
+    }
+
+    #[test]
+    fn test_display_quotes_keywords() {
+        for (name, expected) in [
+            ("date", "\"date\""),
+            ("Select", "\"Select\""),
+            ("rollup", "\"rollup\""),
+            ("key", "\"key\""),
+            ("users", "users"),
+        ] {
+            let column = Expression::Column(Ident::new(name));
+            assert_eq!(column.to_string(), expected);
+            assert_eq!(parse_expression(&column.to_string()).unwrap(), column);
+        }
+    }
+}
@@ -2,5 +2,36 @@ extern crate pest;
 extern crate pest_derive;
 
 pub mod ast;
+pub mod binder;
+pub mod catalog;
+#[cfg(test)]
+mod corpus;
+pub mod dialect;
+mod display;
+pub mod error;
 pub mod expression;
+pub mod fingerprint;
+pub mod format;
+#[cfg(feature = "arbitrary")]
+pub mod generate;
+pub mod keywords;
+pub mod lint;
 pub mod parser;
+mod pratt;
+pub mod reader;
+pub mod references;
+pub mod rewrite;
+pub mod simplify;
+pub mod typecheck;
+pub mod visitor;
+
+pub use dialect::Dialect;
+pub use error::ParseError;
+pub use fingerprint::fingerprint;
+pub use format::{format_sql, FormatOptions};
+pub use parser::{
+    parse_data_type, parse_expression, parse_order_by, parse_script, parse_sql_with,
+    parse_statement, ParseOptions, ParsedScript,
+};
+pub use reader::StatementReader;
+pub use references::{references, Reference, References};
@@ -0,0 +1,529 @@
+use crate::ast::{BinaryOperator, Expression, Literal, Span, Statement};
+use crate::dialect::Dialect;
+use crate::error::ParseError;
+use crate::format::comments;
+use crate::parser::{parse_script_with_options, ParseOptions};
+use crate::visitor::{walk_expression, walk_statement, Flow, Visitor};
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        })
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Rule {
+    pub id: &'static str,
+    // Used unless the rule is allowed or denied
+    pub severity: Severity,
+    pub message: &'static str,
+}
+
+pub static DELETE_WITHOUT_WHERE: Rule = Rule {
+    id: "delete-without-where",
+    severity: Severity::Error,
+    message: "DELETE without WHERE removes every row of the table",
+};
+
+pub static UPDATE_WITHOUT_WHERE: Rule = Rule {
+    id: "update-without-where",
+    severity: Severity::Error,
+    message: "UPDATE without WHERE changes every row of the table",
+};
+
+pub static SELECT_STAR: Rule = Rule {
+    id: "select-star",
+    severity: Severity::Warning,
+    message: "SELECT * depends on the columns and column order of the table",
+};
+
+pub static LIMIT_WITHOUT_ORDER_BY: Rule = Rule {
+    id: "limit-without-order-by",
+    severity: Severity::Warning,
+    message: "LIMIT or OFFSET without ORDER BY returns an unpredictable set of rows",
+};
+
+pub static NULL_COMPARISON: Rule = Rule {
+    id: "null-comparison",
+    severity: Severity::Warning,
+    message: "comparison with NULL is never true; use IS NULL or IS NOT NULL",
+};
+
+pub static CONSTANT_WHERE: Rule = Rule {
+    id: "constant-where",
+    severity: Severity::Warning,
+    message: "WHERE condition does not depend on the row",
+};
+
+pub static RULES: &[&Rule] = &[
+    &DELETE_WITHOUT_WHERE,
+    &UPDATE_WITHOUT_WHERE,
+    &SELECT_STAR,
+    &LIMIT_WITHOUT_ORDER_BY,
+    &NULL_COMPARISON,
+    &CONSTANT_WHERE,
+];
+
+pub fn rule(id: &str) -> Option<&'static Rule> {
+    RULES.iter().copied().find(|rule| rule.id == id)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct LintOptions {
+    // Rule ids that are not reported
+    pub allow: Vec<String>,
+    // Rule ids that are reported as errors
+    pub deny: Vec<String>,
+    pub dialect: Dialect,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Lint {
+    pub rule: &'static Rule,
+    pub severity: Severity,
+    pub span: Span,
+}
+
+impl fmt::Display for Lint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}: {}: {} [{}]",
+            self.span.line, self.span.column, self.severity, self.rule.message, self.rule.id
+        )
+    }
+}
+
+// The lints of every statement that parsed, and one diagnostic for each part of the script
+// that did not parse or each lint directive that is not understood
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LintReport {
+    pub lints: Vec<Lint>,
+    pub diagnostics: Vec<ParseError>,
+}
+
+impl LintReport {
+    // True if nothing was reported as an error
+    pub fn is_ok(&self) -> bool {
+        self.diagnostics.is_empty()
+            && self
+                .lints
+                .iter()
+                .all(|lint| lint.severity != Severity::Error)
+    }
+}
+
+// Lints every statement of a script. Comments of the form `-- lint: allow(id, ...) deny(id)`
+// configure the rules for the whole script and take precedence over the options.
+// A statement that does not parse does not stop the others from being linted
+pub fn lint_sql(sql: &str, options: &LintOptions) -> LintReport {
+    let parse_options = ParseOptions {
+        dialect: options.dialect,
+        ..ParseOptions::default()
+    };
+    let script = parse_script_with_options(sql, &parse_options);
+    let mut diagnostics = script.diagnostics;
+
+    let mut levels = Levels::new(options);
+    for comment in comments(sql) {
+        let text = comment.text.trim_start_matches('-').trim_start();
+        if let Some(directives) = text.strip_prefix("lint:") {
+            if let Err(message) = levels.apply_directives(directives) {
+                let span = pest::Span::new(sql, comment.start, comment.end)
+                    .expect("comments lie within the input");
+                diagnostics.push(ParseError::semantic(message, span));
+            }
+        }
+    }
+    diagnostics.sort_by_key(|error| error.location().offset);
+
+    let lints = script
+        .statements
+        .iter()
+        .flat_map(|statement| levels.lint(statement))
+        .collect();
+    LintReport { lints, diagnostics }
+}
+
+pub fn lint_statement(statement: &Statement, options: &LintOptions) -> Vec<Lint> {
+    Levels::new(options).lint(statement)
+}
+
+// The severity of each rule in RULES, or None if the rule is allowed
+struct Levels(Vec<Option<Severity>>);
+
+impl Levels {
+    fn new(options: &LintOptions) -> Self {
+        let mut levels = Levels(RULES.iter().map(|rule| Some(rule.severity)).collect());
+        for id in &options.allow {
+            levels.set(id, None);
+        }
+        for id in &options.deny {
+            levels.set(id, Some(Severity::Error));
+        }
+        levels
+    }
+
+    // Returns false for an unknown rule id
+    fn set(&mut self, id: &str, level: Option<Severity>) -> bool {
+        match RULES.iter().position(|rule| rule.id == id) {
+            Some(index) => {
+                self.0[index] = level;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn level(&self, rule: &Rule) -> Option<Severity> {
+        RULES
+            .iter()
+            .position(|known| known.id == rule.id)
+            .and_then(|index| self.0[index])
+    }
+
+    fn apply_directives(&mut self, mut directives: &str) -> Result<(), String> {
+        loop {
+            directives = directives.trim_start();
+            if directives.is_empty() {
+                return Ok(());
+            }
+            let (level, rest) = if let Some(rest) = directives.strip_prefix("allow(") {
+                (None, rest)
+            } else if let Some(rest) = directives.strip_prefix("deny(") {
+                (Some(Severity::Error), rest)
+            } else {
+                return Err(format!(
+                    "expected allow(...) or deny(...), found {}",
+                    directives
+                ));
+            };
+            let Some((ids, rest)) = rest.split_once(')') else {
+                return Err("unclosed lint directive".to_string());
+            };
+            for id in ids.split(',').map(str::trim) {
+                if !self.set(id, level) {
+                    return Err(format!("unknown lint rule: {}", id));
+                }
+            }
+            directives = rest;
+        }
+    }
+
+    fn lint(&self, statement: &Statement) -> Vec<Lint> {
+        let mut linter = Linter {
+            levels: self,
+            lints: Vec::new(),
+        };
+        walk_statement(&mut linter, statement);
+        linter.lints.sort_by_key(|lint| lint.span.start);
+        linter.lints
+    }
+}
+
+struct Linter<'a> {
+    levels: &'a Levels,
+    lints: Vec<Lint>,
+}
+
+impl Linter<'_> {
+    fn report(&mut self, rule: &'static Rule, span: Span) {
+        if let Some(severity) = self.levels.level(rule) {
+            self.lints.push(Lint {
+                rule,
+                severity,
+                span,
+            });
+        }
+    }
+
+    fn check_where(&mut self, where_clause: &Option<Expression>) {
+        if let Some(condition) = where_clause {
+            if is_constant(condition) {
+                self.report(&CONSTANT_WHERE, condition.span());
+            }
+        }
+    }
+}
+
+impl Visitor for Linter<'_> {
+    fn pre_visit_statement(&mut self, statement: &Statement) -> Flow {
+        match statement {
+            Statement::Select {
+                where_clause,
+                order_by,
+                limit,
+                offset,
+                span,
+                ..
+            } => {
+                // The grammar has no column list, so every SELECT selects *
+                self.report(&SELECT_STAR, *span);
+                if order_by.is_none() && (limit.is_some() || offset.is_some()) {
+                    self.report(&LIMIT_WITHOUT_ORDER_BY, *span);
+                }
+                self.check_where(where_clause);
+            }
+            Statement::Update {
+                where_clause: None,
+                span,
+                ..
+            } => self.report(&UPDATE_WITHOUT_WHERE, *span),
+            Statement::Delete {
+                where_clause: None,
+                span,
+                ..
+            } => self.report(&DELETE_WITHOUT_WHERE, *span),
+            Statement::Update { where_clause, .. } | Statement::Delete { where_clause, .. } => {
+                self.check_where(where_clause);
+            }
+            _ => {}
+        }
+        Flow::Continue
+    }
+
+    fn pre_visit_expression(&mut self, expression: &Expression) -> Flow {
+        if let Expression::Binary {
+            left,
+            operator: BinaryOperator::Equal | BinaryOperator::NotEqual,
+            right,
+            span,
+        } = expression
+        {
+            let is_null = |operand: &Expression| {
+                matches!(
+                    operand,
+                    Expression::Literal {
+                        value: Literal::Null,
+                        ..
+                    }
+                )
+            };
+            if is_null(left) || is_null(right) {
+                self.report(&NULL_COMPARISON, *span);
+            }
+        }
+        Flow::Continue
+    }
+}
+
+// Stops at the first part of an expression whose value can differ between rows or executions
+struct FindVariable;
+
+impl Visitor for FindVariable {
+    fn pre_visit_expression(&mut self, expression: &Expression) -> Flow {
+        match expression {
+            Expression::Column(_)
+            | Expression::QualifiedColumn { .. }
+            | Expression::Parameter { .. }
+            | Expression::Function { .. }
+            | Expression::Grouping { .. } => Flow::Stop,
+            _ => Flow::Continue,
+        }
+    }
+}
+
+fn is_constant(expression: &Expression) -> bool {
+    walk_expression(&mut FindVariable, expression) == Flow::Continue
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids(sql: &str, options: &LintOptions) -> Vec<(&'static str, Severity)> {
+        let report = lint_sql(sql, options);
+        assert!(report.diagnostics.is_empty(), "{:?}", report.diagnostics);
+        report
+            .lints
+            .iter()
+            .map(|lint| (lint.rule.id, lint.severity))
+            .collect()
+    }
+
+    fn default_ids(sql: &str) -> Vec<&'static str> {
+        ids(sql, &LintOptions::default())
+            .into_iter()
+            .map(|(id, _)| id)
+            .collect()
+    }
+
+    #[test]
+    fn test_delete_and_update_without_where() {
+        assert_eq!(
+            ids("DELETE FROM users;", &LintOptions::default()),
+            [("delete-without-where", Severity::Error)]
+        );
+        assert_eq!(
+            default_ids("UPDATE users SET name = 'a';"),
+            ["update-without-where"]
+        );
+        assert!(default_ids("DELETE FROM users WHERE id = 1;").is_empty());
+        assert!(default_ids("UPDATE users SET name = 'a' WHERE id = $1;").is_empty());
+    }
+
+    #[test]
+    fn test_select_rules() {
+        assert_eq!(default_ids("SELECT * FROM users;"), ["select-star"]);
+        assert_eq!(
+            default_ids("SELECT * FROM users LIMIT 10;"),
+            ["select-star", "limit-without-order-by"]
+        );
+        assert_eq!(
+            default_ids("SELECT * FROM users ORDER BY id LIMIT 10 OFFSET 5;"),
+            ["select-star"]
+        );
+    }
+
+    #[test]
+    fn test_null_comparison() {
+        let options = LintOptions {
+            allow: vec!["select-star".to_string()],
+            ..LintOptions::default()
+        };
+        assert_eq!(
+            ids(
+                "SELECT * FROM users WHERE deleted_at = NULL OR NULL <> name;",
+                &options
+            ),
+            [
+                ("null-comparison", Severity::Warning),
+                ("null-comparison", Severity::Warning)
+            ]
+        );
+        assert!(ids("SELECT * FROM users WHERE name = 'NULL';", &options).is_empty());
+    }
+
+    #[test]
+    fn test_constant_where() {
+        assert_eq!(
+            default_ids("DELETE FROM users WHERE 1 = 1;"),
+            ["constant-where"]
+        );
+        assert_eq!(
+            default_ids("UPDATE users SET a = 'b' WHERE TRUE OR -1 < 2;"),
+            ["constant-where"]
+        );
+        assert!(default_ids("DELETE FROM users WHERE id = 1 OR 1 = 1;").is_empty());
+        assert!(
+            default_ids("DELETE FROM users WHERE now() > CAST('2024-01-01' AS DATE);").is_empty()
+        );
+    }
+
+    #[test]
+    fn test_nested_statements_are_linted() {
+        assert_eq!(
+            default_ids(
+                "MERGE INTO t USING (SELECT * FROM s LIMIT 1) AS s ON t.id = s.id \
+                 WHEN MATCHED THEN DELETE;"
+            ),
+            ["select-star", "limit-without-order-by"]
+        );
+        assert_eq!(
+            default_ids("PREPARE wipe AS DELETE FROM users;"),
+            ["delete-without-where"]
+        );
+    }
+
+    #[test]
+    fn test_options_allow_and_deny() {
+        let options = LintOptions {
+            allow: vec!["delete-without-where".to_string()],
+            deny: vec!["select-star".to_string()],
+            ..LintOptions::default()
+        };
+        assert_eq!(
+            ids("DELETE FROM users; SELECT * FROM users;", &options),
+            [("select-star", Severity::Error)]
+        );
+    }
+
+    #[test]
+    fn test_directives_configure_the_script() {
+        let sql = "-- lint: allow(select-star, limit-without-order-by) deny(null-comparison)\n\
+                   SELECT * FROM users WHERE a = NULL LIMIT 1;";
+        assert_eq!(
+            ids(sql, &LintOptions::default()),
+            [("null-comparison", Severity::Error)]
+        );
+
+        // A directive overrides the options
+        let options = LintOptions {
+            deny: vec!["select-star".to_string()],
+            ..LintOptions::default()
+        };
+        assert!(ids("SELECT * FROM t; -- lint: allow(select-star)", &options).is_empty());
+    }
+
+    #[test]
+    fn test_bad_directives_and_sql_are_errors() {
+        let report = lint_sql(
+            "-- lint: allow(no-such-rule)\nSELECT * FROM t;",
+            &LintOptions::default(),
+        );
+        assert!(!report.is_ok());
+        let [error] = &report.diagnostics[..] else {
+            panic!("expected one diagnostic: {:?}", report.diagnostics);
+        };
+        assert!(error
+            .to_string()
+            .contains("unknown lint rule: no-such-rule"));
+        assert_eq!(error.location().line, 1);
+        assert_eq!(report.lints.len(), 1);
+        assert!(!lint_sql("-- lint: forbid(x)", &LintOptions::default()).is_ok());
+        assert!(!lint_sql("DELETE users;", &LintOptions::default()).is_ok());
+    }
+
+    #[test]
+    fn test_statements_around_a_syntax_error_are_linted() {
+        let report = lint_sql(
+            "DELETE FROM a;\nDELETE b;\nUPDATE c SET x = 'y';\nSELECT * FROM d LIMIT 1;",
+            &LintOptions::default(),
+        );
+        let [error] = &report.diagnostics[..] else {
+            panic!("expected one diagnostic: {:?}", report.diagnostics);
+        };
+        assert_eq!(error.location().line, 2);
+        let lints: Vec<_> = report
+            .lints
+            .iter()
+            .map(|lint| (lint.span.line, lint.rule.id))
+            .collect();
+        assert_eq!(
+            lints,
+            [
+                (1, "delete-without-where"),
+                (3, "update-without-where"),
+                (4, "select-star"),
+                (4, "limit-without-order-by"),
+            ]
+        );
+        // Warnings alone are not a failure
+        assert!(lint_sql("SELECT * FROM t;", &LintOptions::default()).is_ok());
+    }
+
+    #[test]
+    fn test_lint_location_and_display() {
+        let lints = lint_sql(
+            "SELECT * FROM t WHERE id = 1;\nDELETE FROM t;",
+            &LintOptions::default(),
+        )
+        .lints;
+        assert_eq!(
+            lints.last().unwrap().to_string(),
+            "2:1: error: DELETE without WHERE removes every row of the table [delete-without-where]"
+        );
+        assert_eq!(rule("select-star"), Some(&SELECT_STAR));
+        assert_eq!(rule("nope"), None);
+    }
+}
@@ -1,13 +1,70 @@
-use rust_sql::parser;
-use std::io::{self, Write};
+use rust_sql::format::{format_sql, CommaPlacement, FormatOptions, KeywordCase};
+use rust_sql::lint::{self, lint_sql, LintOptions};
+use rust_sql::parser::{self, ParseOptions};
+use std::env;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::process::ExitCode;
 
-fn main() {
+const USAGE: &str = "usage:
+    rust-sql                            read statements interactively
+    rust-sql parse [options]            parse stdin and print the statements
+    rust-sql fmt [options] [files...]   format files in place, or stdin to stdout
+    rust-sql lint [options] [files...]  report suspicious statements in files or stdin
+
+parse options:
+    --json              print the statements and diagnostics as JSON
+    --dialect <name>    generic (default), postgresql, mysql or sqlite
+
+fmt options:
+    --check             list files that are not formatted instead of rewriting them
+    --lowercase         write keywords in lower case
+    --indent <n>        spaces per indentation level (default 4)
+    --max-width <n>     maximum line width (default 80)
+    --leading-commas    put commas at the start of wrapped list items
+    --compact           keep statements that fit on one line
+
+lint options:
+    --allow <rule>      do not report the rule
+    --deny <rule>       report the rule as an error
+    --dialect <name>    generic (default), postgresql, mysql or sqlite
+
+lint rules: delete-without-where, update-without-where, select-star,
+    limit-without-order-by, null-comparison, constant-where.
+    A `-- lint: allow(rule, ...) deny(rule, ...)` comment configures a single file.
+    The exit status is 1 if an error is reported";
+
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        None => {
+            repl();
+            ExitCode::SUCCESS
+        }
+        Some("parse") => parse(&args[1..]),
+        Some("fmt") => fmt(&args[1..]),
+        Some("lint") => lint(&args[1..]),
+        Some("-h" | "--help") => {
+            println!("{}", USAGE);
+            ExitCode::SUCCESS
+        }
+        Some(other) => usage_error(&format!("unknown command: {}", other)),
+    }
+}
+
+fn usage_error(message: &str) -> ExitCode {
+    eprintln!("{}\n\n{}", message, USAGE);
+    ExitCode::from(2)
+}
+
+fn repl() {
     loop {
         print!("> ");
         io::stdout().flush().unwrap();
 
         let mut input = String::new();
-        if io::stdin().read_line(&mut input).is_err() {
+        // Stop at end of input as well as on errors
+        if !matches!(io::stdin().read_line(&mut input), Ok(read) if read > 0) {
             break;
         }
 
@@ -16,14 +73,221 @@ fn main() {
             continue;
         }
 
-        match parser::parse_sql(input) {
-            Ok(statement) => {
-                println!("Parsed successfully:");
-                println!("{:#?}", statement);
+        // Keep going after a bad statement so that every error is reported
+        let script = parser::parse_script(input);
+        for statement in &script.statements {
+            println!("Parsed successfully:");
+            println!("{:#?}", statement);
+        }
+        for e in &script.diagnostics {
+            eprintln!("Parse failed: {}", e);
+        }
+    }
+}
+
+fn parse(args: &[String]) -> ExitCode {
+    let mut json = false;
+    let mut options = ParseOptions::default();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--json" => json = true,
+            "--dialect" => match args.next().map(|name| name.parse()) {
+                Some(Ok(dialect)) => options.dialect = dialect,
+                Some(Err(e)) => return usage_error(&e),
+                None => return usage_error("--dialect expects a name"),
+            },
+            other => return usage_error(&format!("unknown option: {}", other)),
+        }
+    }
+    if json && !cfg!(feature = "serde") {
+        return usage_error("--json needs rust-sql built with the serde feature");
+    }
+
+    let mut sql = String::new();
+    if let Err(e) = io::stdin().read_to_string(&mut sql) {
+        eprintln!("<stdin>: {}", e);
+        return ExitCode::FAILURE;
+    }
+    let script = parser::parse_script_with_options(&sql, &options);
+    if json {
+        #[cfg(feature = "serde")]
+        print_json(&script);
+    } else {
+        for statement in &script.statements {
+            println!("{:#?}", statement);
+        }
+        for e in &script.diagnostics {
+            eprintln!("{}", e);
+        }
+    }
+    if script.is_ok() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+// Statements and diagnostics together, so that consumers get one document per input
+#[cfg(feature = "serde")]
+fn print_json(script: &parser::ParsedScript) {
+    let json = serde_json::to_string_pretty(script).expect("the AST serializes to JSON");
+    println!("{}", json);
+}
+
+fn fmt(args: &[String]) -> ExitCode {
+    let mut options = FormatOptions::default();
+    let mut check = false;
+    let mut files = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--check" => check = true,
+            "--lowercase" => options.keyword_case = KeywordCase::Lower,
+            "--leading-commas" => options.comma_placement = CommaPlacement::Leading,
+            "--compact" => options.clause_per_line = false,
+            "--indent" | "--max-width" => {
+                let Some(value) = args.next().and_then(|value| value.parse().ok()) else {
+                    return usage_error(&format!("{} expects a number", arg));
+                };
+                if arg == "--indent" {
+                    options.indent = value;
+                } else {
+                    options.max_width = value;
+                }
+            }
+            flag if flag.starts_with("--") => {
+                return usage_error(&format!("unknown option: {}", flag));
             }
+            file => files.push(file),
+        }
+    }
+
+    if files.is_empty() {
+        return fmt_stdin(&options, check);
+    }
+    let mut failed = false;
+    for path in files {
+        match fmt_file(path, &options, check) {
+            Ok(true) => {}
+            Ok(false) => {
+                println!("{}: not formatted", path);
+                failed = true;
+            }
+            Err(e) => {
+                eprintln!("{}: {}", path, e);
+                failed = true;
+            }
+        }
+    }
+    if failed {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+// Ok(false) when checking a file that is not formatted
+fn fmt_file(path: &str, options: &FormatOptions, check: bool) -> Result<bool, String> {
+    let sql = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let formatted = format_sql(&sql, options).map_err(|e| e.to_string())?;
+    if formatted == sql {
+        Ok(true)
+    } else if check {
+        Ok(false)
+    } else {
+        fs::write(path, formatted).map_err(|e| e.to_string())?;
+        Ok(true)
+    }
+}
+
+fn fmt_stdin(options: &FormatOptions, check: bool) -> ExitCode {
+    let mut sql = String::new();
+    if let Err(e) = io::stdin().read_to_string(&mut sql) {
+        eprintln!("<stdin>: {}", e);
+        return ExitCode::FAILURE;
+    }
+    match format_sql(&sql, options) {
+        Ok(formatted) if check && formatted != sql => {
+            println!("<stdin>: not formatted");
+            ExitCode::FAILURE
+        }
+        Ok(_) if check => ExitCode::SUCCESS,
+        Ok(formatted) => {
+            print!("{}", formatted);
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("<stdin>: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn lint(args: &[String]) -> ExitCode {
+    let mut options = LintOptions::default();
+    let mut files = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--allow" | "--deny" => {
+                let Some(id) = args.next() else {
+                    return usage_error(&format!("{} expects a rule", arg));
+                };
+                if lint::rule(id).is_none() {
+                    return usage_error(&format!("unknown lint rule: {}", id));
+                }
+                if arg == "--allow" {
+                    options.allow.push(id.clone());
+                } else {
+                    options.deny.push(id.clone());
+                }
+            }
+            "--dialect" => match args.next().map(|name| name.parse()) {
+                Some(Ok(dialect)) => options.dialect = dialect,
+                Some(Err(e)) => return usage_error(&e),
+                None => return usage_error("--dialect expects a name"),
+            },
+            flag if flag.starts_with("--") => {
+                return usage_error(&format!("unknown option: {}", flag));
+            }
+            file => files.push(file),
+        }
+    }
+
+    let mut failed = false;
+    if files.is_empty() {
+        let mut sql = String::new();
+        if let Err(e) = io::stdin().read_to_string(&mut sql) {
+            eprintln!("<stdin>: {}", e);
+            return ExitCode::FAILURE;
+        }
+        failed = !lint_source("<stdin>", &sql, &options);
+    }
+    for path in files {
+        match fs::read_to_string(path) {
+            Ok(sql) => failed |= !lint_source(path, &sql, &options),
             Err(e) => {
-                eprintln!("Parse failed: {}", e);
+                eprintln!("{}: {}", path, e);
+                failed = true;
             }
         }
     }
+    if failed {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+// Prints the lints and parse errors of one file. Returns false if an error was reported
+fn lint_source(path: &str, sql: &str, options: &LintOptions) -> bool {
+    let report = lint_sql(sql, options);
+    for error in &report.diagnostics {
+        eprintln!("{}: {}", path, error);
+    }
+    for lint in &report.lints {
+        println!("{}:{}", path, lint);
+    }
+    report.is_ok()
 }
@@ -1,371 +1,1107 @@
 use crate::ast::{
-    BinaryOperator, Expression, GroupBy, Literal, OrderBy, OrderByItem, OrderDirection, Statement,
-    UnaryOperator,
+    ColumnDefinition, ColumnOption, ConflictTarget, DataType, Expression, GroupBy, GroupingElement,
+    Ident, Literal, MergeAction, MergeClause, MergeSource, ObjectName, OnConflict, OrderBy,
+    OrderByItem, OrderDirection, Span, Statement,
 };
-use pest::{iterators::Pair, Parser};
+use crate::dialect::{normalize_identifier, unquote_identifier, Dialect};
+use crate::error::ParseError;
+use crate::pratt::build_expression;
+use pest::{
+    iterators::{Pair, Pairs},
+    Parser, Position, RuleType,
+};
+use std::borrow::Cow;
+use std::iter::Peekable;
+use std::ops::Range;
 
 #[derive(pest_derive::Parser)]
 #[grammar = "sql.pest"]
 pub struct SQLParser;
 
-pub fn parse_sql(sql: &str) -> Result<Statement, Box<pest::error::Error<Rule>>> {
-    let pairs = SQLParser::parse(Rule::statement, sql)?;
-    let statement_pair = pairs.peek().unwrap();
-    if statement_pair.as_rule() != Rule::statement {
-        unreachable!();
-    }
-    let inner_statement = statement_pair.into_inner().peek().unwrap();
-    Ok(match inner_statement.as_rule() {
-        Rule::select_statement => {
-            let mut inner_rules = inner_statement.into_inner();
-            // The first rule is select_clause, then from_clause, then optional clauses, then semicolon
-            inner_rules.next(); // Consume the select_clause (SELECT *)
-            let from_clause_pair = inner_rules.next().unwrap(); // This is the from_clause (FROM users)
-
-            let mut from_inner_rules = from_clause_pair.into_inner();
-            from_inner_rules.next(); // Consume the 'FROM' keyword
-            let table_name = from_inner_rules.next().unwrap().as_str(); // This should be the identifier
-
-            // Parse optional clauses
-            let mut where_clause = None;
-            let mut group_by = None;
-            let mut order_by = None;
-            let mut limit = None;
-
-            for clause in inner_rules {
-                match clause.as_rule() {
-                    Rule::where_clause => {
-                        let mut where_inner = clause.into_inner();
-                        where_inner.next(); // Consume WHERE keyword
-                        let expr_pair = where_inner.next().unwrap();
-                        where_clause = Some(build_expression_from_sql_parser(expr_pair));
-                    }
-                    Rule::group_by_clause => {
-                        let mut group_by_inner = clause.into_inner();
-                        group_by_inner.next(); // Consume GROUP keyword
-                        group_by_inner.next(); // Consume BY keyword
-                        let identifier_list = group_by_inner.next().unwrap(); // identifier_list
-                        let columns = identifier_list
-                            .into_inner()
-                            .map(|p| p.as_str().to_string())
-                            .collect();
-                        group_by = Some(GroupBy { columns });
-                    }
-                    Rule::order_by_clause => {
-                        let mut order_by_inner = clause.into_inner();
-                        order_by_inner.next(); // Consume ORDER keyword
-                        order_by_inner.next(); // Consume BY keyword
-                        let items = order_by_inner
-                            .filter(|pair| pair.as_rule() == Rule::order_by_item)
-                            .map(|pair| {
-                                let mut item_inner = pair.into_inner();
-                                let column = item_inner.next().unwrap().as_str().to_string();
-                                let direction = match item_inner.next() {
-                                    Some(dir_pair)
-                                        if dir_pair.as_rule() == Rule::order_direction =>
-                                    {
-                                        match dir_pair.into_inner().next().unwrap().as_rule() {
-                                            Rule::ASC => OrderDirection::Asc,
-                                            Rule::DESC => OrderDirection::Desc,
-                                            _ => OrderDirection::Asc,
-                                        }
-                                    }
-                                    _ => OrderDirection::Asc,
-                                };
-
-                                OrderByItem { column, direction }
-                            })
-                            .collect();
-                        order_by = Some(OrderBy { items });
-                    }
-                    Rule::limit_clause => {
-                        let mut limit_inner = clause.into_inner();
-                        limit_inner.next(); // Consume LIMIT keyword
-                        let limit_value = limit_inner.next().unwrap().as_str().parse().unwrap();
-                        limit = Some(limit_value);
+// Safe for the 2 MiB stack of a spawned thread even in debug builds
+pub const DEFAULT_MAX_DEPTH: usize = 64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseOptions {
+    // Maximum nesting depth of parentheses and of expression trees, so that input fails with an
+    // error instead of overflowing the stack when it is parsed, rendered, walked or dropped.
+    // Parentheses are checked before parsing. Raising it requires a larger stack
+    pub max_depth: usize,
+    pub dialect: Dialect,
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        ParseOptions {
+            max_depth: DEFAULT_MAX_DEPTH,
+            dialect: Dialect::Generic,
+        }
+    }
+}
+
+pub fn parse_sql(sql: &str) -> Result<Statement, ParseError> {
+    parse_statement(sql)
+}
+
+pub fn parse_sql_with(dialect: Dialect, sql: &str) -> Result<Statement, ParseError> {
+    let options = ParseOptions {
+        dialect,
+        ..ParseOptions::default()
+    };
+    parse_statement_with_options(sql, &options)
+}
+
+pub fn parse_statement(sql: &str) -> Result<Statement, ParseError> {
+    parse_statement_with_options(sql, &ParseOptions::default())
+}
+
+pub fn parse_statement_with_options(
+    sql: &str,
+    options: &ParseOptions,
+) -> Result<Statement, ParseError> {
+    parse_input(Rule::statement, sql, options, |cx, mut children| {
+        build_statement(cx, children.required()?)
+    })
+}
+
+pub fn parse_expression(input: &str) -> Result<Expression, ParseError> {
+    parse_expression_with_options(input, &ParseOptions::default())
+}
+
+pub fn parse_expression_with_options(
+    input: &str,
+    options: &ParseOptions,
+) -> Result<Expression, ParseError> {
+    parse_input(
+        Rule::expression_input,
+        input,
+        options,
+        |cx, mut children| build_expression(cx, children.required()?),
+    )
+}
+
+pub fn parse_data_type(input: &str) -> Result<DataType, ParseError> {
+    parse_data_type_with_options(input, &ParseOptions::default())
+}
+
+pub fn parse_data_type_with_options(
+    input: &str,
+    options: &ParseOptions,
+) -> Result<DataType, ParseError> {
+    parse_input(Rule::data_type_input, input, options, |cx, mut children| {
+        build_data_type(cx, children.required()?)
+    })
+}
+
+// Accepts a list of ORDER BY items, with or without the leading ORDER BY keywords
+pub fn parse_order_by(input: &str) -> Result<OrderBy, ParseError> {
+    parse_order_by_with_options(input, &ParseOptions::default())
+}
+
+pub fn parse_order_by_with_options(
+    input: &str,
+    options: &ParseOptions,
+) -> Result<OrderBy, ParseError> {
+    parse_input(Rule::order_by_input, input, options, build_order_by)
+}
+
+// Best-effort result of parsing a script: every statement that parsed, and one diagnostic
+// for each part of the input that did not
+#[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ParsedScript {
+    pub statements: Vec<Statement>,
+    pub diagnostics: Vec<ParseError>,
+}
+
+impl ParsedScript {
+    pub fn is_ok(&self) -> bool {
+        self.diagnostics.is_empty()
+    }
+}
+
+pub fn parse_script(sql: &str) -> ParsedScript {
+    parse_script_with_options(sql, &ParseOptions::default())
+}
+
+pub fn parse_script_with_options(sql: &str, options: &ParseOptions) -> ParsedScript {
+    let mut script = ParsedScript {
+        statements: Vec::new(),
+        diagnostics: Vec::new(),
+    };
+    let mut normalized = options.dialect.normalize(sql);
+    // The depth check has to run before the grammar. Statements that nest too deep are blanked
+    // out, keeping their offsets and lines, so that the grammar only sees the others
+    let violations = nesting_violations(&normalized, options.max_depth);
+    if !violations.is_empty() {
+        let mut bytes = normalized.into_owned().into_bytes();
+        for (range, error) in violations {
+            for byte in &mut bytes[range] {
+                if *byte != b'\n' {
+                    *byte = b' ';
+                }
+            }
+            script.diagnostics.push(error.relocate(sql, 0));
+        }
+        normalized = Cow::Owned(String::from_utf8(bytes).expect("only ASCII bytes are written"));
+    }
+    let script_pair = match SQLParser::parse(Rule::script, &normalized) {
+        Ok(mut pairs) => pairs.next(),
+        Err(error) => {
+            script
+                .diagnostics
+                .push(ParseError::from(error).relocate(sql, 0));
+            return script;
+        }
+    };
+
+    let lines = LineIndex::new(sql);
+    let cx = Context {
+        source: sql,
+        dialect: options.dialect,
+        lines: &lines,
+        max_depth: options.max_depth,
+    };
+    for pair in script_pair.into_iter().flat_map(|pair| pair.into_inner()) {
+        match pair.as_rule() {
+            Rule::script_statement => {
+                match Children::of(pair)
+                    .required()
+                    .and_then(|pair| build_statement(cx, pair))
+                {
+                    Ok(statement) => script.statements.push(statement),
+                    Err(error) => script.diagnostics.push(error.relocate(sql, 0)),
+                }
+            }
+            Rule::skipped_input => {
+                // Parse the skipped text on its own to find out why it is not a statement
+                let start = pair.as_span().start();
+                let error = match parse_statement_with_options(cx.text(&pair), options) {
+                    Err(error) => error.relocate(sql, start),
+                    Ok(_) => {
+                        ParseError::semantic("unexpected input", pair.as_span()).relocate(sql, 0)
                     }
-                    Rule::semicolon => {
-                        // Skip semicolon
+                };
+                script.diagnostics.push(error);
+            }
+            _ => {}
+        }
+    }
+    script
+        .diagnostics
+        .sort_by_key(|error| error.location().offset);
+    script
+}
+
+// Runs the grammar for one of the SOI ~ ... ~ EOI entry rules over the input as normalized
+// by the dialect, and builds the result from the children of the wrapping pair. Errors are
+// located in the input as written
+fn parse_input<T>(
+    rule: Rule,
+    input: &str,
+    options: &ParseOptions,
+    build: impl FnOnce(Context, Children<Rule>) -> Result<T, ParseError>,
+) -> Result<T, ParseError> {
+    let normalized = options.dialect.normalize(input);
+    let lines = LineIndex::new(input);
+    let cx = Context {
+        source: input,
+        dialect: options.dialect,
+        lines: &lines,
+        max_depth: options.max_depth,
+    };
+    check_nesting_depth(&normalized, options.max_depth)
+        .and_then(|()| SQLParser::parse(rule, &normalized).map_err(ParseError::from))
+        .and_then(|mut pairs| pairs.next().ok_or_else(|| empty_parse_result(&normalized)))
+        .and_then(|input_pair| build(cx, Children::of(input_pair)))
+        .map_err(|error| error.relocate(input, 0))
+}
+
+fn empty_parse_result(input: &str) -> ParseError {
+    let start = Position::from_start(input);
+    ParseError::semantic("empty parse result", start.span(&start))
+}
+
+// Scans the input for parentheses outside string literals, quoted identifiers and comments, and fails at the
+// first one that exceeds the maximum depth
+pub(crate) fn check_nesting_depth(input: &str, max_depth: usize) -> Result<(), ParseError> {
+    match nesting_violations(input, max_depth).into_iter().next() {
+        Some((_, error)) => Err(error),
+        None => Ok(()),
+    }
+}
+
+// The statements of a script that nest deeper than the maximum depth, with an error at the
+// first parenthesis too deep in each. A statement ends at a ";" and does not include it
+fn nesting_violations(input: &str, max_depth: usize) -> Vec<(Range<usize>, ParseError)> {
+    let mut violations = Vec::new();
+    let mut statement_start = 0;
+    let mut violation = None;
+    let mut depth = 0usize;
+    let mut chars = input.char_indices().peekable();
+    while let Some((offset, c)) = chars.next() {
+        match c {
+            '\'' | '"' => {
+                for (_, next) in chars.by_ref() {
+                    if next == c {
+                        break;
                     }
-                    _ => {
-                        // Skip other rules like semicolon
+                }
+            }
+            '-' if chars.next_if(|&(_, next)| next == '-').is_some() => {
+                for (_, c) in chars.by_ref() {
+                    if c == '\n' {
+                        break;
                     }
                 }
             }
+            '(' => {
+                depth += 1;
+                if depth > max_depth && violation.is_none() {
+                    let span = pest::Span::new(input, offset, offset + 1)
+                        .expect("offset is a char boundary");
+                    violation = Some(ParseError::semantic(
+                        format!("nesting depth exceeds the limit of {}", max_depth),
+                        span,
+                    ));
+                }
+            }
+            ')' => depth = depth.saturating_sub(1),
+            ';' => {
+                if let Some(error) = violation.take() {
+                    violations.push((statement_start..offset, error));
+                }
+                statement_start = offset + 1;
+                depth = 0;
+            }
+            _ => {}
+        }
+    }
+    if let Some(error) = violation {
+        violations.push((statement_start..input.len(), error));
+    }
+    violations
+}
 
-            Statement::Select {
-                table: table_name.to_string(),
-                where_clause,
-                order_by,
-                group_by,
-                limit,
+// Walks the children of a pair. The grammar guarantees their shape, but a
+// mismatch is reported as a ParseError instead of panicking.
+pub(crate) struct Children<'i, R: RuleType> {
+    pairs: Peekable<Pairs<'i, R>>,
+    span: pest::Span<'i>,
+    rule: R,
+}
+
+impl<'i, R: RuleType> Children<'i, R> {
+    pub(crate) fn of(pair: Pair<'i, R>) -> Self {
+        Children {
+            span: pair.as_span(),
+            rule: pair.as_rule(),
+            pairs: pair.into_inner().peekable(),
+        }
+    }
+
+    // Returns the next child, failing if the pair has no more children
+    pub(crate) fn required(&mut self) -> Result<Pair<'i, R>, ParseError> {
+        self.pairs
+            .next()
+            .ok_or_else(|| ParseError::semantic(format!("incomplete {:?}", self.rule), self.span))
+    }
+
+    // Consumes a keyword child
+    pub(crate) fn consume(&mut self) {
+        self.pairs.next();
+    }
+
+    pub(crate) fn peek(&mut self) -> Option<&Pair<'i, R>> {
+        self.pairs.peek()
+    }
+
+    // Returns the next child only if it was produced by the given rule
+    pub(crate) fn optional(&mut self, rule: R) -> Option<Pair<'i, R>> {
+        self.pairs.next_if(|pair| pair.as_rule() == rule)
+    }
+}
+
+impl<'i, R: RuleType> Iterator for Children<'i, R> {
+    type Item = Pair<'i, R>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.pairs.next()
+    }
+}
+
+pub(crate) fn unexpected<R: RuleType>(pair: &Pair<R>) -> ParseError {
+    ParseError::semantic(format!("unexpected {:?}", pair.as_rule()), pair.as_span())
+}
+
+// Bytes between the character counts that LineIndex keeps
+const CHAR_COUNT_INTERVAL: usize = 64;
+
+// Line starts and character counts of an input, built once per parse so that spans get their
+// line and column without rescanning the input from its start for every node
+pub(crate) struct LineIndex<'a> {
+    input: &'a str,
+    line_starts: Vec<usize>,
+    // The number of characters before every CHAR_COUNT_INTERVAL-th byte
+    char_counts: Vec<usize>,
+}
+
+impl<'a> LineIndex<'a> {
+    pub(crate) fn new(input: &'a str) -> Self {
+        let mut line_starts = vec![0];
+        let mut char_counts = Vec::with_capacity(input.len() / CHAR_COUNT_INTERVAL + 1);
+        let mut chars = 0;
+        for (offset, &byte) in input.as_bytes().iter().enumerate() {
+            if offset.is_multiple_of(CHAR_COUNT_INTERVAL) {
+                char_counts.push(chars);
+            }
+            if is_char_start(byte) {
+                chars += 1;
             }
+            if byte == b'\n' {
+                line_starts.push(offset + 1);
+            }
+        }
+        if input.len().is_multiple_of(CHAR_COUNT_INTERVAL) {
+            char_counts.push(chars);
+        }
+        LineIndex {
+            input,
+            line_starts,
+            char_counts,
+        }
+    }
+
+    fn chars_before(&self, offset: usize) -> usize {
+        let interval = offset / CHAR_COUNT_INTERVAL;
+        let start = interval * CHAR_COUNT_INTERVAL;
+        self.char_counts[interval]
+            + self.input.as_bytes()[start..offset]
+                .iter()
+                .filter(|&&byte| is_char_start(byte))
+                .count()
+    }
+
+    // The 1-based line and column of a byte offset; the column counts characters
+    pub(crate) fn line_col(&self, offset: usize) -> (usize, usize) {
+        let line = self.line_starts.partition_point(|&start| start <= offset);
+        let line_start = self.line_starts[line - 1];
+        let column = self.chars_before(offset) - self.chars_before(line_start) + 1;
+        (line, column)
+    }
+}
+
+fn is_char_start(byte: u8) -> bool {
+    byte & 0xC0 != 0x80
+}
+
+// What the builders need besides the pairs: the dialect, and the input as written. The
+// grammar runs over a normalized copy whose quoted tokens may differ from the input
+#[derive(Clone, Copy)]
+pub(crate) struct Context<'a> {
+    pub(crate) source: &'a str,
+    pub(crate) dialect: Dialect,
+    pub(crate) lines: &'a LineIndex<'a>,
+    pub(crate) max_depth: usize,
+}
+
+impl<'a> Context<'a> {
+    // For pairs that were parsed from the input as written
+    pub(crate) fn of<R: RuleType>(pair: &Pair<'a, R>, lines: &'a LineIndex<'a>) -> Self {
+        Context {
+            source: pair.as_span().get_input(),
+            dialect: Dialect::Generic,
+            lines,
+            max_depth: DEFAULT_MAX_DEPTH,
+        }
+    }
+
+    // Converts the position of a pair to an AST span, excluding trailing whitespace
+    pub(crate) fn span_of<R: RuleType>(&self, pair: &Pair<R>) -> Span {
+        let pest_span = pair.as_span();
+        let (line, column) = self.lines.line_col(pest_span.start());
+        Span {
+            start: pest_span.start(),
+            end: pest_span.start() + pest_span.as_str().trim_end().len(),
+            line,
+            column,
+        }
+    }
+
+    pub(crate) fn text<R: RuleType>(&self, pair: &Pair<R>) -> &'a str {
+        let span = pair.as_span();
+        &self.source[span.start()..span.end()]
+    }
+
+    // Quoted identifiers lose their quotes, so "users" and users are the same name. A bare
+    // identifier must not be a reserved word of the dialect
+    pub(crate) fn ident_of<R: RuleType>(&self, pair: &Pair<R>) -> Result<Ident, ParseError> {
+        let text = self.text(pair);
+        if self.dialect.is_reserved(text) {
+            return Err(ParseError::semantic(
+                format!(
+                    "{} is a reserved keyword in {}; quote it to use it as an identifier",
+                    text, self.dialect
+                ),
+                pair.as_span(),
+            ));
+        }
+        Ok(self.function_name_of(pair))
+    }
+
+    // A table name with an optional schema in front of it
+    pub(crate) fn object_name_of<R: RuleType>(
+        &self,
+        pair: &Pair<R>,
+    ) -> Result<ObjectName, ParseError> {
+        let mut names = Children::of(pair.clone());
+        let first = self.ident_of(&names.required()?)?;
+        Ok(match names.next() {
+            Some(name) => ObjectName {
+                schema: Some(first),
+                name: self.ident_of(&name)?,
+            },
+            None => ObjectName::new(first),
+        })
+    }
+
+    // Function names may be reserved words, as in VALUES(column). Bare names are normalized
+    // to NFC so that composed and decomposed spellings of a name are the same name
+    pub(crate) fn function_name_of<R: RuleType>(&self, pair: &Pair<R>) -> Ident {
+        let text = self.text(pair);
+        Ident {
+            value: unquote_identifier(text).unwrap_or_else(|| normalize_identifier(text)),
+            span: self.span_of(pair),
         }
+    }
+
+    // Strips the quotes of a string literal and resolves its escapes
+    pub(crate) fn string_value<R: RuleType>(&self, pair: &Pair<R>) -> String {
+        self.dialect.string_value(self.text(pair))
+    }
+
+    pub(crate) fn unsupported<R: RuleType>(&self, feature: &str, pair: &Pair<R>) -> ParseError {
+        ParseError::semantic(
+            format!("{} is not supported by {}", feature, self.dialect),
+            pair.as_span(),
+        )
+    }
+}
+
+fn build_statement(cx: Context, inner_statement: Pair<Rule>) -> Result<Statement, ParseError> {
+    let rule = inner_statement.as_rule();
+    let pest_span = inner_statement.as_span();
+    let span = cx.span_of(&inner_statement);
+    let mut inner_rules = Children::of(inner_statement);
+    Ok(match rule {
+        Rule::select_statement => build_select_query(cx, inner_rules.required()?, span)?,
         Rule::insert_statement => {
-            let mut inner_rules = inner_statement.into_inner();
-            inner_rules.next(); // INSERT
-            inner_rules.next(); // INTO
-            let table_name = inner_rules.next().unwrap().as_str(); // identifier
-            let values_clause = inner_rules.next().unwrap(); // VALUES clause
-            let mut values_inner_rules = values_clause.into_inner();
-            for (i, p) in values_inner_rules.clone().enumerate() {
-                eprintln!("[DEBUG] values_inner_rules[{}]: {:?}", i, p);
-            }
-            let _values_keyword = values_inner_rules.next(); // VALUES
-            let value_list_pair = values_inner_rules.next().unwrap(); // value_list
-            let values = value_list_pair
+            inner_rules.consume(); // INSERT
+            inner_rules.consume(); // INTO
+            let table_name = cx.object_name_of(&inner_rules.required()?)?; // object_name
+            let mut values_inner_rules = Children::of(inner_rules.required()?); // VALUES clause
+            values_inner_rules.consume(); // VALUES
+            let values = values_inner_rules
+                .required()? // value_list
                 .into_inner()
-                .map(|p| p.as_str().trim_matches('\'').to_string())
+                .map(|p| cx.string_value(&p))
                 .collect();
+
+            // Check for optional ON CONFLICT / ON DUPLICATE KEY UPDATE clause
+            let on_conflict = if let Some(conflict_pair) =
+                inner_rules.optional(Rule::on_conflict_clause)
+            {
+                let mut conflict_inner = Children::of(conflict_pair);
+                conflict_inner.consume(); // Consume ON keyword
+                conflict_inner.consume(); // Consume CONFLICT keyword
+                Some(build_on_conflict(cx, conflict_inner.required()?)?)
+            } else if let Some(duplicate_pair) = inner_rules.optional(Rule::on_duplicate_key_clause)
+            {
+                let mut duplicate_inner = Children::of(duplicate_pair);
+                duplicate_inner.consume(); // Consume ON keyword
+                duplicate_inner.consume(); // Consume DUPLICATE keyword
+                duplicate_inner.consume(); // Consume KEY keyword
+                duplicate_inner.consume(); // Consume UPDATE keyword
+                let set = build_expression_assignments(cx, duplicate_inner.required()?)?;
+                Some(OnConflict::DuplicateKeyUpdate { set })
+            } else {
+                None
+            };
+
             Statement::Insert {
-                table: table_name.to_string(),
+                table: table_name,
                 values,
+                on_conflict,
+                span,
             }
         }
         Rule::update_statement => {
-            let mut inner_rules = inner_statement.into_inner();
-            inner_rules.next(); // UPDATE
-            let table_name = inner_rules.next().unwrap().as_str(); // identifier
-            let set_clause = inner_rules.next().unwrap(); // SET clause
-            let mut set_inner_rules = set_clause.into_inner();
-            set_inner_rules.next(); // Consume 'SET'
-            let assignment_list_pair = set_inner_rules.next().unwrap(); // This is the assignment_list
+            inner_rules.consume(); // UPDATE
+            let table_name = cx.object_name_of(&inner_rules.required()?)?; // object_name
+            let mut set_inner_rules = Children::of(inner_rules.required()?); // SET clause
+            set_inner_rules.consume(); // Consume 'SET'
+            let assignment_list_pair = set_inner_rules.required()?; // This is the assignment_list
             let assignments = assignment_list_pair
                 .into_inner()
                 .map(|p| {
-                    let mut assignment_parts = p.into_inner();
-                    let column = assignment_parts.next().unwrap().as_str();
-                    let value = assignment_parts
-                        .next()
-                        .unwrap()
-                        .as_str()
-                        .trim_matches('\'')
-                        .to_string();
-                    (column.to_string(), value)
+                    let mut assignment_parts = Children::of(p);
+                    let column = cx.ident_of(&assignment_parts.required()?)?;
+                    let value = cx.string_value(&assignment_parts.required()?);
+                    Ok((column, value))
                 })
-                .collect();
+                .collect::<Result<_, ParseError>>()?;
 
             // Check for optional WHERE clause
-            let where_clause = if let Some(where_pair) = inner_rules.next() {
-                if where_pair.as_rule() == Rule::where_clause {
-                    let mut where_inner = where_pair.into_inner();
-                    where_inner.next(); // Consume WHERE keyword
-                    let expr_pair = where_inner.next().unwrap();
-                    Some(build_expression_from_sql_parser(expr_pair))
-                } else {
-                    None
-                }
-            } else {
-                None
-            };
+            let where_clause = inner_rules
+                .optional(Rule::where_clause)
+                .map(|pair| build_where_clause(cx, pair))
+                .transpose()?;
 
             Statement::Update {
-                table: table_name.to_string(),
+                table: table_name,
                 set: assignments,
                 where_clause,
+                span,
             }
         }
         Rule::delete_statement => {
-            let mut inner_rules = inner_statement.into_inner();
-            inner_rules.next(); // DELETE
-            inner_rules.next(); // FROM
-            let table_name = inner_rules.next().unwrap().as_str(); // identifier
+            inner_rules.consume(); // DELETE
+            inner_rules.consume(); // FROM
+            let table_name = cx.object_name_of(&inner_rules.required()?)?; // object_name
 
             // Check for optional WHERE clause
-            let where_clause = if let Some(where_pair) = inner_rules.next() {
-                if where_pair.as_rule() == Rule::where_clause {
-                    let mut where_inner = where_pair.into_inner();
-                    where_inner.next(); // Consume WHERE keyword
-                    let expr_pair = where_inner.next().unwrap();
-                    Some(build_expression_from_sql_parser(expr_pair))
-                } else {
-                    None
-                }
-            } else {
-                None
-            };
+            let where_clause = inner_rules
+                .optional(Rule::where_clause)
+                .map(|pair| build_where_clause(cx, pair))
+                .transpose()?;
 
             Statement::Delete {
-                table: table_name.to_string(),
+                table: table_name,
                 where_clause,
+                span,
             }
         }
-        _ => unimplemented!(),
-    })
-}
+        Rule::merge_statement => {
+            inner_rules.consume(); // MERGE
+            inner_rules.consume(); // INTO
+            let table_name = cx.object_name_of(&inner_rules.required()?)?; // object_name
+            let target_alias = inner_rules
+                .optional(Rule::table_alias)
+                .map(|pair| build_table_alias(cx, pair))
+                .transpose()?;
+            inner_rules.consume(); // USING
+            let source = build_merge_source(cx, inner_rules.required()?)?;
+            inner_rules.consume(); // ON
+            let on = build_expression(cx, inner_rules.required()?)?;
+            let clauses = inner_rules
+                .filter(|pair| pair.as_rule() == Rule::merge_when_clause)
+                .map(|pair| build_merge_clause(cx, pair))
+                .collect::<Result<_, ParseError>>()?;
 
-fn build_expression_from_sql_parser(pair: Pair<Rule>) -> Expression {
-    match pair.as_rule() {
-        Rule::expression => {
-            let inner = pair.into_inner().next().unwrap();
-            build_expression_from_sql_parser(inner)
-        }
-        Rule::or_expression => {
-            let mut inner = pair.into_inner();
-            let mut expr = build_expression_from_sql_parser(inner.next().unwrap());
-
-            while let Some(keyword) = inner.next() {
-                if keyword.as_rule() == Rule::OR {
-                    let right = build_expression_from_sql_parser(inner.next().unwrap());
-                    expr = Expression::Binary {
-                        left: Box::new(expr),
-                        operator: BinaryOperator::Or,
-                        right: Box::new(right),
-                    };
-                }
+            Statement::Merge {
+                table: table_name,
+                alias: target_alias,
+                source,
+                on,
+                clauses,
+                span,
             }
-            expr
         }
-        Rule::and_expression => {
-            let mut inner = pair.into_inner();
-            let mut expr = build_expression_from_sql_parser(inner.next().unwrap());
-
-            while let Some(keyword) = inner.next() {
-                if keyword.as_rule() == Rule::AND {
-                    let right = build_expression_from_sql_parser(inner.next().unwrap());
-                    expr = Expression::Binary {
-                        left: Box::new(expr),
-                        operator: BinaryOperator::And,
-                        right: Box::new(right),
-                    };
-                }
+        Rule::prepare_statement => {
+            inner_rules.consume(); // PREPARE
+            let name = cx.ident_of(&inner_rules.required()?)?; // identifier
+            let mut parameter_types = Vec::new();
+            while let Some(type_pair) = inner_rules.optional(Rule::data_type) {
+                parameter_types.push(build_data_type(cx, type_pair)?);
             }
-            expr
-        }
-        Rule::equality_expression => {
-            let mut inner = pair.into_inner();
-            let mut expr = build_expression_from_sql_parser(inner.next().unwrap());
+            inner_rules.consume(); // AS
+            let statement = build_statement(cx, inner_rules.required()?)?;
 
-            while let Some(op_pair) = inner.next() {
-                let operator = match op_pair.as_rule() {
-                    Rule::EQUAL => BinaryOperator::Equal,
-                    Rule::NOT_EQUAL => BinaryOperator::NotEqual,
-                    _ => unreachable!("Unexpected operator rule: {:?}", op_pair.as_rule()),
-                };
-                let right = build_expression_from_sql_parser(inner.next().unwrap());
-                expr = Expression::Binary {
-                    left: Box::new(expr),
-                    operator,
-                    right: Box::new(right),
-                };
+            Statement::Prepare {
+                name,
+                parameter_types,
+                statement: Box::new(statement),
+                span,
             }
-            expr
         }
-        Rule::comparison_expression => {
-            let mut inner = pair.into_inner();
-            let mut expr = build_expression_from_sql_parser(inner.next().unwrap());
+        Rule::execute_statement => {
+            inner_rules.consume(); // EXECUTE
+            let name = cx.ident_of(&inner_rules.required()?)?; // identifier
+            let parameters = match inner_rules.optional(Rule::expression_list) {
+                Some(list_pair) => list_pair
+                    .into_inner()
+                    .map(|pair| build_expression(cx, pair))
+                    .collect::<Result<_, ParseError>>()?,
+                None => Vec::new(),
+            };
 
-            while let Some(op_pair) = inner.next() {
-                let operator = match op_pair.as_rule() {
-                    Rule::LESS_THAN => BinaryOperator::LessThan,
-                    Rule::LESS_THAN_OR_EQUAL => BinaryOperator::LessThanOrEqual,
-                    Rule::GREATER_THAN => BinaryOperator::GreaterThan,
-                    Rule::GREATER_THAN_OR_EQUAL => BinaryOperator::GreaterThanOrEqual,
-                    _ => unreachable!("Unexpected operator rule: {:?}", op_pair.as_rule()),
-                };
-                let right = build_expression_from_sql_parser(inner.next().unwrap());
-                expr = Expression::Binary {
-                    left: Box::new(expr),
-                    operator,
-                    right: Box::new(right),
-                };
+            Statement::Execute {
+                name,
+                parameters,
+                span,
             }
-            expr
         }
-        Rule::additive_expression => {
-            let mut inner = pair.into_inner();
-            let mut expr = build_expression_from_sql_parser(inner.next().unwrap());
+        Rule::deallocate_statement => {
+            // DEALLOCATE [PREPARE] name | ALL
+            let name = inner_rules
+                .filter(|pair| pair.as_rule() == Rule::identifier)
+                .map(|pair| cx.ident_of(&pair))
+                .next()
+                .transpose()?;
 
-            while let Some(op_pair) = inner.next() {
-                let operator = match op_pair.as_rule() {
-                    Rule::ADD => BinaryOperator::Add,
-                    Rule::SUBTRACT => BinaryOperator::Subtract,
-                    _ => unreachable!("Unexpected operator rule: {:?}", op_pair.as_rule()),
-                };
-                let right = build_expression_from_sql_parser(inner.next().unwrap());
-                expr = Expression::Binary {
-                    left: Box::new(expr),
-                    operator,
-                    right: Box::new(right),
-                };
-            }
-            expr
+            Statement::Deallocate { name, span }
         }
-        Rule::multiplicative_expression => {
-            let mut inner = pair.into_inner();
-            let mut expr = build_expression_from_sql_parser(inner.next().unwrap());
+        Rule::create_table_statement => {
+            inner_rules.consume(); // CREATE
+            inner_rules.consume(); // TABLE
+            let table = cx.object_name_of(&inner_rules.required()?)?; // object_name
+            let columns = inner_rules
+                .filter(|pair| pair.as_rule() == Rule::column_definition)
+                .map(|pair| build_column_definition(cx, pair))
+                .collect::<Result<_, ParseError>>()?;
 
-            while let Some(op_pair) = inner.next() {
-                let operator = match op_pair.as_rule() {
-                    Rule::MULTIPLY => BinaryOperator::Multiply,
-                    Rule::DIVIDE => BinaryOperator::Divide,
-                    _ => unreachable!("Unexpected operator rule: {:?}", op_pair.as_rule()),
-                };
-                let right = build_expression_from_sql_parser(inner.next().unwrap());
-                expr = Expression::Binary {
-                    left: Box::new(expr),
-                    operator,
-                    right: Box::new(right),
-                };
+            Statement::CreateTable {
+                table,
+                columns,
+                span,
             }
-            expr
         }
-        Rule::unary_expression => {
-            let mut inner = pair.into_inner();
-            let first = inner.next().unwrap();
+        _ => {
+            return Err(ParseError::semantic(
+                format!("unexpected {:?}", rule),
+                pest_span,
+            ))
+        }
+    })
+}
 
-            match first.as_rule() {
-                Rule::NOT => {
-                    let operand = build_expression_from_sql_parser(inner.next().unwrap());
-                    Expression::Unary {
-                        operator: UnaryOperator::Not,
-                        operand: Box::new(operand),
+fn build_column_definition(cx: Context, pair: Pair<Rule>) -> Result<ColumnDefinition, ParseError> {
+    let span = cx.span_of(&pair);
+    let mut inner = Children::of(pair);
+    let name = cx.ident_of(&inner.required()?)?;
+    let data_type = build_data_type(cx, inner.required()?)?;
+    let options = inner
+        .map(|option| {
+            Ok(match option.as_rule() {
+                Rule::column_not_null => ColumnOption::NotNull,
+                Rule::column_null => ColumnOption::Null,
+                Rule::column_primary_key => ColumnOption::PrimaryKey,
+                Rule::column_unique => ColumnOption::Unique,
+                Rule::column_default => {
+                    let mut default_inner = Children::of(option);
+                    default_inner.consume(); // Consume DEFAULT keyword
+                    ColumnOption::Default(build_expression(cx, default_inner.required()?)?)
+                }
+                Rule::column_auto_increment => {
+                    let keyword = option.as_str().to_uppercase();
+                    if !cx
+                        .dialect
+                        .auto_increment_keywords()
+                        .contains(&keyword.as_str())
+                    {
+                        return Err(cx.unsupported(&keyword, &option));
                     }
+                    ColumnOption::AutoIncrement
                 }
-                Rule::MINUS => {
-                    let operand = build_expression_from_sql_parser(inner.next().unwrap());
-                    Expression::Unary {
-                        operator: UnaryOperator::Minus,
-                        operand: Box::new(operand),
+                _ => return Err(unexpected(&option)),
+            })
+        })
+        .collect::<Result<_, ParseError>>()?;
+
+    Ok(ColumnDefinition {
+        name,
+        data_type,
+        options,
+        span,
+    })
+}
+
+fn build_where_clause(cx: Context, pair: Pair<Rule>) -> Result<Expression, ParseError> {
+    let mut where_inner = Children::of(pair);
+    where_inner.consume(); // Consume WHERE keyword
+    build_expression(cx, where_inner.required()?)
+}
+
+fn build_order_by(cx: Context, children: Children<Rule>) -> Result<OrderBy, ParseError> {
+    // ORDER and BY keywords are skipped by the filter
+    let items = children
+        .filter(|pair| pair.as_rule() == Rule::order_by_item)
+        .map(|pair| {
+            let span = cx.span_of(&pair);
+            let mut item_inner = Children::of(pair);
+            let column = cx.ident_of(&item_inner.required()?)?;
+            let direction = match item_inner.optional(Rule::order_direction) {
+                Some(dir_pair) => match Children::of(dir_pair).required()?.as_rule() {
+                    Rule::DESC => OrderDirection::Desc,
+                    _ => OrderDirection::Asc,
+                },
+                None => OrderDirection::Asc,
+            };
+
+            Ok(OrderByItem {
+                column,
+                direction,
+                span,
+            })
+        })
+        .collect::<Result<_, ParseError>>()?;
+    Ok(OrderBy { items })
+}
+
+pub(crate) fn build_data_type(cx: Context, pair: Pair<Rule>) -> Result<DataType, ParseError> {
+    let mut inner = Children::of(pair);
+    let type_name = cx
+        .text(&inner.required()?)
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+    let modifiers: Vec<u64> = match inner.next() {
+        Some(modifier_pair) => modifier_pair
+            .into_inner()
+            .map(|p| {
+                p.as_str().parse().map_err(|_| {
+                    ParseError::semantic(
+                        format!("type modifier {} is out of range", p.as_str()),
+                        p.as_span(),
+                    )
+                })
+            })
+            .collect::<Result<_, ParseError>>()?,
+        None => Vec::new(),
+    };
+    let length = modifiers.first().copied();
+
+    Ok(match type_name.to_uppercase().as_str() {
+        "SMALLINT" => DataType::SmallInt,
+        "INT" | "INTEGER" => DataType::Integer,
+        "BIGINT" => DataType::BigInt,
+        "REAL" => DataType::Real,
+        "DOUBLE PRECISION" => DataType::DoublePrecision,
+        "FLOAT" => DataType::Float(length),
+        "DECIMAL" | "NUMERIC" => DataType::Decimal {
+            precision: length,
+            scale: modifiers.get(1).copied(),
+        },
+        "CHAR" | "CHARACTER" => DataType::Char(length),
+        "VARCHAR" | "CHARACTER VARYING" => DataType::Varchar(length),
+        "TEXT" => DataType::Text,
+        "BOOLEAN" | "BOOL" => DataType::Boolean,
+        "DATE" => DataType::Date,
+        "TIME" => DataType::Time,
+        "TIMESTAMP" => DataType::Timestamp,
+        "SMALLSERIAL" | "SERIAL2" if cx.dialect.supports_serial_types() => DataType::SmallSerial,
+        "SERIAL" | "SERIAL4" if cx.dialect.supports_serial_types() => DataType::Serial,
+        "BIGSERIAL" | "SERIAL8" if cx.dialect.supports_serial_types() => DataType::BigSerial,
+        _ => DataType::Custom(type_name),
+    })
+}
+
+fn build_select_query(cx: Context, pair: Pair<Rule>, span: Span) -> Result<Statement, ParseError> {
+    let mut inner_rules = Children::of(pair);
+    // The first rule is select_clause, then from_clause, then optional clauses
+    inner_rules.consume(); // Consume the select_clause (SELECT *)
+    let mut from_inner_rules = Children::of(inner_rules.required()?); // This is the from_clause (FROM users)
+    from_inner_rules.consume(); // Consume the 'FROM' keyword
+    let table_name = cx.object_name_of(&from_inner_rules.required()?)?; // object_name
+
+    // Parse optional clauses
+    let mut where_clause = None;
+    let mut group_by = None;
+    let mut order_by = None;
+    let mut limit = None;
+    let mut offset = None;
+
+    for clause in inner_rules {
+        match clause.as_rule() {
+            Rule::where_clause => {
+                where_clause = Some(build_where_clause(cx, clause)?);
+            }
+            Rule::group_by_clause => {
+                let mut group_by_inner = Children::of(clause);
+                group_by_inner.consume(); // Consume GROUP keyword
+                group_by_inner.consume(); // Consume BY keyword
+                let items = group_by_inner
+                    .map(|pair| build_grouping_element(cx, pair))
+                    .collect::<Result<_, ParseError>>()?;
+                group_by = Some(GroupBy { items });
+            }
+            Rule::order_by_clause => {
+                order_by = Some(build_order_by(cx, Children::of(clause))?);
+            }
+            Rule::limit_clause => {
+                let mut limit_inner = Children::of(clause);
+                limit_inner.consume(); // Consume LIMIT keyword
+                let first = row_count(cx, &limit_inner.required()?, "LIMIT")?;
+                match limit_inner.next() {
+                    // LIMIT offset, count
+                    Some(count_pair) => {
+                        if !cx.dialect.supports_limit_comma() {
+                            return Err(cx.unsupported("LIMIT offset, count", &count_pair));
+                        }
+                        offset = Some(first);
+                        limit = Some(row_count(cx, &count_pair, "LIMIT")?);
                     }
+                    None => limit = Some(first),
                 }
-                _ => build_expression_from_sql_parser(first),
             }
-        }
-        Rule::primary_expression => {
-            let inner = pair.into_inner().next().unwrap();
-            match inner.as_rule() {
-                Rule::expression => build_expression_from_sql_parser(inner),
-                _ => build_expression_from_sql_parser(inner),
+            Rule::offset_clause => {
+                if offset.is_some() {
+                    return Err(ParseError::semantic(
+                        "OFFSET cannot follow LIMIT offset, count",
+                        clause.as_span(),
+                    ));
+                }
+                if limit.is_none() && !cx.dialect.supports_offset_without_limit() {
+                    return Err(cx.unsupported("OFFSET without LIMIT", &clause));
+                }
+                let mut offset_inner = Children::of(clause);
+                offset_inner.consume(); // Consume OFFSET keyword
+                offset = Some(row_count(cx, &offset_inner.required()?, "OFFSET")?);
             }
+            _ => {
+                // Skip other rules
+            }
+        }
+    }
+
+    Ok(Statement::Select {
+        table: table_name,
+        where_clause,
+        order_by,
+        group_by,
+        limit,
+        offset,
+        span,
+    })
+}
+
+// The row count of a LIMIT or OFFSET clause: a number literal or a parameter
+fn row_count(cx: Context, pair: &Pair<Rule>, clause: &str) -> Result<Expression, ParseError> {
+    if pair.as_rule() == Rule::parameter {
+        return Ok(Expression::Parameter {
+            name: pair.as_str().to_string(),
+            span: cx.span_of(pair),
+        });
+    }
+    let count: i64 = pair.as_str().parse().map_err(|_| {
+        ParseError::semantic(
+            format!("{} value {} is out of range", clause, pair.as_str()),
+            pair.as_span(),
+        )
+    })?;
+    Ok(Expression::Literal {
+        value: Literal::Number(count),
+        span: cx.span_of(pair),
+    })
+}
+
+fn build_grouping_element(cx: Context, pair: Pair<Rule>) -> Result<GroupingElement, ParseError> {
+    Ok(match pair.as_rule() {
+        Rule::grouping_element | Rule::grouping_set_item => {
+            build_grouping_element(cx, Children::of(pair).required()?)?
         }
-        Rule::string_literal => {
-            let content = pair.as_str();
-            let trimmed = content.trim_matches('\'');
-            Expression::Literal(Literal::String(trimmed.to_string()))
+        Rule::rollup => {
+            let mut inner = Children::of(pair);
+            inner.consume(); // Consume ROLLUP keyword
+            GroupingElement::Rollup(
+                inner
+                    .map(|pair| build_grouping_element(cx, pair))
+                    .collect::<Result<_, _>>()?,
+            )
         }
-        Rule::number_literal => {
-            let num: i64 = pair.as_str().parse().unwrap();
-            Expression::Literal(Literal::Number(num))
+        Rule::cube => {
+            let mut inner = Children::of(pair);
+            inner.consume(); // Consume CUBE keyword
+            GroupingElement::Cube(
+                inner
+                    .map(|pair| build_grouping_element(cx, pair))
+                    .collect::<Result<_, _>>()?,
+            )
         }
-        Rule::float_literal => {
-            let num: f64 = pair.as_str().parse().unwrap();
-            Expression::Literal(Literal::Float(num))
+        Rule::grouping_sets => {
+            let mut inner = Children::of(pair);
+            inner.consume(); // Consume GROUPING keyword
+            inner.consume(); // Consume SETS keyword
+            GroupingElement::GroupingSets(
+                inner
+                    .map(|pair| build_grouping_element(cx, pair))
+                    .collect::<Result<_, _>>()?,
+            )
         }
-        Rule::boolean_literal => {
-            let is_true = pair.as_str().to_uppercase() == "TRUE";
-            Expression::Literal(Literal::Boolean(is_true))
+        Rule::grouping_set => GroupingElement::Set(
+            pair.into_inner()
+                .map(|pair| build_expression(cx, pair))
+                .collect::<Result<_, _>>()?,
+        ),
+        Rule::expression => GroupingElement::Expression(build_expression(cx, pair)?),
+        _ => return Err(unexpected(&pair)),
+    })
+}
+
+fn build_table_alias(cx: Context, pair: Pair<Rule>) -> Result<Ident, ParseError> {
+    // The alias is the last identifier, after the optional AS keyword
+    let span = pair.as_span();
+    pair.into_inner()
+        .last()
+        .map(|alias| cx.ident_of(&alias))
+        .ok_or_else(|| ParseError::semantic("incomplete table_alias", span))?
+}
+
+fn build_merge_source(cx: Context, pair: Pair<Rule>) -> Result<MergeSource, ParseError> {
+    let mut inner = Children::of(pair);
+    let source_pair = inner.required()?;
+    let alias = inner
+        .next()
+        .map(|pair| build_table_alias(cx, pair))
+        .transpose()?;
+    Ok(match source_pair.as_rule() {
+        Rule::select_query => {
+            let span = cx.span_of(&source_pair);
+            MergeSource::Subquery {
+                query: Box::new(build_select_query(cx, source_pair, span)?),
+                alias,
+            }
         }
-        Rule::null_literal => Expression::Literal(Literal::Null),
-        Rule::identifier => Expression::Column(pair.as_str().to_string()),
-        _ => unreachable!("Unexpected rule: {:?}", pair.as_rule()),
+        Rule::object_name => MergeSource::Table {
+            name: cx.object_name_of(&source_pair)?,
+            alias,
+        },
+        _ => return Err(unexpected(&source_pair)),
+    })
+}
+
+fn build_merge_clause(cx: Context, pair: Pair<Rule>) -> Result<MergeClause, ParseError> {
+    let span = pair.as_span();
+    let mut inner = Children::of(pair);
+    inner.consume(); // Consume WHEN keyword
+    let mut matched = true;
+    let mut condition = None;
+    for part in inner {
+        let action = match part.as_rule() {
+            Rule::NOT => {
+                matched = false;
+                continue;
+            }
+            Rule::expression => {
+                condition = Some(build_expression(cx, part)?);
+                continue;
+            }
+            Rule::merge_update => {
+                let mut update_inner = Children::of(part);
+                update_inner.consume(); // Consume UPDATE keyword
+                update_inner.consume(); // Consume SET keyword
+                let set = build_expression_assignments(cx, update_inner.required()?)?;
+                MergeAction::Update { set }
+            }
+            Rule::merge_delete => MergeAction::Delete,
+            Rule::merge_insert => {
+                let mut columns = Vec::new();
+                let mut values = Vec::new();
+                for insert_part in part.into_inner() {
+                    match insert_part.as_rule() {
+                        Rule::identifier_list => {
+                            columns = insert_part
+                                .into_inner()
+                                .map(|p| cx.ident_of(&p))
+                                .collect::<Result<_, _>>()?;
+                        }
+                        Rule::expression_list => {
+                            values = insert_part
+                                .into_inner()
+                                .map(|pair| build_expression(cx, pair))
+                                .collect::<Result<_, _>>()?;
+                        }
+                        _ => {
+                            // Skip INSERT and VALUES keywords
+                        }
+                    }
+                }
+                MergeAction::Insert { columns, values }
+            }
+            _ => {
+                // Skip MATCHED, AND and THEN keywords
+                continue;
+            }
+        };
+        return Ok(MergeClause {
+            matched,
+            condition,
+            action,
+        });
     }
+    Err(ParseError::semantic(
+        "MERGE WHEN clause without an action",
+        span,
+    ))
+}
+
+fn build_on_conflict(cx: Context, pair: Pair<Rule>) -> Result<OnConflict, ParseError> {
+    let rule = pair.as_rule();
+    let span = pair.as_span();
+    let mut inner = Children::of(pair);
+    let target = inner
+        .optional(Rule::conflict_target)
+        .map(|pair| build_conflict_target(cx, pair))
+        .transpose()?;
+    inner.consume(); // Consume DO keyword
+
+    Ok(match (rule, target) {
+        (Rule::conflict_do_nothing, target) => OnConflict::DoNothing { target },
+        (Rule::conflict_do_update, Some(target)) => {
+            inner.consume(); // Consume UPDATE keyword
+            inner.consume(); // Consume SET keyword
+            let set = build_expression_assignments(cx, inner.required()?)?;
+            let where_clause = inner
+                .next()
+                .map(|pair| build_where_clause(cx, pair))
+                .transpose()?;
+            OnConflict::DoUpdate {
+                target,
+                set,
+                where_clause,
+            }
+        }
+        _ => return Err(ParseError::semantic(format!("unexpected {:?}", rule), span)),
+    })
+}
+
+fn build_conflict_target(cx: Context, pair: Pair<Rule>) -> Result<ConflictTarget, ParseError> {
+    let mut inner = Children::of(pair);
+    let first = inner.required()?;
+    Ok(match first.as_rule() {
+        Rule::identifier_list => ConflictTarget::Columns(
+            first
+                .into_inner()
+                .map(|p| cx.ident_of(&p))
+                .collect::<Result<_, _>>()?,
+        ),
+        Rule::ON => {
+            inner.consume(); // Consume CONSTRAINT keyword
+            let constraint_name = cx.ident_of(&inner.required()?)?;
+            ConflictTarget::Constraint(constraint_name)
+        }
+        _ => return Err(unexpected(&first)),
+    })
+}
+
+fn build_expression_assignments(
+    cx: Context,
+    pair: Pair<Rule>,
+) -> Result<Vec<(Ident, Expression)>, ParseError> {
+    pair.into_inner()
+        .map(|p| {
+            let mut assignment_parts = Children::of(p);
+            let column = cx.ident_of(&assignment_parts.required()?)?;
+            let value = build_expression(cx, assignment_parts.required()?)?;
+            Ok((column, value))
+        })
+        .collect()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::ast::Statement;
+    use crate::ast::{BinaryOperator, Literal, Statement, UnaryOperator};
 
     #[test]
     fn test_parse_select_statement() {
@@ -375,11 +1111,13 @@ mod tests {
         assert_eq!(
             result.unwrap(),
             Statement::Select {
-                table: "users".to_string(),
+                table: "users".into(),
                 where_clause: None,
                 order_by: None,
                 group_by: None,
                 limit: None,
+                offset: None,
+                span: Span::default(),
             }
         );
     }
@@ -392,8 +1130,10 @@ mod tests {
         assert_eq!(
             result.unwrap(),
             Statement::Insert {
-                table: "users".to_string(),
-                values: vec!["test_user".to_string(), "test_password".to_string()]
+                table: "users".into(),
+                values: vec!["test_user".to_string(), "test_password".to_string()],
+                on_conflict: None,
+                span: Span::default(),
             }
         );
     }
@@ -406,12 +1146,13 @@ mod tests {
         assert_eq!(
             result.unwrap(),
             Statement::Update {
-                table: "users".to_string(),
+                table: "users".into(),
                 set: vec![
-                    ("name".to_string(), "new_name".to_string()),
-                    ("password".to_string(), "new_password".to_string())
+                    ("name".into(), "new_name".to_string()),
+                    ("password".into(), "new_password".to_string())
                 ],
-                where_clause: None
+                where_clause: None,
+                span: Span::default(),
             }
         );
     }
@@ -424,8 +1165,9 @@ mod tests {
         assert_eq!(
             result.unwrap(),
             Statement::Delete {
-                table: "users".to_string(),
-                where_clause: None
+                table: "users".into(),
+                where_clause: None,
+                span: Span::default(),
             }
         );
     }
@@ -444,6 +1186,27 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn test_line_index_matches_pest_positions() {
+        let input = format!(
+            "SELECT *\r\nFROM t -- é\n\nWHERE name = 'ü{}'\n  AND x = 1;",
+            "あ".repeat(70)
+        );
+        let lines = LineIndex::new(&input);
+        for (offset, _) in input.char_indices().chain([(input.len(), ' ')]) {
+            let position = pest::Position::new(&input, offset).unwrap();
+            assert_eq!(lines.line_col(offset), position.line_col(), "{}", offset);
+        }
+    }
+
+    #[test]
+    fn test_parse_with_crlf_line_endings() {
+        let sql = "-- header\r\nSELECT *\r\nFROM users\r\nWHERE id = 1;\r\n";
+        let statement = parse_sql(sql).unwrap();
+        assert_eq!(statement.span().line, 2);
+        assert!(parse_script("SELECT * FROM a;\r\nDELETE FROM b;\r\n").is_ok());
+    }
+
     #[test]
     fn test_parse_with_comment() {
         let sql = "-- This is a comment
@@ -467,8 +1230,10 @@ SELECT * FROM users;";
         assert_eq!(
             result.unwrap(),
             Statement::Insert {
-                table: "users".to_string(),
-                values: vec!["only_one".to_string()]
+                table: "users".into(),
+                values: vec!["only_one".to_string()],
+                on_conflict: None,
+                span: Span::default(),
             }
         );
     }
@@ -481,9 +1246,10 @@ SELECT * FROM users;";
         assert_eq!(
             result.unwrap(),
             Statement::Update {
-                table: "users".to_string(),
-                set: vec![("name".to_string(), "foo".to_string())],
-                where_clause: None
+                table: "users".into(),
+                set: vec![("name".into(), "foo".to_string())],
+                where_clause: None,
+                span: Span::default(),
             }
         );
     }
@@ -496,11 +1262,13 @@ SELECT * FROM users;";
         assert_eq!(
             result.unwrap(),
             Statement::Select {
-                table: "user_01".to_string(),
+                table: "user_01".into(),
                 where_clause: None,
                 order_by: None,
                 group_by: None,
                 limit: None,
+                offset: None,
+                span: Span::default(),
             }
         );
     }
@@ -513,8 +1281,10 @@ SELECT * FROM users;";
         assert_eq!(
             result.unwrap(),
             Statement::Insert {
-                table: "users".to_string(),
-                values: vec!["foo_1".to_string(), "bar2".to_string()]
+                table: "users".into(),
+                values: vec!["foo_1".to_string(), "bar2".to_string()],
+                on_conflict: None,
+                span: Span::default(),
             }
         );
     }
@@ -527,12 +1297,13 @@ SELECT * FROM users;";
         assert_eq!(
             result.unwrap(),
             Statement::Update {
-                table: "users".to_string(),
+                table: "users".into(),
                 set: vec![
-                    ("name_1".to_string(), "foo".to_string()),
-                    ("pass2".to_string(), "bar".to_string())
+                    ("name_1".into(), "foo".to_string()),
+                    ("pass2".into(), "bar".to_string())
                 ],
-                where_clause: None
+                where_clause: None,
+                span: Span::default(),
             }
         );
     }
@@ -551,11 +1322,25 @@ SELECT * FROM users;";
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_parse_escaped_quote_and_quoted_identifier() {
+        let sql = r#"INSERT INTO "user ""data""" VALUES ('it''s', '''');"#;
+        assert_eq!(
+            parse_sql(sql).unwrap(),
+            Statement::Insert {
+                table: "user \"data\"".into(),
+                values: vec!["it's".to_string(), "'".to_string()],
+                on_conflict: None,
+                span: Span::default(),
+            }
+        );
+    }
+
     #[test]
     fn test_parse_insert_value_with_single_quote_should_fail() {
         let sql = "INSERT INTO users VALUES ('foo\'bar');";
         let result = parse_sql(sql);
-        assert!(result.is_err()); // エスケープ未対応のためエラーになるべき
+        assert!(result.is_err()); // 文字列中の ' は '' と書く必要があるためエラーになるべき
     }
 
     #[test]
@@ -566,8 +1351,10 @@ SELECT * FROM users;";
         assert_eq!(
             result.unwrap(),
             Statement::Insert {
-                table: "users".to_string(),
-                values: vec!["ユーザー".to_string(), "パスワード".to_string()]
+                table: "users".into(),
+                values: vec!["ユーザー".to_string(), "パスワード".to_string()],
+                on_conflict: None,
+                span: Span::default(),
             }
         );
     }
@@ -585,6 +1372,7 @@ SELECT * FROM users;";
                 order_by,
                 group_by,
                 limit,
+                ..
             } => {
                 assert_eq!(table, "users");
                 assert!(where_clause.is_some());
@@ -597,12 +1385,16 @@ SELECT * FROM users;";
                         left,
                         operator,
                         right,
+                        ..
                     } => {
-                        assert_eq!(*left, Expression::Column("name".to_string()));
+                        assert_eq!(*left, Expression::Column("name".into()));
                         assert_eq!(operator, BinaryOperator::Equal);
                         assert_eq!(
                             *right,
-                            Expression::Literal(Literal::String("John".to_string()))
+                            Expression::Literal {
+                                value: Literal::String("John".to_string()),
+                                span: Span::default(),
+                            }
                         );
                     }
                     _ => panic!("Expected binary expression"),
@@ -623,9 +1415,10 @@ SELECT * FROM users;";
                 table,
                 set,
                 where_clause,
+                ..
             } => {
                 assert_eq!(table, "users");
-                assert_eq!(set, vec![("name".to_string(), "Jane".to_string())]);
+                assert_eq!(set, vec![("name".into(), "Jane".to_string())]);
                 assert!(where_clause.is_some());
             }
             _ => panic!("Expected Update statement"),
@@ -642,6 +1435,7 @@ SELECT * FROM users;";
             Statement::Delete {
                 table,
                 where_clause,
+                ..
             } => {
                 assert_eq!(table, "users");
                 assert!(where_clause.is_some());
@@ -663,6 +1457,7 @@ SELECT * FROM users;";
                 order_by,
                 group_by,
                 limit,
+                ..
             } => {
                 assert_eq!(table, "users");
                 assert!(where_clause.is_none());
@@ -673,8 +1468,9 @@ SELECT * FROM users;";
                 assert_eq!(
                     order.items,
                     vec![OrderByItem {
-                        column: "name".to_string(),
+                        column: "name".into(),
                         direction: OrderDirection::Asc,
+                        span: Span::default(),
                     }]
                 );
             }
@@ -695,6 +1491,7 @@ SELECT * FROM users;";
                 order_by,
                 group_by,
                 limit,
+                ..
             } => {
                 assert_eq!(table, "users");
                 assert!(where_clause.is_none());
@@ -705,8 +1502,9 @@ SELECT * FROM users;";
                 assert_eq!(
                     order.items,
                     vec![OrderByItem {
-                        column: "name".to_string(),
+                        column: "name".into(),
                         direction: OrderDirection::Desc,
+                        span: Span::default(),
                     }]
                 );
             }
@@ -727,6 +1525,7 @@ SELECT * FROM users;";
                 order_by,
                 group_by,
                 limit,
+                ..
             } => {
                 assert_eq!(table, "users");
                 assert!(where_clause.is_none());
@@ -738,12 +1537,14 @@ SELECT * FROM users;";
                     order.items,
                     vec![
                         OrderByItem {
-                            column: "name".to_string(),
+                            column: "name".into(),
                             direction: OrderDirection::Asc,
+                            span: Span::default(),
                         },
                         OrderByItem {
-                            column: "created_at".to_string(),
+                            column: "created_at".into(),
                             direction: OrderDirection::Desc,
+                            span: Span::default(),
                         },
                     ]
                 );
@@ -765,6 +1566,7 @@ SELECT * FROM users;";
                 order_by,
                 group_by,
                 limit,
+                ..
             } => {
                 assert_eq!(table, "users");
                 assert!(where_clause.is_none());
@@ -772,8 +1574,13 @@ SELECT * FROM users;";
                 assert!(limit.is_none());
                 assert!(group_by.is_some());
                 let group = group_by.unwrap();
-                assert_eq!(group.columns, vec!["department"]);
-            }
+                assert_eq!(
+                    group.items,
+                    vec![GroupingElement::Expression(Expression::Column(
+                        "department".into()
+                    ))]
+                );
+            }
             _ => panic!("Expected Select statement"),
         }
     }
@@ -791,6 +1598,7 @@ SELECT * FROM users;";
                 order_by,
                 group_by,
                 limit,
+                ..
             } => {
                 assert_eq!(table, "users");
                 assert!(where_clause.is_none());
@@ -798,7 +1606,13 @@ SELECT * FROM users;";
                 assert!(limit.is_none());
                 assert!(group_by.is_some());
                 let group = group_by.unwrap();
-                assert_eq!(group.columns, vec!["department", "status"]);
+                assert_eq!(
+                    group.items,
+                    vec![
+                        GroupingElement::Expression(Expression::Column("department".into())),
+                        GroupingElement::Expression(Expression::Column("status".into()))
+                    ]
+                );
             }
             _ => panic!("Expected Select statement"),
         }
@@ -817,13 +1631,14 @@ SELECT * FROM users;";
                 order_by,
                 group_by,
                 limit,
+                ..
             } => {
                 assert_eq!(table, "users");
                 assert!(where_clause.is_none());
                 assert!(order_by.is_none());
                 assert!(group_by.is_none());
                 assert!(limit.is_some());
-                assert_eq!(limit.unwrap(), 10);
+                assert_eq!(limit.unwrap().to_string(), "10");
             }
             _ => panic!("Expected Select statement"),
         }
@@ -842,6 +1657,7 @@ SELECT * FROM users;";
                 order_by,
                 group_by,
                 limit,
+                ..
             } => {
                 assert_eq!(table, "users");
                 assert!(where_clause.is_some());
@@ -853,15 +1669,21 @@ SELECT * FROM users;";
                 assert_eq!(
                     order.items,
                     vec![OrderByItem {
-                        column: "name".to_string(),
+                        column: "name".into(),
                         direction: OrderDirection::Asc,
+                        span: Span::default(),
                     }]
                 );
 
                 let group = group_by.unwrap();
-                assert_eq!(group.columns, vec!["department"]);
+                assert_eq!(
+                    group.items,
+                    vec![GroupingElement::Expression(Expression::Column(
+                        "department".into()
+                    ))]
+                );
 
-                assert_eq!(limit.unwrap(), 10);
+                assert_eq!(limit.unwrap().to_string(), "10");
             }
             _ => panic!("Expected Select statement"),
         }
@@ -880,16 +1702,31 @@ SELECT * FROM users;";
                     expr,
                     Expression::Binary {
                         left: Box::new(Expression::Binary {
-                            left: Box::new(Expression::Literal(Literal::Number(1))),
+                            left: Box::new(Expression::Literal {
+                                value: Literal::Number(1),
+                                span: Span::default(),
+                            }),
                             operator: BinaryOperator::Add,
                             right: Box::new(Expression::Binary {
-                                left: Box::new(Expression::Literal(Literal::Number(2))),
+                                left: Box::new(Expression::Literal {
+                                    value: Literal::Number(2),
+                                    span: Span::default(),
+                                }),
                                 operator: BinaryOperator::Multiply,
-                                right: Box::new(Expression::Literal(Literal::Number(3))),
+                                right: Box::new(Expression::Literal {
+                                    value: Literal::Number(3),
+                                    span: Span::default(),
+                                }),
+                                span: Span::default(),
                             }),
+                            span: Span::default(),
                         }),
                         operator: BinaryOperator::Equal,
-                        right: Box::new(Expression::Literal(Literal::Number(7))),
+                        right: Box::new(Expression::Literal {
+                            value: Literal::Number(7),
+                            span: Span::default(),
+                        }),
+                        span: Span::default(),
                     }
                 );
             }
@@ -911,15 +1748,30 @@ SELECT * FROM users;";
                     Expression::Binary {
                         left: Box::new(Expression::Binary {
                             left: Box::new(Expression::Binary {
-                                left: Box::new(Expression::Literal(Literal::Number(1))),
+                                left: Box::new(Expression::Literal {
+                                    value: Literal::Number(1),
+                                    span: Span::default(),
+                                }),
                                 operator: BinaryOperator::Add,
-                                right: Box::new(Expression::Literal(Literal::Number(2))),
+                                right: Box::new(Expression::Literal {
+                                    value: Literal::Number(2),
+                                    span: Span::default(),
+                                }),
+                                span: Span::default(),
                             }),
                             operator: BinaryOperator::Multiply,
-                            right: Box::new(Expression::Literal(Literal::Number(3))),
+                            right: Box::new(Expression::Literal {
+                                value: Literal::Number(3),
+                                span: Span::default(),
+                            }),
+                            span: Span::default(),
                         }),
                         operator: BinaryOperator::Equal,
-                        right: Box::new(Expression::Literal(Literal::Number(9))),
+                        right: Box::new(Expression::Literal {
+                            value: Literal::Number(9),
+                            span: Span::default(),
+                        }),
+                        span: Span::default(),
                     }
                 );
             }
@@ -942,13 +1794,25 @@ SELECT * FROM users;";
                         left: Box::new(Expression::Binary {
                             left: Box::new(Expression::Unary {
                                 operator: UnaryOperator::Minus,
-                                operand: Box::new(Expression::Literal(Literal::Number(1))),
+                                operand: Box::new(Expression::Literal {
+                                    value: Literal::Number(1),
+                                    span: Span::default(),
+                                }),
+                                span: Span::default(),
                             }),
                             operator: BinaryOperator::Add,
-                            right: Box::new(Expression::Literal(Literal::Number(2))),
+                            right: Box::new(Expression::Literal {
+                                value: Literal::Number(2),
+                                span: Span::default(),
+                            }),
+                            span: Span::default(),
                         }),
                         operator: BinaryOperator::Equal,
-                        right: Box::new(Expression::Literal(Literal::Number(1))),
+                        right: Box::new(Expression::Literal {
+                            value: Literal::Number(1),
+                            span: Span::default(),
+                        }),
+                        span: Span::default(),
                     }
                 );
             }
@@ -976,4 +1840,1011 @@ SELECT * FROM users;";
         let result = parse_sql(sql);
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_parse_insert_on_conflict_do_nothing() {
+        let sql = "INSERT INTO users VALUES ('foo') ON CONFLICT DO NOTHING;";
+        let result = parse_sql(sql);
+        assert!(result.is_ok());
+        assert_eq!(
+            result.unwrap(),
+            Statement::Insert {
+                table: "users".into(),
+                values: vec!["foo".to_string()],
+                on_conflict: Some(OnConflict::DoNothing { target: None }),
+                span: Span::default(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_insert_on_conflict_on_constraint_do_nothing() {
+        let sql =
+            "INSERT INTO users VALUES ('foo') ON CONFLICT ON CONSTRAINT users_pkey DO NOTHING;";
+        let result = parse_sql(sql);
+        assert!(result.is_ok());
+        assert_eq!(
+            result.unwrap(),
+            Statement::Insert {
+                table: "users".into(),
+                values: vec!["foo".to_string()],
+                on_conflict: Some(OnConflict::DoNothing {
+                    target: Some(ConflictTarget::Constraint("users_pkey".into()))
+                }),
+                span: Span::default(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_insert_on_conflict_do_update() {
+        let sql = "INSERT INTO users VALUES ('1', 'foo') ON CONFLICT (id) DO UPDATE SET name = EXCLUDED.name WHERE users.active = TRUE;";
+        let result = parse_sql(sql);
+        assert!(result.is_ok());
+        assert_eq!(
+            result.unwrap(),
+            Statement::Insert {
+                table: "users".into(),
+                values: vec!["1".to_string(), "foo".to_string()],
+                on_conflict: Some(OnConflict::DoUpdate {
+                    target: ConflictTarget::Columns(vec!["id".into()]),
+                    set: vec![(
+                        "name".into(),
+                        Expression::QualifiedColumn {
+                            table: "EXCLUDED".into(),
+                            column: "name".into(),
+                        }
+                    )],
+                    where_clause: Some(Expression::Binary {
+                        left: Box::new(Expression::QualifiedColumn {
+                            table: "users".into(),
+                            column: "active".into(),
+                        }),
+                        operator: BinaryOperator::Equal,
+                        right: Box::new(Expression::Literal {
+                            value: Literal::Boolean(true),
+                            span: Span::default(),
+                        }),
+                        span: Span::default(),
+                    }),
+                }),
+                span: Span::default(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_insert_on_conflict_do_update_without_target_should_fail() {
+        let sql = "INSERT INTO users VALUES ('foo') ON CONFLICT DO UPDATE SET name = 'bar';";
+        let result = parse_sql(sql);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_parse_insert_on_duplicate_key_update() {
+        let sql = "INSERT INTO users VALUES ('1', 'foo') ON DUPLICATE KEY UPDATE name = VALUES(name), hits = hits + 1;";
+        let result = parse_sql(sql);
+        assert!(result.is_ok());
+        assert_eq!(
+            result.unwrap(),
+            Statement::Insert {
+                table: "users".into(),
+                values: vec!["1".to_string(), "foo".to_string()],
+                on_conflict: Some(OnConflict::DuplicateKeyUpdate {
+                    set: vec![
+                        (
+                            "name".into(),
+                            Expression::Function {
+                                name: "VALUES".into(),
+                                args: vec![Expression::Column("name".into())],
+                                span: Span::default(),
+                            }
+                        ),
+                        (
+                            "hits".into(),
+                            Expression::Binary {
+                                left: Box::new(Expression::Column("hits".into())),
+                                operator: BinaryOperator::Add,
+                                right: Box::new(Expression::Literal {
+                                    value: Literal::Number(1),
+                                    span: Span::default(),
+                                }),
+                                span: Span::default(),
+                            }
+                        ),
+                    ]
+                }),
+                span: Span::default(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_merge_statement() {
+        let sql = "MERGE INTO customers AS t USING staging_customers s ON t.id = s.id \
+                   WHEN MATCHED AND s.deleted = TRUE THEN DELETE \
+                   WHEN MATCHED THEN UPDATE SET name = s.name \
+                   WHEN NOT MATCHED THEN INSERT (id, name) VALUES (s.id, s.name);";
+        let result = parse_sql(sql);
+        assert!(result.is_ok());
+        let qualified = |table: &str, column: &str| Expression::QualifiedColumn {
+            table: table.into(),
+            column: column.into(),
+        };
+        assert_eq!(
+            result.unwrap(),
+            Statement::Merge {
+                table: "customers".into(),
+                alias: Some("t".into()),
+                source: MergeSource::Table {
+                    name: "staging_customers".into(),
+                    alias: Some("s".into()),
+                },
+                on: Expression::Binary {
+                    left: Box::new(qualified("t", "id")),
+                    operator: BinaryOperator::Equal,
+                    right: Box::new(qualified("s", "id")),
+                    span: Span::default(),
+                },
+                clauses: vec![
+                    MergeClause {
+                        matched: true,
+                        condition: Some(Expression::Binary {
+                            left: Box::new(qualified("s", "deleted")),
+                            operator: BinaryOperator::Equal,
+                            right: Box::new(Expression::Literal {
+                                value: Literal::Boolean(true),
+                                span: Span::default(),
+                            }),
+                            span: Span::default(),
+                        }),
+                        action: MergeAction::Delete,
+                    },
+                    MergeClause {
+                        matched: true,
+                        condition: None,
+                        action: MergeAction::Update {
+                            set: vec![("name".into(), qualified("s", "name"))],
+                        },
+                    },
+                    MergeClause {
+                        matched: false,
+                        condition: None,
+                        action: MergeAction::Insert {
+                            columns: vec!["id".into(), "name".into()],
+                            values: vec![qualified("s", "id"), qualified("s", "name")],
+                        },
+                    },
+                ],
+                span: Span::default(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_merge_using_subquery() {
+        let sql = "MERGE INTO customers USING (SELECT * FROM staging WHERE active = TRUE) AS s ON customers.id = s.id WHEN NOT MATCHED THEN INSERT VALUES (s.id);";
+        let result = parse_sql(sql);
+        assert!(result.is_ok());
+        let statement = result.unwrap();
+        match statement {
+            Statement::Merge {
+                table,
+                alias,
+                source,
+                clauses,
+                ..
+            } => {
+                assert_eq!(table, "customers");
+                assert!(alias.is_none());
+                match source {
+                    MergeSource::Subquery { query, alias } => {
+                        assert_eq!(alias, Some("s".into()));
+                        match *query {
+                            Statement::Select {
+                                table,
+                                where_clause,
+                                ..
+                            } => {
+                                assert_eq!(table, "staging");
+                                assert!(where_clause.is_some());
+                            }
+                            _ => panic!("Expected Select subquery"),
+                        }
+                    }
+                    _ => panic!("Expected subquery source"),
+                }
+                assert_eq!(clauses.len(), 1);
+                assert!(!clauses[0].matched);
+            }
+            _ => panic!("Expected Merge statement"),
+        }
+    }
+
+    #[test]
+    fn test_parse_merge_without_when_clause_should_fail() {
+        let sql = "MERGE INTO customers USING staging ON customers.id = staging.id;";
+        let result = parse_sql(sql);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_parse_select_with_group_by_expression() {
+        let sql = "SELECT * FROM sales GROUP BY amount / 100;";
+        let result = parse_sql(sql);
+        assert!(result.is_ok());
+        match result.unwrap() {
+            Statement::Select { group_by, .. } => {
+                assert_eq!(
+                    group_by.unwrap().items,
+                    vec![GroupingElement::Expression(Expression::Binary {
+                        left: Box::new(Expression::Column("amount".into())),
+                        operator: BinaryOperator::Divide,
+                        right: Box::new(Expression::Literal {
+                            value: Literal::Number(100),
+                            span: Span::default(),
+                        }),
+                        span: Span::default(),
+                    })]
+                );
+            }
+            _ => panic!("Expected Select statement"),
+        }
+    }
+
+    #[test]
+    fn test_parse_select_with_group_by_rollup_and_cube() {
+        let sql = "SELECT * FROM sales GROUP BY ROLLUP(region, (country, city)), CUBE(product);";
+        let result = parse_sql(sql);
+        assert!(result.is_ok());
+        let column = |name: &str| Expression::Column(name.into());
+        match result.unwrap() {
+            Statement::Select { group_by, .. } => {
+                assert_eq!(
+                    group_by.unwrap().items,
+                    vec![
+                        GroupingElement::Rollup(vec![
+                            GroupingElement::Expression(column("region")),
+                            GroupingElement::Set(vec![column("country"), column("city")]),
+                        ]),
+                        GroupingElement::Cube(vec![GroupingElement::Expression(column("product"))]),
+                    ]
+                );
+            }
+            _ => panic!("Expected Select statement"),
+        }
+    }
+
+    #[test]
+    fn test_parse_select_with_grouping_sets() {
+        let sql = "SELECT * FROM sales GROUP BY GROUPING SETS ((region, product), (region), ());";
+        let result = parse_sql(sql);
+        assert!(result.is_ok());
+        let column = |name: &str| Expression::Column(name.into());
+        match result.unwrap() {
+            Statement::Select { group_by, .. } => {
+                assert_eq!(
+                    group_by.unwrap().items,
+                    vec![GroupingElement::GroupingSets(vec![
+                        GroupingElement::Set(vec![column("region"), column("product")]),
+                        GroupingElement::Expression(column("region")),
+                        GroupingElement::Set(vec![]),
+                    ])]
+                );
+            }
+            _ => panic!("Expected Select statement"),
+        }
+    }
+
+    #[test]
+    fn test_parse_select_with_grouping_function() {
+        let sql = "SELECT * FROM sales WHERE GROUPING(region, product) = 0 GROUP BY CUBE(region, product);";
+        let result = parse_sql(sql);
+        assert!(result.is_ok());
+        match result.unwrap() {
+            Statement::Select { where_clause, .. } => {
+                assert_eq!(
+                    where_clause.unwrap(),
+                    Expression::Binary {
+                        left: Box::new(Expression::Grouping {
+                            args: vec![
+                                Expression::Column("region".into()),
+                                Expression::Column("product".into()),
+                            ],
+                            span: Span::default(),
+                        }),
+                        operator: BinaryOperator::Equal,
+                        right: Box::new(Expression::Literal {
+                            value: Literal::Number(0),
+                            span: Span::default(),
+                        }),
+                        span: Span::default(),
+                    }
+                );
+            }
+            _ => panic!("Expected Select statement"),
+        }
+    }
+
+    #[test]
+    fn test_parse_select_with_where_and_order_by() {
+        let sql = "SELECT * FROM users WHERE id = 1 ORDER BY name;";
+        let result = parse_sql(sql);
+        assert!(result.is_ok());
+        match result.unwrap() {
+            Statement::Select {
+                where_clause,
+                order_by,
+                ..
+            } => {
+                assert_eq!(
+                    where_clause.unwrap(),
+                    Expression::Binary {
+                        left: Box::new(Expression::Column("id".into())),
+                        operator: BinaryOperator::Equal,
+                        right: Box::new(Expression::Literal {
+                            value: Literal::Number(1),
+                            span: Span::default(),
+                        }),
+                        span: Span::default(),
+                    }
+                );
+                assert!(order_by.is_some());
+            }
+            _ => panic!("Expected Select statement"),
+        }
+    }
+
+    #[test]
+    fn test_parse_columns_starting_with_literal_keywords() {
+        let sql = "SELECT * FROM users WHERE nullable = TRUE AND trueish = falsey;";
+        let result = parse_sql(sql);
+        assert!(result.is_ok());
+        match result.unwrap() {
+            Statement::Select { where_clause, .. } => {
+                assert_eq!(
+                    where_clause.unwrap().to_string(),
+                    "nullable = TRUE AND trueish = falsey"
+                );
+            }
+            _ => panic!("Expected Select statement"),
+        }
+    }
+
+    #[test]
+    fn test_parse_schema_qualified_tables() {
+        let sql = "DELETE FROM app.\"user data\" WHERE \"user data\".id = 1;";
+        match parse_sql(sql).unwrap() {
+            Statement::Delete { table, .. } => {
+                assert_eq!(table, ObjectName::qualified("app", "user data"));
+                assert_eq!((table.span().start, table.span().end), (12, 27));
+            }
+            _ => panic!("Expected Delete statement"),
+        }
+
+        let sql = "MERGE INTO app.t USING staging.s ON t.id = s.id WHEN MATCHED THEN DELETE;";
+        match parse_sql(sql).unwrap() {
+            Statement::Merge { table, source, .. } => {
+                assert_eq!(table, ObjectName::qualified("app", "t"));
+                assert_eq!(
+                    source,
+                    MergeSource::Table {
+                        name: ObjectName::qualified("staging", "s"),
+                        alias: None,
+                    }
+                );
+            }
+            _ => panic!("Expected Merge statement"),
+        }
+
+        // The dot binds the two names without whitespace, as in qualified columns
+        assert!(parse_sql("SELECT * FROM app . users;").is_err());
+        assert!(parse_sql("SELECT * FROM app.order;").is_err());
+    }
+
+    #[test]
+    fn test_parse_prepare_statement() {
+        let sql = "PREPARE find_user (integer, varchar(20)) AS SELECT * FROM users WHERE id = $1 AND name = $2;";
+        let result = parse_sql(sql);
+        assert!(result.is_ok());
+        assert_eq!(
+            result.unwrap(),
+            Statement::Prepare {
+                name: "find_user".into(),
+                parameter_types: vec![DataType::Integer, DataType::Varchar(Some(20))],
+                statement: Box::new(Statement::Select {
+                    table: "users".into(),
+                    where_clause: Some(Expression::Binary {
+                        left: Box::new(Expression::Binary {
+                            left: Box::new(Expression::Column("id".into())),
+                            operator: BinaryOperator::Equal,
+                            right: Box::new(Expression::Parameter {
+                                name: "$1".to_string(),
+                                span: Span::default(),
+                            }),
+                            span: Span::default(),
+                        }),
+                        operator: BinaryOperator::And,
+                        right: Box::new(Expression::Binary {
+                            left: Box::new(Expression::Column("name".into())),
+                            operator: BinaryOperator::Equal,
+                            right: Box::new(Expression::Parameter {
+                                name: "$2".to_string(),
+                                span: Span::default(),
+                            }),
+                            span: Span::default(),
+                        }),
+                        span: Span::default(),
+                    }),
+                    order_by: None,
+                    group_by: None,
+                    limit: None,
+                    offset: None,
+                    span: Span::default(),
+                }),
+                span: Span::default(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_prepare_statement_without_parameter_types() {
+        let sql = "PREPARE purge AS DELETE FROM sessions WHERE expires_at < ?;";
+        let result = parse_sql(sql);
+        assert!(result.is_ok());
+        match result.unwrap() {
+            Statement::Prepare {
+                name,
+                parameter_types,
+                statement,
+                ..
+            } => {
+                assert_eq!(name, "purge");
+                assert!(parameter_types.is_empty());
+                assert!(matches!(*statement, Statement::Delete { .. }));
+            }
+            _ => panic!("Expected Prepare statement"),
+        }
+    }
+
+    #[test]
+    fn test_parse_execute_statement() {
+        let sql = "EXECUTE find_user (42, 'foo');";
+        let result = parse_sql(sql);
+        assert!(result.is_ok());
+        assert_eq!(
+            result.unwrap(),
+            Statement::Execute {
+                name: "find_user".into(),
+                parameters: vec![
+                    Expression::Literal {
+                        value: Literal::Number(42),
+                        span: Span::default(),
+                    },
+                    Expression::Literal {
+                        value: Literal::String("foo".to_string()),
+                        span: Span::default(),
+                    },
+                ],
+                span: Span::default(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_deallocate_statement() {
+        assert_eq!(
+            parse_sql("DEALLOCATE PREPARE find_user;").unwrap(),
+            Statement::Deallocate {
+                name: Some("find_user".into()),
+                span: Span::default(),
+            }
+        );
+        assert_eq!(
+            parse_sql("DEALLOCATE prepared_query;").unwrap(),
+            Statement::Deallocate {
+                name: Some("prepared_query".into()),
+                span: Span::default(),
+            }
+        );
+        assert_eq!(
+            parse_sql("DEALLOCATE ALL;").unwrap(),
+            Statement::Deallocate {
+                name: None,
+                span: Span::default(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_create_table_statement() {
+        let sql = "CREATE TABLE users (id INTEGER PRIMARY KEY AUTO_INCREMENT, name VARCHAR(20) NOT NULL DEFAULT 'x', note TEXT NULL UNIQUE);";
+        let result = parse_sql(sql);
+        assert!(result.is_ok());
+        match result.unwrap() {
+            Statement::CreateTable { table, columns, .. } => {
+                assert_eq!(table.name.value, "users");
+                assert_eq!(columns.len(), 3);
+                assert_eq!(columns[0].data_type, DataType::Integer);
+                assert_eq!(
+                    columns[0].options,
+                    [ColumnOption::PrimaryKey, ColumnOption::AutoIncrement]
+                );
+                assert_eq!(
+                    columns[1].options,
+                    [
+                        ColumnOption::NotNull,
+                        ColumnOption::Default(Expression::Literal {
+                            value: Literal::String("x".to_string()),
+                            span: Span::default(),
+                        }),
+                    ]
+                );
+                assert_eq!(
+                    columns[2].options,
+                    [ColumnOption::Null, ColumnOption::Unique]
+                );
+            }
+            _ => panic!("Expected CreateTable statement"),
+        }
+    }
+
+    #[test]
+    fn test_parse_select_with_like_and_cast() {
+        let sql = "SELECT * FROM users WHERE name LIKE 'a%' AND CAST(age AS TEXT) ILIKE '1%' AND id::BIGINT > 0;";
+        let result = parse_sql(sql);
+        assert!(result.is_ok());
+        // LIKE compares like = and binds tighter than AND
+        let expr = "name LIKE 'a%' AND b";
+        match parse_expression(expr).unwrap() {
+            Expression::Binary { left, operator, .. } => {
+                assert_eq!(operator, BinaryOperator::And);
+                assert!(matches!(
+                    *left,
+                    Expression::Binary {
+                        operator: BinaryOperator::Like,
+                        ..
+                    }
+                ));
+            }
+            other => panic!("Expected AND, got {:?}", other),
+        }
+        assert_eq!(
+            parse_expression("id::BIGINT").unwrap(),
+            parse_expression("CAST(id AS BIGINT)").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_parse_select_with_limit_and_offset() {
+        let sql = "SELECT * FROM users ORDER BY id LIMIT 10 OFFSET 20;";
+        match parse_sql(sql).unwrap() {
+            Statement::Select {
+                limit: Some(limit),
+                offset: Some(offset),
+                ..
+            } => {
+                assert!(matches!(
+                    limit,
+                    Expression::Literal {
+                        value: Literal::Number(10),
+                        ..
+                    }
+                ));
+                assert!(matches!(
+                    offset,
+                    Expression::Literal {
+                        value: Literal::Number(20),
+                        ..
+                    }
+                ));
+            }
+            _ => panic!("Expected Select statement"),
+        }
+        // LIMIT offset, count
+        let sql = "SELECT * FROM users LIMIT 20, 10;";
+        match parse_sql(sql).unwrap() {
+            Statement::Select {
+                limit: Some(limit),
+                offset: Some(offset),
+                ..
+            } => {
+                assert!(matches!(
+                    limit,
+                    Expression::Literal {
+                        value: Literal::Number(10),
+                        ..
+                    }
+                ));
+                assert!(matches!(
+                    offset,
+                    Expression::Literal {
+                        value: Literal::Number(20),
+                        ..
+                    }
+                ));
+            }
+            _ => panic!("Expected Select statement"),
+        }
+        let sql = "SELECT * FROM users LIMIT 20, 10 OFFSET 5;";
+        assert!(matches!(parse_sql(sql), Err(ParseError::Semantic { .. })));
+        // Prepared statements bind the row counts
+        let sql = "SELECT * FROM users LIMIT ? OFFSET $1;";
+        match parse_sql(sql).unwrap() {
+            Statement::Select {
+                limit: Some(limit),
+                offset: Some(offset),
+                ..
+            } => {
+                assert!(matches!(limit, Expression::Parameter { name, .. } if name == "?"));
+                assert!(matches!(offset, Expression::Parameter { name, .. } if name == "$1"));
+            }
+            _ => panic!("Expected Select statement"),
+        }
+        let sql = "SELECT * FROM users LIMIT 99999999999999999999;";
+        assert!(matches!(parse_sql(sql), Err(ParseError::Semantic { .. })));
+    }
+
+    #[test]
+    fn test_parse_japanese_identifiers() {
+        // The space before AND is U+3000
+        let sql = "SELECT * FROM 顧客 WHERE 名前 = '山田'\u{3000}AND 年齢_2 > 20 ORDER BY 名前;";
+        let result = parse_sql(sql);
+        assert!(result.is_ok());
+        match result.unwrap() {
+            Statement::Select {
+                table,
+                where_clause,
+                ..
+            } => {
+                assert_eq!(table.name.value, "顧客");
+                assert_eq!(
+                    where_clause.unwrap().to_string(),
+                    "名前 = '山田' AND 年齢_2 > 20"
+                );
+            }
+            _ => panic!("Expected Select statement"),
+        }
+        let sql = "UPDATE 商品 SET 価格 = '100';";
+        assert!(parse_sql(sql).is_ok());
+        // Digits and punctuation cannot start or continue a name
+        assert!(parse_sql("SELECT * FROM 1顧客;").is_err());
+        assert!(parse_sql("SELECT * FROM 顧客、注文;").is_err());
+    }
+
+    #[test]
+    fn test_bare_identifiers_are_normalized_to_nfc() {
+        let decomposed = parse_expression("cafe\u{301} = 1").unwrap();
+        let composed = parse_expression("caf\u{e9} = 1").unwrap();
+        assert_eq!(decomposed, composed);
+        // A quoted name is kept as written
+        match parse_expression("\"cafe\u{301}\"").unwrap() {
+            Expression::Column(column) => assert_eq!(column.value, "cafe\u{301}"),
+            other => panic!("Expected a column, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_limit_out_of_range_should_fail() {
+        let sql = "SELECT * FROM users LIMIT 99999999999999999999;";
+        let result = parse_sql(sql);
+        assert!(matches!(result, Err(ParseError::Semantic { .. })));
+    }
+
+    #[test]
+    fn test_parse_number_literal_out_of_range_should_fail() {
+        let sql = "SELECT * FROM users WHERE id = 99999999999999999999;";
+        let result = parse_sql(sql);
+        let error = result.unwrap_err();
+        assert!(matches!(error, ParseError::Semantic { .. }));
+        assert_eq!(error.location().column, 32);
+    }
+
+    #[test]
+    fn test_parse_type_modifier_out_of_range_should_fail() {
+        let sql = "PREPARE q (varchar(99999999999999999999)) AS DELETE FROM users;";
+        let result = parse_sql(sql);
+        assert!(matches!(result, Err(ParseError::Semantic { .. })));
+    }
+
+    #[test]
+    fn test_parse_select_with_repeated_unary_operators() {
+        let sql = "SELECT * FROM users WHERE NOT NOT active AND - -1 = 1;";
+        let result = parse_sql(sql);
+        assert!(result.is_ok());
+        match result.unwrap() {
+            Statement::Select { where_clause, .. } => {
+                assert_eq!(
+                    where_clause.unwrap(),
+                    Expression::Binary {
+                        left: Box::new(Expression::Unary {
+                            operator: UnaryOperator::Not,
+                            operand: Box::new(Expression::Unary {
+                                operator: UnaryOperator::Not,
+                                operand: Box::new(Expression::Column("active".into())),
+                                span: Span::default(),
+                            }),
+                            span: Span::default(),
+                        }),
+                        operator: BinaryOperator::And,
+                        right: Box::new(Expression::Binary {
+                            left: Box::new(Expression::Unary {
+                                operator: UnaryOperator::Minus,
+                                operand: Box::new(Expression::Unary {
+                                    operator: UnaryOperator::Minus,
+                                    operand: Box::new(Expression::Literal {
+                                        value: Literal::Number(1),
+                                        span: Span::default(),
+                                    }),
+                                    span: Span::default(),
+                                }),
+                                span: Span::default(),
+                            }),
+                            operator: BinaryOperator::Equal,
+                            right: Box::new(Expression::Literal {
+                                value: Literal::Number(1),
+                                span: Span::default(),
+                            }),
+                            span: Span::default(),
+                        }),
+                        span: Span::default(),
+                    }
+                );
+            }
+            _ => panic!("Expected Select statement"),
+        }
+    }
+
+    #[test]
+    fn test_statement_span_excludes_trailing_whitespace() {
+        let sql = "  SELECT * FROM users;  \n";
+        let statement = parse_sql(sql).unwrap();
+        let span = statement.span();
+        assert_eq!((span.start, span.end), (2, 22));
+        assert_eq!((span.line, span.column), (1, 3));
+        assert_eq!(&sql[span.start..span.end], "SELECT * FROM users;");
+    }
+
+    #[test]
+    fn test_identifier_and_expression_spans() {
+        let sql = "SELECT * FROM users\nWHERE age > 1 + 2\nORDER BY name;";
+        let statement = parse_sql(sql).unwrap();
+        match statement {
+            Statement::Select {
+                table,
+                where_clause,
+                order_by,
+                ..
+            } => {
+                assert_eq!((table.span().line, table.span().column), (1, 15));
+
+                let where_clause = where_clause.unwrap();
+                let span = where_clause.span();
+                assert_eq!(&sql[span.start..span.end], "age > 1 + 2");
+                match where_clause {
+                    Expression::Binary { left, right, .. } => {
+                        let left_span = left.span();
+                        assert_eq!((left_span.line, left_span.column), (2, 7));
+                        let right_span = right.span();
+                        assert_eq!(&sql[right_span.start..right_span.end], "1 + 2");
+                    }
+                    _ => panic!("Expected binary expression"),
+                }
+
+                let item = &order_by.unwrap().items[0];
+                assert_eq!((item.span.line, item.span.column), (3, 10));
+                assert_eq!(&sql[item.column.span.start..item.column.span.end], "name");
+            }
+            _ => panic!("Expected Select statement"),
+        }
+    }
+
+    #[test]
+    fn test_spans_are_ignored_by_equality() {
+        let a = parse_sql("SELECT * FROM users WHERE id = 1;").unwrap();
+        let b = parse_sql("SELECT   *   FROM users\nWHERE id   =   1;").unwrap();
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn test_parse_data_type_entry_point() {
+        assert_eq!(
+            parse_data_type("varchar(20)").unwrap(),
+            DataType::Varchar(Some(20))
+        );
+        assert_eq!(
+            parse_data_type("DOUBLE PRECISION").unwrap(),
+            DataType::DoublePrecision
+        );
+        assert_eq!(
+            parse_data_type("numeric(10, 2)").unwrap(),
+            DataType::Decimal {
+                precision: Some(10),
+                scale: Some(2)
+            }
+        );
+        assert!(matches!(
+            parse_data_type("varchar(20) x"),
+            Err(ParseError::Syntax { .. })
+        ));
+    }
+
+    #[test]
+    fn test_parse_order_by_entry_point() {
+        let expected = OrderBy {
+            items: vec![
+                OrderByItem {
+                    column: "name".into(),
+                    direction: OrderDirection::Asc,
+                    span: Span::default(),
+                },
+                OrderByItem {
+                    column: "created_at".into(),
+                    direction: OrderDirection::Desc,
+                    span: Span::default(),
+                },
+            ],
+        };
+        assert_eq!(parse_order_by("name, created_at DESC").unwrap(), expected);
+        assert_eq!(
+            parse_order_by("ORDER BY name ASC, created_at DESC").unwrap(),
+            expected
+        );
+        assert!(parse_order_by("").is_err());
+    }
+
+    #[test]
+    fn test_parse_expression_entry_point_requires_whole_input() {
+        assert_eq!(
+            parse_expression("id = 1").unwrap(),
+            Expression::Binary {
+                left: Box::new(Expression::Column("id".into())),
+                operator: BinaryOperator::Equal,
+                right: Box::new(Expression::Literal {
+                    value: Literal::Number(1),
+                    span: Span::default(),
+                }),
+                span: Span::default(),
+            }
+        );
+        let error = parse_expression("id = 1 garbage").unwrap_err();
+        assert_eq!(error.location().column, 8);
+    }
+
+    #[test]
+    fn test_entry_points_share_error_type() {
+        let errors: Vec<ParseError> = vec![
+            parse_statement("SELECT * FROM;").unwrap_err(),
+            parse_expression("1 +").unwrap_err(),
+            parse_data_type("varchar(99999999999999999999)").unwrap_err(),
+            parse_order_by("name SIDEWAYS").unwrap_err(),
+        ];
+        assert!(matches!(errors[2], ParseError::Semantic { .. }));
+        for error in errors {
+            assert!(error.location().line >= 1);
+        }
+    }
+
+    #[test]
+    fn test_parse_corpus() {
+        let statements = crate::corpus::statements();
+        // Every kind of statement is covered
+        for kind in [
+            "Select",
+            "Insert",
+            "Update",
+            "Delete",
+            "Merge",
+            "Prepare",
+            "Execute",
+            "Deallocate",
+            "CreateTable",
+        ] {
+            assert!(
+                statements
+                    .iter()
+                    .any(|statement| format!("{:?}", statement).starts_with(kind)),
+                "no {} statement in the corpus",
+                kind
+            );
+        }
+        for input in crate::corpus::expressions() {
+            parse_expression(input).unwrap_or_else(|error| panic!("{}\n{}", input, error));
+        }
+    }
+
+    #[test]
+    fn test_parse_script_with_valid_statements() {
+        let script = parse_script(
+            "SELECT * FROM users;\nDELETE FROM sessions;\n-- comment\nUPDATE users SET name = 'a';\n",
+        );
+        assert!(script.is_ok());
+        assert_eq!(script.statements.len(), 3);
+        assert!(matches!(script.statements[1], Statement::Delete { .. }));
+        assert_eq!(script.statements[2].span().line, 4);
+    }
+
+    #[test]
+    fn test_parse_script_recovers_at_semicolon() {
+        let sql = "SELECT * FROM users;\nSELECT * FROM ;\nDELETE FROM sessions;";
+        let script = parse_script(sql);
+        assert_eq!(script.statements.len(), 2);
+        assert!(matches!(script.statements[0], Statement::Select { .. }));
+        assert!(matches!(script.statements[1], Statement::Delete { .. }));
+
+        assert_eq!(script.diagnostics.len(), 1);
+        let location = script.diagnostics[0].location();
+        assert_eq!((location.line, location.column), (2, 15));
+        assert_eq!(location.offset, sql.find(" ;").unwrap() + 1);
+        assert_eq!(script.diagnostics[0].expected(), ["identifier"]);
+    }
+
+    #[test]
+    fn test_parse_script_recovers_at_statement_keyword() {
+        // The first statement is missing its semicolon
+        let sql = "SELECT * FROM users\nINSERT INTO logs VALUES ('x');";
+        let script = parse_script(sql);
+        assert_eq!(script.statements.len(), 1);
+        assert!(matches!(script.statements[0], Statement::Insert { .. }));
+        assert_eq!(script.diagnostics.len(), 1);
+        assert_eq!(script.diagnostics[0].location().line, 2);
+    }
+
+    #[test]
+    fn test_parse_script_reports_every_error() {
+        let sql = "SELEC * FROM users;\nSELECT * FROM users LIMIT 99999999999999999999;\nUPDATE SET;\nSELECT * FROM selected_items;";
+        let script = parse_script(sql);
+        assert_eq!(script.statements.len(), 1);
+        assert_eq!(script.diagnostics.len(), 3);
+        let lines: Vec<usize> = script
+            .diagnostics
+            .iter()
+            .map(|error| error.location().line)
+            .collect();
+        assert_eq!(lines, [1, 2, 3]);
+        assert!(matches!(script.diagnostics[1], ParseError::Semantic { .. }));
+        assert!(script.diagnostics[2]
+            .to_string()
+            .contains("3 | UPDATE SET;"));
+    }
+
+    #[test]
+    fn test_parse_script_rejects_only_the_statement_that_nests_too_deep() {
+        let sql = format!(
+            "SELECT * FROM users;\nSELECT * FROM t WHERE {}1{};\nDELETE FROM sessions WHERE (id = 1);",
+            "(".repeat(10_000),
+            ")".repeat(10_000)
+        );
+        let script = parse_script(&sql);
+        assert_eq!(script.statements.len(), 2);
+        assert!(matches!(script.statements[1], Statement::Delete { .. }));
+        assert_eq!(script.statements[1].span().line, 3);
+
+        assert_eq!(script.diagnostics.len(), 1);
+        let location = script.diagnostics[0].location();
+        assert_eq!(
+            (location.line, location.column),
+            (2, 23 + DEFAULT_MAX_DEPTH)
+        );
+        assert!(script.diagnostics[0]
+            .to_string()
+            .contains("nesting depth exceeds the limit of 64"));
+    }
+
+    #[test]
+    fn test_parse_script_accepts_empty_statements() {
+        let script = parse_script(";\nSELECT * FROM users;;\n ; -- nothing\n;DELETE FROM t;");
+        assert!(script.is_ok(), "{:?}", script.diagnostics);
+        assert_eq!(script.statements.len(), 2);
+        assert!(parse_script(";;").statements.is_empty());
+    }
+
+    #[test]
+    fn test_parse_script_does_not_resync_inside_words_or_strings() {
+        // "selected" and the string literal contain statement keywords
+        let sql = "DELETE FROM t WHERE selected = 'x; DELETE' AND;\nSELECT * FROM t;";
+        let script = parse_script(sql);
+        assert_eq!(script.statements.len(), 1);
+        assert_eq!(script.diagnostics.len(), 1);
+    }
 }
@@ -0,0 +1,512 @@
+use crate::ast::{BinaryOperator, Expression, Literal, Span, UnaryOperator};
+use crate::error::ParseError;
+use crate::parser::{build_data_type, unexpected, Children, Context, Rule};
+use pest::iterators::Pair;
+
+// Precedence is defined by BinaryOperator::precedence, shared with the SQL renderer
+fn infix_operator(rule: Rule) -> Option<BinaryOperator> {
+    Some(match rule {
+        Rule::OR => BinaryOperator::Or,
+        Rule::AND => BinaryOperator::And,
+        Rule::EQUAL => BinaryOperator::Equal,
+        Rule::NOT_EQUAL => BinaryOperator::NotEqual,
+        Rule::LESS_THAN => BinaryOperator::LessThan,
+        Rule::LESS_THAN_OR_EQUAL => BinaryOperator::LessThanOrEqual,
+        Rule::GREATER_THAN => BinaryOperator::GreaterThan,
+        Rule::GREATER_THAN_OR_EQUAL => BinaryOperator::GreaterThanOrEqual,
+        Rule::LIKE => BinaryOperator::Like,
+        Rule::ILIKE => BinaryOperator::ILike,
+        Rule::ADD => BinaryOperator::Add,
+        Rule::SUBTRACT => BinaryOperator::Subtract,
+        Rule::MULTIPLY => BinaryOperator::Multiply,
+        Rule::DIVIDE => BinaryOperator::Divide,
+        _ => return None,
+    })
+}
+
+// Builds an expression from the flat operand/operator sequence produced by the `expression` rule
+pub(crate) fn build_expression(cx: Context, pair: Pair<Rule>) -> Result<Expression, ParseError> {
+    build_nested(cx, pair).map(|(expression, _)| expression)
+}
+
+// An expression with the depth of its tree: 0 for a leaf, and one more than the deepest
+// operand for any other node. Everything that walks the tree recurses, so the depth is
+// bounded by max_depth, not only the nesting of parentheses
+type Built = (Expression, usize);
+
+fn build_nested(cx: Context, pair: Pair<Rule>) -> Result<Built, ParseError> {
+    match pair.as_rule() {
+        Rule::expression => {
+            let mut tokens = Children::of(pair);
+            build_binary(cx, &mut tokens, 0)
+        }
+        _ => build_primary(cx, pair),
+    }
+}
+
+// The depth of a node over operands of the given depth, located at its operator
+fn deeper(cx: Context, operands: usize, operator: pest::Span) -> Result<usize, ParseError> {
+    if operands >= cx.max_depth {
+        return Err(ParseError::semantic(
+            format!("nesting depth exceeds the limit of {}", cx.max_depth),
+            operator,
+        ));
+    }
+    Ok(operands + 1)
+}
+
+fn build_list<'i>(
+    cx: Context,
+    pairs: impl Iterator<Item = Pair<'i, Rule>>,
+) -> Result<(Vec<Expression>, usize), ParseError> {
+    let mut list = Vec::new();
+    let mut depth = 0;
+    for pair in pairs {
+        let (expression, item_depth) = build_nested(cx, pair)?;
+        list.push(expression);
+        depth = depth.max(item_depth);
+    }
+    Ok((list, depth))
+}
+
+// Precedence climbing: the recursion depth is bounded by the number of precedence levels,
+// not by the length of the input
+fn build_binary(
+    cx: Context,
+    tokens: &mut Children<Rule>,
+    min_power: u8,
+) -> Result<Built, ParseError> {
+    let (mut left, mut depth) = build_operand(cx, tokens)?;
+    loop {
+        // [NOT] IN (list) is a postfix operator as strong as LIKE
+        if let Some(pair) = tokens.peek().filter(|pair| pair.as_rule() == Rule::in_list) {
+            if BinaryOperator::Like.precedence() < min_power {
+                break;
+            }
+            let span = left.span().union(&cx.span_of(pair));
+            (left, depth) = build_in_list(cx, tokens.required()?, (left, depth), span)?;
+            continue;
+        }
+        let Some(operator) = tokens
+            .peek()
+            .and_then(|pair| infix_operator(pair.as_rule()))
+        else {
+            break;
+        };
+        let power = operator.precedence();
+        if power < min_power {
+            break;
+        }
+        let operator_pair = tokens.required()?;
+        if operator == BinaryOperator::ILike && !cx.dialect.supports_ilike() {
+            return Err(cx.unsupported("ILIKE", &operator_pair));
+        }
+        let (right, right_depth) = build_binary(cx, tokens, power + 1)?;
+        depth = deeper(cx, depth.max(right_depth), operator_pair.as_span())?;
+        let span = left.span().union(&right.span());
+        left = Expression::Binary {
+            left: Box::new(left),
+            operator,
+            right: Box::new(right),
+            span,
+        };
+    }
+    Ok((left, depth))
+}
+
+fn build_in_list(
+    cx: Context,
+    pair: Pair<Rule>,
+    (expression, expression_depth): Built,
+    span: Span,
+) -> Result<Built, ParseError> {
+    let operator = pair.as_span();
+    let mut inner = Children::of(pair);
+    let negated = inner.optional(Rule::NOT).is_some();
+    inner.consume(); // IN
+    let (list, list_depth) = build_list(cx, inner.required()?.into_inner())?;
+    let depth = deeper(cx, expression_depth.max(list_depth), operator)?;
+    let expression = Expression::InList {
+        expression: Box::new(expression),
+        list,
+        negated,
+        span,
+    };
+    Ok((expression, depth))
+}
+
+fn build_operand(cx: Context, tokens: &mut Children<Rule>) -> Result<Built, ParseError> {
+    // Prefix operators apply innermost first: NOT -x is NOT (-x)
+    let mut operators = Vec::new();
+    let operand_pair = loop {
+        let pair = tokens.required()?;
+        match pair.as_rule() {
+            Rule::NOT => operators.push((UnaryOperator::Not, pair)),
+            Rule::MINUS => operators.push((UnaryOperator::Minus, pair)),
+            _ => break pair,
+        }
+    };
+    // i64::MIN has no positive counterpart, so a minus sign directly before its digits is
+    // read as part of the literal
+    let digits = match operators.last() {
+        Some((UnaryOperator::Minus, _)) => number_literal(&operand_pair),
+        _ => None,
+    };
+    let minimum = match (operators.last(), digits) {
+        (Some((_, minus)), Some(digits)) if digits.as_str().parse::<i64>().is_err() => {
+            let text = format!("-{}", digits.as_str());
+            let value: i64 = text.parse().map_err(|_| {
+                ParseError::semantic(
+                    format!("integer literal {} is out of range", text),
+                    digits.as_span(),
+                )
+            })?;
+            Some(Expression::Literal {
+                value: Literal::Number(value),
+                span: cx.span_of(minus).union(&cx.span_of(&digits)),
+            })
+        }
+        _ => None,
+    };
+    let (mut expr, mut depth) = match minimum {
+        Some(literal) => {
+            operators.pop();
+            (literal, 0)
+        }
+        None => build_primary(cx, operand_pair)?,
+    };
+    // Casts bind tighter than prefix operators: -x::int is -(x::int)
+    while let Some(cast_pair) = tokens.optional(Rule::cast_suffix) {
+        if !cx.dialect.supports_double_colon_cast() {
+            return Err(cx.unsupported("the :: cast", &cast_pair));
+        }
+        depth = deeper(cx, depth, cast_pair.as_span())?;
+        let span = expr.span().union(&cx.span_of(&cast_pair));
+        let data_type = build_data_type(cx, Children::of(cast_pair).required()?)?;
+        expr = Expression::Cast {
+            expression: Box::new(expr),
+            data_type,
+            span,
+        };
+    }
+    for (operator, operator_pair) in operators.into_iter().rev() {
+        depth = deeper(cx, depth, operator_pair.as_span())?;
+        let span = cx.span_of(&operator_pair).union(&expr.span());
+        expr = Expression::Unary {
+            operator,
+            operand: Box::new(expr),
+            span,
+        };
+    }
+    Ok((expr, depth))
+}
+
+// The number literal that a primary expression consists of, if any
+fn number_literal<'i>(pair: &Pair<'i, Rule>) -> Option<Pair<'i, Rule>> {
+    match pair.as_rule() {
+        Rule::number_literal => Some(pair.clone()),
+        Rule::primary_expression => {
+            let mut inner = pair.clone().into_inner();
+            match (inner.next(), inner.next()) {
+                (Some(only), None) => number_literal(&only),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+fn build_primary(cx: Context, pair: Pair<Rule>) -> Result<Built, ParseError> {
+    let leaf = match pair.as_rule() {
+        Rule::expression => return build_nested(cx, pair),
+        Rule::primary_expression => return build_primary(cx, Children::of(pair).required()?),
+        Rule::string_literal => Expression::Literal {
+            value: Literal::String(cx.string_value(&pair)),
+            span: cx.span_of(&pair),
+        },
+        Rule::number_literal => {
+            let num: i64 = pair.as_str().parse().map_err(|_| {
+                ParseError::semantic(
+                    format!("integer literal {} is out of range", pair.as_str()),
+                    pair.as_span(),
+                )
+            })?;
+            Expression::Literal {
+                value: Literal::Number(num),
+                span: cx.span_of(&pair),
+            }
+        }
+        Rule::float_literal => {
+            let num: f64 = pair.as_str().parse().map_err(|_| {
+                ParseError::semantic(
+                    format!("invalid float literal {}", pair.as_str()),
+                    pair.as_span(),
+                )
+            })?;
+            Expression::Literal {
+                value: Literal::Float(num),
+                span: cx.span_of(&pair),
+            }
+        }
+        Rule::boolean_literal => {
+            let is_true = pair.as_str().eq_ignore_ascii_case("TRUE");
+            Expression::Literal {
+                value: Literal::Boolean(is_true),
+                span: cx.span_of(&pair),
+            }
+        }
+        Rule::null_literal => Expression::Literal {
+            value: Literal::Null,
+            span: cx.span_of(&pair),
+        },
+        Rule::identifier => Expression::Column(cx.ident_of(&pair)?),
+        Rule::qualified_identifier => {
+            let mut inner = Children::of(pair);
+            let table = cx.ident_of(&inner.required()?)?;
+            let column = cx.ident_of(&inner.required()?)?;
+            Expression::QualifiedColumn { table, column }
+        }
+        Rule::parameter => Expression::Parameter {
+            name: pair.as_str().to_string(),
+            span: cx.span_of(&pair),
+        },
+        Rule::function_call | Rule::grouping_function | Rule::cast_function => {
+            return build_call(cx, pair)
+        }
+        _ => return Err(unexpected(&pair)),
+    };
+    Ok((leaf, 0))
+}
+
+fn build_call(cx: Context, pair: Pair<Rule>) -> Result<Built, ParseError> {
+    let span = cx.span_of(&pair);
+    let call = pair.as_span();
+    let rule = pair.as_rule();
+    let mut inner = Children::of(pair);
+    let (expression, operands) = match rule {
+        Rule::function_call => {
+            let name = cx.function_name_of(&inner.required()?);
+            let (args, depth) = build_list(cx, inner)?;
+            (Expression::Function { name, args, span }, depth)
+        }
+        Rule::grouping_function => {
+            inner.consume(); // Consume GROUPING keyword
+            let (args, depth) = build_list(cx, inner.required()?.into_inner())?;
+            (Expression::Grouping { args, span }, depth)
+        }
+        _ => {
+            inner.consume(); // Consume CAST keyword
+            let (expression, depth) = build_nested(cx, inner.required()?)?;
+            inner.consume(); // Consume AS keyword
+            let data_type = build_data_type(cx, inner.required()?)?;
+            let cast = Expression::Cast {
+                expression: Box::new(expression),
+                data_type,
+                span,
+            };
+            (cast, depth)
+        }
+    };
+    Ok((expression, deeper(cx, operands, call)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ast::{BinaryOperator, Expression, Literal, UnaryOperator};
+    use crate::error::ParseError;
+    use crate::expression::{parse_expression, parse_expression_with_options};
+    use crate::parser::{parse_sql, ParseOptions, DEFAULT_MAX_DEPTH};
+
+    // Renders the tree with explicit parentheses to make the grouping easy to assert
+    fn grouping(expr: &Expression) -> String {
+        match expr {
+            Expression::Binary {
+                left,
+                operator,
+                right,
+                ..
+            } => format!("({} {:?} {})", grouping(left), operator, grouping(right)),
+            Expression::Unary {
+                operator, operand, ..
+            } => format!("({:?} {})", operator, grouping(operand)),
+            Expression::InList {
+                expression,
+                list,
+                negated,
+                ..
+            } => format!(
+                "({} {}In [{}])",
+                grouping(expression),
+                if *negated { "Not" } else { "" },
+                list.iter().map(grouping).collect::<Vec<_>>().join(", ")
+            ),
+            Expression::Column(ident) => ident.value.clone(),
+            other => format!("{:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_precedence_and_associativity() {
+        let expr = parse_expression("a OR b AND c = d + e * f - g / h").unwrap();
+        assert_eq!(
+            grouping(&expr),
+            "(a Or (b And (c Equal ((d Add (e Multiply f)) Subtract (g Divide h)))))"
+        );
+
+        let expr = parse_expression("a - b - c < d").unwrap();
+        assert_eq!(grouping(&expr), "(((a Subtract b) Subtract c) LessThan d)");
+    }
+
+    #[test]
+    fn test_prefix_operators_bind_tightest() {
+        let expr = parse_expression("NOT a = - b * c").unwrap();
+        assert_eq!(grouping(&expr), "((Not a) Equal ((Minus b) Multiply c))");
+        assert!(matches!(
+            expr,
+            Expression::Binary {
+                operator: BinaryOperator::Equal,
+                ..
+            }
+        ));
+        let expr = parse_expression("NOT (a = b)").unwrap();
+        assert!(matches!(
+            expr,
+            Expression::Unary {
+                operator: UnaryOperator::Not,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_in_list_binds_like_like() {
+        let expr = parse_expression("a + b IN (c, d) AND e NOT IN (f) = g").unwrap();
+        assert_eq!(
+            grouping(&expr),
+            "(((a Add b) In [c, d]) And ((e NotIn [f]) Equal g))"
+        );
+        let expr = parse_expression("NOT a IN (b)").unwrap();
+        assert_eq!(grouping(&expr), "((Not a) In [b])");
+        let expr = parse_expression("a IN (b) IN (c)").unwrap();
+        assert_eq!(grouping(&expr), "((a In [b]) In [c])");
+        assert_eq!(expr.span().end, 15);
+        assert!(parse_expression("a IN ()").is_err());
+    }
+
+    #[test]
+    fn test_minimum_integer_literal() {
+        let expr = parse_expression("x = -9223372036854775808").unwrap();
+        let Expression::Binary { right, .. } = &expr else {
+            panic!("expected a comparison");
+        };
+        assert!(matches!(
+            right.as_ref(),
+            Expression::Literal {
+                value: Literal::Number(i64::MIN),
+                ..
+            }
+        ));
+        assert_eq!(right.span().start, 4);
+        assert_eq!(expr.to_string(), "x = -9223372036854775808");
+
+        // Other negative numbers stay the minus operator applied to the number
+        assert!(matches!(
+            parse_expression("-1").unwrap(),
+            Expression::Unary { .. }
+        ));
+        let error = parse_expression("x = -9223372036854775809").unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("integer literal -9223372036854775809 is out of range"));
+        assert!(parse_expression("x = 9223372036854775808").is_err());
+    }
+
+    #[test]
+    fn test_long_operator_chain() {
+        let options = ParseOptions {
+            max_depth: 1000,
+            ..ParseOptions::default()
+        };
+        let sql = vec!["a"; 1001].join(" OR ");
+        let expr = parse_expression_with_options(&sql, &options).unwrap();
+        assert!(matches!(
+            expr,
+            Expression::Binary {
+                operator: BinaryOperator::Or,
+                ..
+            }
+        ));
+    }
+
+    // Rendering, walking and dropping the tree recurse, so chains of operators count towards
+    // the limit like parentheses do
+    #[test]
+    fn test_operator_chains_are_bounded() {
+        let chains: [fn(usize) -> String; 5] = [
+            |n| format!("a{}", " OR a".repeat(n)),
+            |n| format!("{}a", "NOT ".repeat(n)),
+            |n| format!("{}a", "- ".repeat(n)),
+            |n| format!("a{}", "::int".repeat(n)),
+            |n| format!("a{}", " IN (b)".repeat(n)),
+        ];
+        for chain in chains {
+            let sql = format!("SELECT * FROM t WHERE {};", chain(DEFAULT_MAX_DEPTH));
+            let statement = parse_sql(&sql).unwrap();
+            assert_eq!(parse_sql(&statement.to_string()).unwrap(), statement);
+            drop(statement);
+
+            let sql = format!("SELECT * FROM t WHERE {};", chain(DEFAULT_MAX_DEPTH + 1));
+            let error = parse_sql(&sql).unwrap_err();
+            assert!(error
+                .to_string()
+                .contains("nesting depth exceeds the limit of 64"));
+            let sql = format!("SELECT * FROM t WHERE {};", chain(100_000));
+            assert!(parse_sql(&sql).is_err());
+        }
+        // The error points at the first operator past the limit
+        let sql = vec!["a"; 100_000].join(" OR ");
+        let error = parse_expression(&sql).unwrap_err();
+        assert_eq!(error.location().offset, 5 * DEFAULT_MAX_DEPTH + 2);
+        // Function calls and their arguments nest as well
+        let sql = format!("{}a{}", "f(1, ".repeat(40), ")".repeat(40));
+        let options = ParseOptions {
+            max_depth: 30,
+            ..ParseOptions::default()
+        };
+        assert!(parse_expression_with_options(&sql, &options).is_err());
+    }
+
+    #[test]
+    fn test_nesting_up_to_the_limit() {
+        let sql = format!(
+            "SELECT * FROM users WHERE {}1{} = 1;",
+            "(".repeat(DEFAULT_MAX_DEPTH),
+            ")".repeat(DEFAULT_MAX_DEPTH)
+        );
+        assert!(parse_sql(&sql).is_ok());
+    }
+
+    #[test]
+    fn test_deep_nesting_returns_error() {
+        let sql = format!("SELECT * FROM users WHERE {}1;", "(".repeat(10_000));
+        let error = parse_sql(&sql).unwrap_err();
+        assert!(matches!(error, ParseError::Semantic { .. }));
+        assert_eq!(error.location().offset, 26 + DEFAULT_MAX_DEPTH);
+        assert!(error
+            .to_string()
+            .contains("nesting depth exceeds the limit of 64"));
+    }
+
+    #[test]
+    fn test_configured_max_depth() {
+        let options = ParseOptions {
+            max_depth: 2,
+            ..ParseOptions::default()
+        };
+        assert!(parse_expression_with_options("f((1))", &options).is_ok());
+        assert!(parse_expression_with_options("f(((1)))", &options).is_err());
+        // Parentheses inside string literals and comments do not count
+        assert!(parse_expression_with_options("f('(((')", &options).is_ok());
+        assert!(parse_expression_with_options("a -- (((\n + 1", &options).is_ok());
+    }
+}
@@ -0,0 +1,434 @@
+use crate::ast::{ColumnDefinition, Expression, Ident, OrderByItem, Span, Statement};
+use crate::error::{Location, ParseError};
+use crate::parser::{parse_statement_with_options, ParseOptions};
+use crate::visitor::{walk_statement_mut, Flow, VisitorMut};
+use std::io::{BufRead, ErrorKind};
+
+// Statements longer than this are reported and skipped rather than buffered
+pub const DEFAULT_MAX_STATEMENT_SIZE: usize = 64 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Code,
+    Quoted { delimiter: u8, escaped: bool },
+    Comment,
+}
+
+// Reads statements one at a time from a stream, such as a database dump too large to hold in
+// memory. Only the statement being read is buffered. Statements end at a ";" outside quotes
+// and comments, and their spans and errors are located in the whole stream. Unlike
+// `parse_script`, a statement that is missing its ";" is not recovered at the next keyword
+pub struct StatementReader<R> {
+    reader: R,
+    options: ParseOptions,
+    max_statement_size: usize,
+    buffer: Vec<u8>,
+    // How much of the buffer has been scanned, and the state at that point
+    scanned: usize,
+    state: State,
+    // Whether the buffered statement has anything besides whitespace and comments
+    content: bool,
+    // Where the buffer starts in the stream
+    offset: usize,
+    line: usize,
+    column: usize,
+    // Set after a statement exceeded the size limit, until its ";" has been dropped
+    skipping: bool,
+    eof: bool,
+    done: bool,
+}
+
+impl<R: BufRead> StatementReader<R> {
+    pub fn new(reader: R) -> Self {
+        StatementReader::with_options(reader, ParseOptions::default())
+    }
+
+    pub fn with_options(reader: R, options: ParseOptions) -> Self {
+        StatementReader {
+            reader,
+            options,
+            max_statement_size: DEFAULT_MAX_STATEMENT_SIZE,
+            buffer: Vec::new(),
+            scanned: 0,
+            state: State::Code,
+            content: false,
+            offset: 0,
+            line: 1,
+            column: 1,
+            skipping: false,
+            eof: false,
+            done: false,
+        }
+    }
+
+    pub fn max_statement_size(mut self, bytes: usize) -> Self {
+        self.max_statement_size = bytes;
+        self
+    }
+
+    // The byte offset in the stream where the next statement starts
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    // Scans the buffer for the end of the statement, and returns the length of the statement
+    // including its ";". Stops early when a "-" might start a comment in the next read
+    fn scan(&mut self) -> Option<usize> {
+        let backslash_escapes = self.options.dialect.backslash_escapes();
+        let backticks = self.options.dialect.supports_backtick_identifiers();
+        while self.scanned < self.buffer.len() {
+            let byte = self.buffer[self.scanned];
+            let next = self.buffer.get(self.scanned + 1).copied();
+            self.state = match self.state {
+                State::Code => {
+                    let comment =
+                        byte == b'-' && (next == Some(b'-') || (next.is_none() && !self.eof));
+                    if !byte.is_ascii_whitespace() && !comment && byte != b';' {
+                        self.content = true;
+                    }
+                    match byte {
+                        b';' => {
+                            self.scanned += 1;
+                            return Some(self.scanned);
+                        }
+                        b'-' if next.is_none() && !self.eof => return None,
+                        b'-' if next == Some(b'-') => {
+                            self.scanned += 1;
+                            State::Comment
+                        }
+                        b'\'' | b'"' => State::Quoted {
+                            delimiter: byte,
+                            escaped: false,
+                        },
+                        b'`' if backticks => State::Quoted {
+                            delimiter: byte,
+                            escaped: false,
+                        },
+                        _ => State::Code,
+                    }
+                }
+                // A doubled delimiter closes the token and opens it again
+                State::Quoted { delimiter, escaped } => {
+                    if escaped {
+                        State::Quoted {
+                            delimiter,
+                            escaped: false,
+                        }
+                    } else if byte == b'\\' && backslash_escapes && delimiter != b'`' {
+                        State::Quoted {
+                            delimiter,
+                            escaped: true,
+                        }
+                    } else if byte == delimiter {
+                        State::Code
+                    } else {
+                        self.state
+                    }
+                }
+                State::Comment if byte == b'\n' => State::Code,
+                State::Comment => State::Comment,
+            };
+            self.scanned += 1;
+        }
+        None
+    }
+
+    // Removes the first `length` bytes of the buffer and moves the stream position past them
+    fn take(&mut self, length: usize) -> (Vec<u8>, Shift) {
+        let shift = Shift {
+            offset: self.offset,
+            lines: self.line - 1,
+            columns: self.column - 1,
+        };
+        let taken: Vec<u8> = self.buffer.drain(..length).collect();
+        for &byte in &taken {
+            if byte == b'\n' {
+                self.line += 1;
+                self.column = 1;
+            } else if byte & 0xC0 != 0x80 {
+                // Columns count characters, so UTF-8 continuation bytes are skipped
+                self.column += 1;
+            }
+        }
+        self.offset += length;
+        self.scanned -= length;
+        (taken, shift)
+    }
+
+    fn parse(&self, bytes: Vec<u8>, mut shift: Shift) -> Result<Statement, ParseError> {
+        let sql = String::from_utf8(bytes).map_err(|error| {
+            let valid = error.utf8_error().valid_up_to();
+            let prefix =
+                std::str::from_utf8(&error.as_bytes()[..valid]).expect("the prefix is valid UTF-8");
+            let span = pest::Span::new(prefix, valid, valid).expect("the offset is in range");
+            shift.error(ParseError::semantic("invalid UTF-8", span))
+        })?;
+        let mut statement = parse_statement_with_options(&sql, &self.options)
+            .map_err(|error| shift.error(error))?;
+        walk_statement_mut(&mut shift, &mut statement);
+        Ok(statement)
+    }
+
+    // An error at the start of the buffered statement, for problems that have no source text
+    fn error_here(&self, message: String) -> ParseError {
+        ParseError::Semantic {
+            location: Location {
+                offset: self.offset,
+                line: self.line,
+                column: self.column,
+            },
+            message,
+            source_line: String::new(),
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for StatementReader<R> {
+    type Item = Result<Statement, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.done {
+            if let Some(length) = self.scan() {
+                let (bytes, shift) = self.take(length);
+                let content = std::mem::take(&mut self.content);
+                // An oversized statement has been reported already, and an empty one is dropped
+                if std::mem::take(&mut self.skipping) || !content {
+                    continue;
+                }
+                return Some(self.parse(bytes, shift));
+            }
+            if self.skipping {
+                // The rest of an oversized statement is dropped as it is scanned
+                self.take(self.scanned);
+            } else if self.buffer.len() > self.max_statement_size {
+                let error = self.error_here(format!(
+                    "statement exceeds the limit of {} bytes",
+                    self.max_statement_size
+                ));
+                self.skipping = true;
+                self.take(self.scanned);
+                return Some(Err(error));
+            }
+            if self.eof {
+                self.done = true;
+                if self.content && !self.skipping {
+                    let (bytes, shift) = self.take(self.buffer.len());
+                    return Some(self.parse(bytes, shift));
+                }
+                return None;
+            }
+            match self.reader.fill_buf() {
+                Ok([]) => self.eof = true,
+                Ok(data) => {
+                    let length = data.len();
+                    self.buffer.extend_from_slice(data);
+                    self.reader.consume(length);
+                }
+                Err(error) if error.kind() == ErrorKind::Interrupted => {}
+                Err(error) => {
+                    self.done = true;
+                    return Some(Err(self.error_here(format!("read error: {}", error))));
+                }
+            }
+        }
+        None
+    }
+}
+
+// Moves spans from a statement parsed on its own to where the statement is in the stream
+#[derive(Debug, Clone, Copy)]
+struct Shift {
+    offset: usize,
+    lines: usize,
+    // Applies to the first line of the statement only
+    columns: usize,
+}
+
+impl Shift {
+    fn span(&self, span: &mut Span) {
+        span.start += self.offset;
+        span.end += self.offset;
+        if span.line == 1 {
+            span.column += self.columns;
+        }
+        span.line += self.lines;
+    }
+
+    fn error(&self, error: ParseError) -> ParseError {
+        error.shift(self.offset, self.lines, self.columns)
+    }
+}
+
+impl VisitorMut for Shift {
+    fn pre_visit_statement(&mut self, statement: &mut Statement) -> Flow {
+        match statement {
+            Statement::Select { span, .. }
+            | Statement::Insert { span, .. }
+            | Statement::Update { span, .. }
+            | Statement::Delete { span, .. }
+            | Statement::Merge { span, .. }
+            | Statement::Prepare { span, .. }
+            | Statement::Execute { span, .. }
+            | Statement::Deallocate { span, .. }
+            | Statement::CreateTable { span, .. } => self.span(span),
+        }
+        Flow::Continue
+    }
+
+    // Column references have no span of their own; their identifiers are visited instead
+    fn pre_visit_expression(&mut self, expression: &mut Expression) -> Flow {
+        match expression {
+            Expression::Column(_) | Expression::QualifiedColumn { .. } => {}
+            Expression::Literal { span, .. }
+            | Expression::Function { span, .. }
+            | Expression::Grouping { span, .. }
+            | Expression::Parameter { span, .. }
+            | Expression::Binary { span, .. }
+            | Expression::Unary { span, .. }
+            | Expression::Cast { span, .. }
+            | Expression::InList { span, .. } => self.span(span),
+        }
+        Flow::Continue
+    }
+
+    fn pre_visit_ident(&mut self, ident: &mut Ident) -> Flow {
+        self.span(&mut ident.span);
+        Flow::Continue
+    }
+
+    fn pre_visit_order_by_item(&mut self, item: &mut OrderByItem) -> Flow {
+        self.span(&mut item.span);
+        Flow::Continue
+    }
+
+    fn pre_visit_column_definition(&mut self, column: &mut ColumnDefinition) -> Flow {
+        self.span(&mut column.span);
+        Flow::Continue
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dialect::Dialect;
+    use crate::parser::parse_script_with_options;
+    use std::io::BufReader;
+
+    // A tiny read buffer splits quotes, comments and characters across reads
+    fn read(sql: &str, dialect: Dialect) -> Vec<Result<Statement, ParseError>> {
+        let options = ParseOptions {
+            dialect,
+            ..ParseOptions::default()
+        };
+        StatementReader::with_options(BufReader::with_capacity(3, sql.as_bytes()), options)
+            .collect()
+    }
+
+    #[test]
+    fn test_matches_parse_script() {
+        let sql = "-- users; and more\nSELECT * FROM users WHERE name = 'a;b' AND \"semi;colon\" = 1;\n\
+                   DELETE FROM sessions; UPDATE users SET name = 'それ;' WHERE id = -1;\n\n\
+                   CREATE TABLE t (id INTEGER PRIMARY KEY, note TEXT DEFAULT 'x''; y');\n\
+                   MERGE INTO t USING s ON t.id = s.id WHEN MATCHED THEN UPDATE SET note = s.note;\n\
+                   PREPARE q (integer) AS SELECT * FROM t WHERE id = $1 ORDER BY id DESC; -- done";
+        let options = ParseOptions::default();
+        let script = parse_script_with_options(sql, &options);
+        assert!(script.is_ok());
+
+        let statements: Vec<Statement> = read(sql, Dialect::Generic)
+            .into_iter()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        // Debug output includes the spans, which PartialEq ignores
+        assert_eq!(
+            format!("{:?}", statements),
+            format!("{:?}", script.statements)
+        );
+        assert_eq!(statements.len(), 6);
+    }
+
+    #[test]
+    fn test_errors_are_located_in_the_stream() {
+        let sql = "SELECT * FROM users;\nSELECT * FROM ;\nDELETE FROM sessions; SELEC * FROM t;";
+        let items = read(sql, Dialect::Generic);
+        assert_eq!(items.len(), 4);
+        assert!(items[0].is_ok() && items[2].is_ok());
+
+        let location = items[1].as_ref().unwrap_err().location();
+        assert_eq!((location.line, location.column), (2, 15));
+        assert_eq!(location.offset, sql.find(" ;").unwrap() + 1);
+
+        let error = items[3].as_ref().unwrap_err();
+        let location = error.location();
+        assert_eq!((location.line, location.column), (3, 23));
+        assert_eq!(location.offset, sql.find("SELEC ").unwrap());
+        assert!(error
+            .to_string()
+            .ends_with(" SELEC * FROM t;\n  |                       ^"));
+    }
+
+    #[test]
+    fn test_end_of_stream() {
+        assert!(read(
+            "SELECT * FROM t;\n-- trailing comment\n  ",
+            Dialect::Generic
+        )
+        .into_iter()
+        .all(|item| item.is_ok()));
+        assert_eq!(read("", Dialect::Generic).len(), 0);
+
+        // Empty statements are dropped, as parse_script drops them
+        let items = read(
+            ";\nSELECT * FROM t;; -- x\n ;DELETE FROM t;",
+            Dialect::Generic,
+        );
+        assert_eq!(items.len(), 2);
+        assert!(items.iter().all(|item| item.is_ok()), "{:?}", items);
+
+        // The last statement is parsed even without its ";", which the grammar requires
+        let items = read("SELECT * FROM t;\nDELETE FROM t", Dialect::Generic);
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[1].as_ref().unwrap_err().location().line, 2);
+    }
+
+    #[test]
+    fn test_dialect_quoting() {
+        let sql = "INSERT INTO t VALUES ('it\\'s; fine');\nINSERT INTO `a;b` VALUES (\"x;y\");";
+        let items = read(sql, Dialect::MySql);
+        assert_eq!(items.len(), 2);
+        assert!(items.iter().all(|item| item.is_ok()), "{:?}", items);
+
+        // Outside MySQL the backslash is an ordinary character and the quote ends the string
+        assert!(read("SELECT * FROM t WHERE a = 'x\\';", Dialect::PostgreSql)[0].is_ok());
+    }
+
+    #[test]
+    fn test_oversized_statement_is_skipped() {
+        let sql = "SELECT * FROM a_rather_long_table_name;\nDELETE FROM t;\n";
+        let mut reader = StatementReader::new(BufReader::with_capacity(4, sql.as_bytes()))
+            .max_statement_size(16);
+        let error = reader.next().unwrap().unwrap_err();
+        assert_eq!(
+            error.to_string().lines().next().unwrap(),
+            "error at line 1, column 1: statement exceeds the limit of 16 bytes"
+        );
+        let statement = reader.next().unwrap().unwrap();
+        assert_eq!(statement.span().line, 2);
+        assert_eq!(statement.span().start, sql.find("DELETE").unwrap());
+        assert_eq!(reader.offset(), sql.len() - 1);
+        assert!(reader.next().is_none());
+    }
+
+    #[test]
+    fn test_invalid_utf8() {
+        let bytes = b"SELECT * FROM t;\nDELETE FROM \xff;\nDELETE FROM t;";
+        let items: Vec<_> = StatementReader::new(&bytes[..]).collect();
+        assert_eq!(items.len(), 3);
+        let location = items[1].as_ref().unwrap_err().location();
+        assert_eq!(
+            (location.offset, location.line, location.column),
+            (29, 2, 13)
+        );
+        assert!(items[2].is_ok());
+    }
+}
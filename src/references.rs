@@ -0,0 +1,452 @@
+use std::collections::BTreeSet;
+
+use crate::ast::{
+    same_name, ColumnDefinition, ConflictTarget, Expression, Ident, MergeAction, MergeSource,
+    ObjectName, OnConflict, OrderByItem, Statement,
+};
+use crate::visitor::{walk_statement, Flow, Visitor};
+
+// A table, or a column of a table. The table of a column is None when the statement does not
+// say which of its tables the column belongs to
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Reference {
+    Table(String),
+    Column {
+        table: Option<String>,
+        column: String,
+    },
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct References {
+    pub reads: BTreeSet<Reference>,
+    pub writes: BTreeSet<Reference>,
+}
+
+// Collects the tables and columns that a statement reads and writes.
+// Aliases and EXCLUDED are resolved to the tables they stand for
+pub fn references(statement: &Statement) -> References {
+    let mut collector = Collector::default();
+    walk_statement(&mut collector, statement);
+    collector.references
+}
+
+// The name of a table as it is reported, such as app.users
+pub(crate) fn table_name(table: &ObjectName) -> String {
+    match &table.schema {
+        Some(schema) => format!("{}.{}", schema.value, table.name.value),
+        None => table.name.value.clone(),
+    }
+}
+
+// The tables visible to a statement
+#[derive(Default)]
+pub(crate) struct Scope {
+    // The table that the statement writes
+    pub(crate) target: Option<String>,
+    // The table of unqualified columns
+    pub(crate) default: Option<String>,
+    // Qualifiers that stand for another table, as (qualifier, table)
+    aliases: Vec<(String, String)>,
+}
+
+impl Scope {
+    pub(crate) fn of(statement: &Statement) -> Scope {
+        match statement {
+            Statement::Select { table, .. }
+            | Statement::Update { table, .. }
+            | Statement::Delete { table, .. }
+            | Statement::CreateTable { table, .. } => Scope::single_table(table),
+            Statement::Insert { table, .. } => {
+                let mut scope = Scope::single_table(table);
+                // EXCLUDED in ON CONFLICT DO UPDATE is the row that was proposed for insertion
+                scope
+                    .aliases
+                    .push(("EXCLUDED".to_string(), table_name(table)));
+                scope
+            }
+            Statement::Merge {
+                table,
+                alias,
+                source,
+                ..
+            } => {
+                // Unqualified columns may belong to either the target or the source
+                let mut scope = Scope {
+                    target: Some(table_name(table)),
+                    default: None,
+                    aliases: Vec::new(),
+                };
+                scope.add_table(table, alias.as_ref());
+                if let Some((name, alias)) = source_table(source) {
+                    scope.add_table(name, alias);
+                }
+                scope
+            }
+            Statement::Prepare { .. }
+            | Statement::Execute { .. }
+            | Statement::Deallocate { .. } => Scope::default(),
+        }
+    }
+
+    fn single_table(table: &ObjectName) -> Scope {
+        let mut scope = Scope {
+            target: Some(table_name(table)),
+            default: Some(table_name(table)),
+            aliases: Vec::new(),
+        };
+        scope.add_table(table, None);
+        scope
+    }
+
+    // A table qualified with its schema can also be referred to by its name alone
+    fn add_table(&mut self, table: &ObjectName, alias: Option<&Ident>) {
+        if let Some(alias) = alias {
+            self.aliases.push((alias.value.clone(), table_name(table)));
+        }
+        if table.schema.is_some() {
+            self.aliases
+                .push((table.name.value.clone(), table_name(table)));
+        }
+    }
+
+    // The table that a column qualifier stands for
+    pub(crate) fn resolve(&self, qualifier: &Ident) -> String {
+        self.aliases
+            .iter()
+            .find(|(alias, _)| same_name(alias, &qualifier.value))
+            .map_or_else(|| qualifier.value.clone(), |(_, table)| table.clone())
+    }
+}
+
+#[derive(Default)]
+struct Collector {
+    references: References,
+    scopes: Vec<Scope>,
+}
+
+impl Collector {
+    fn scope(&self) -> Option<&Scope> {
+        self.scopes.last()
+    }
+
+    fn target(&self) -> Option<String> {
+        self.scope().and_then(|scope| scope.target.clone())
+    }
+
+    fn read_table(&mut self, table: &ObjectName) {
+        self.references
+            .reads
+            .insert(Reference::Table(table_name(table)));
+    }
+
+    fn write_table(&mut self, table: &ObjectName) {
+        self.references
+            .writes
+            .insert(Reference::Table(table_name(table)));
+    }
+
+    fn read_column(&mut self, table: Option<String>, column: &Ident) {
+        self.references.reads.insert(Reference::Column {
+            table,
+            column: column.value.clone(),
+        });
+    }
+
+    fn write_column(&mut self, column: &Ident) {
+        let table = self.target();
+        self.references.writes.insert(Reference::Column {
+            table,
+            column: column.value.clone(),
+        });
+    }
+}
+
+// The table that a MERGE source stands for. A subquery always selects every column of one table
+fn source_table(source: &MergeSource) -> Option<(&ObjectName, Option<&Ident>)> {
+    match source {
+        MergeSource::Table { name, alias } => Some((name, alias.as_ref())),
+        MergeSource::Subquery { query, alias } => match query.as_ref() {
+            Statement::Select { table, .. } => Some((table, alias.as_ref())),
+            _ => None,
+        },
+    }
+}
+
+impl Visitor for Collector {
+    fn pre_visit_statement(&mut self, statement: &Statement) -> Flow {
+        match statement {
+            Statement::Select { table, .. } => self.read_table(table),
+            Statement::Insert { table, .. }
+            | Statement::Update { table, .. }
+            | Statement::Delete { table, .. }
+            | Statement::CreateTable { table, .. } => self.write_table(table),
+            Statement::Merge { table, source, .. } => {
+                self.write_table(table);
+                if let MergeSource::Table { name, .. } = source {
+                    self.read_table(name);
+                }
+            }
+            Statement::Prepare { .. }
+            | Statement::Execute { .. }
+            | Statement::Deallocate { .. } => {}
+        }
+        self.scopes.push(Scope::of(statement));
+        if let Statement::Update { set, .. } = statement {
+            for (column, _) in set {
+                self.write_column(column);
+            }
+        }
+        Flow::Continue
+    }
+
+    fn post_visit_statement(&mut self, _statement: &Statement) -> Flow {
+        self.scopes.pop();
+        Flow::Continue
+    }
+
+    fn pre_visit_expression(&mut self, expression: &Expression) -> Flow {
+        match expression {
+            Expression::Column(column) => {
+                let table = self.scope().and_then(|scope| scope.default.clone());
+                self.read_column(table, column);
+            }
+            Expression::QualifiedColumn { table, column } => {
+                let table = self
+                    .scope()
+                    .map_or_else(|| table.value.clone(), |scope| scope.resolve(table));
+                self.read_column(Some(table), column);
+            }
+            _ => {}
+        }
+        Flow::Continue
+    }
+
+    fn pre_visit_order_by_item(&mut self, item: &OrderByItem) -> Flow {
+        let table = self.scope().and_then(|scope| scope.default.clone());
+        self.read_column(table, &item.column);
+        Flow::Continue
+    }
+
+    fn pre_visit_on_conflict(&mut self, on_conflict: &OnConflict) -> Flow {
+        match on_conflict {
+            OnConflict::DoUpdate { set, .. } | OnConflict::DuplicateKeyUpdate { set } => {
+                for (column, _) in set {
+                    self.write_column(column);
+                }
+            }
+            OnConflict::DoNothing { .. } => {}
+        }
+        Flow::Continue
+    }
+
+    fn pre_visit_conflict_target(&mut self, target: &ConflictTarget) -> Flow {
+        if let ConflictTarget::Columns(columns) = target {
+            let table = self.target();
+            for column in columns {
+                self.read_column(table.clone(), column);
+            }
+        }
+        Flow::Continue
+    }
+
+    fn pre_visit_merge_action(&mut self, action: &MergeAction) -> Flow {
+        match action {
+            MergeAction::Update { set } => {
+                for (column, _) in set {
+                    self.write_column(column);
+                }
+            }
+            MergeAction::Insert { columns, .. } => {
+                for column in columns {
+                    self.write_column(column);
+                }
+            }
+            MergeAction::Delete => {}
+        }
+        Flow::Continue
+    }
+
+    fn pre_visit_column_definition(&mut self, column: &ColumnDefinition) -> Flow {
+        self.write_column(&column.name);
+        Flow::Continue
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_statement;
+
+    fn table(name: &str) -> Reference {
+        Reference::Table(name.to_string())
+    }
+
+    fn column(table: Option<&str>, name: &str) -> Reference {
+        Reference::Column {
+            table: table.map(str::to_string),
+            column: name.to_string(),
+        }
+    }
+
+    fn references_of(sql: &str) -> (Vec<Reference>, Vec<Reference>) {
+        let references = references(&parse_statement(sql).unwrap());
+        (
+            references.reads.into_iter().collect(),
+            references.writes.into_iter().collect(),
+        )
+    }
+
+    #[test]
+    fn test_select_reads_table_and_columns() {
+        let (reads, writes) = references_of(
+            "SELECT * FROM users WHERE active = TRUE AND users.age > 20 \
+             GROUP BY ROLLUP(country, (city)) ORDER BY name;",
+        );
+        assert_eq!(
+            reads,
+            [
+                table("users"),
+                column(Some("users"), "active"),
+                column(Some("users"), "age"),
+                column(Some("users"), "city"),
+                column(Some("users"), "country"),
+                column(Some("users"), "name"),
+            ]
+        );
+        assert!(writes.is_empty());
+    }
+
+    #[test]
+    fn test_update_writes_set_columns() {
+        let (reads, writes) = references_of("UPDATE users SET name = 'bob' WHERE id = 1;");
+        assert_eq!(reads, [column(Some("users"), "id")]);
+        assert_eq!(writes, [table("users"), column(Some("users"), "name")]);
+    }
+
+    #[test]
+    fn test_delete_and_insert_write_table() {
+        let (reads, writes) = references_of("DELETE FROM users WHERE lower(email) = 'a';");
+        assert_eq!(reads, [column(Some("users"), "email")]);
+        assert_eq!(writes, [table("users")]);
+
+        let (reads, writes) = references_of("INSERT INTO users VALUES ('a', 'b');");
+        assert!(reads.is_empty());
+        assert_eq!(writes, [table("users")]);
+    }
+
+    #[test]
+    fn test_upsert_resolves_excluded() {
+        let (reads, writes) = references_of(
+            "INSERT INTO users VALUES ('1', 'a') ON CONFLICT (id) \
+             DO UPDATE SET name = EXCLUDED.name WHERE users.active = TRUE;",
+        );
+        assert_eq!(
+            reads,
+            [
+                column(Some("users"), "active"),
+                column(Some("users"), "id"),
+                column(Some("users"), "name"),
+            ]
+        );
+        assert_eq!(writes, [table("users"), column(Some("users"), "name")]);
+
+        let (reads, writes) = references_of(
+            "INSERT INTO users VALUES ('1', 'a') ON DUPLICATE KEY UPDATE name = VALUES(name);",
+        );
+        assert_eq!(reads, [column(Some("users"), "name")]);
+        assert_eq!(writes, [table("users"), column(Some("users"), "name")]);
+    }
+
+    #[test]
+    fn test_merge_resolves_aliases() {
+        let (reads, writes) = references_of(
+            "MERGE INTO accounts AS a USING payments AS p ON a.id = p.account_id \
+             WHEN MATCHED AND p.amount > 0 THEN UPDATE SET balance = a.balance + p.amount \
+             WHEN NOT MATCHED THEN INSERT (id, balance) VALUES (p.account_id, p.amount);",
+        );
+        assert_eq!(
+            reads,
+            [
+                table("payments"),
+                column(Some("accounts"), "balance"),
+                column(Some("accounts"), "id"),
+                column(Some("payments"), "account_id"),
+                column(Some("payments"), "amount"),
+            ]
+        );
+        assert_eq!(
+            writes,
+            [
+                table("accounts"),
+                column(Some("accounts"), "balance"),
+                column(Some("accounts"), "id"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_merge_subquery_source() {
+        let (reads, writes) = references_of(
+            "MERGE INTO accounts USING (SELECT * FROM payments WHERE amount > 0) AS p \
+             ON accounts.id = p.account_id WHEN MATCHED THEN DELETE;",
+        );
+        assert_eq!(
+            reads,
+            [
+                table("payments"),
+                column(Some("accounts"), "id"),
+                column(Some("payments"), "account_id"),
+                column(Some("payments"), "amount"),
+            ]
+        );
+        assert_eq!(writes, [table("accounts")]);
+    }
+
+    #[test]
+    fn test_schema_qualified_tables() {
+        let (reads, writes) = references_of(
+            "MERGE INTO app.accounts USING staging.payments ON accounts.id = payments.id \
+             WHEN MATCHED THEN UPDATE SET balance = payments.amount;",
+        );
+        assert_eq!(
+            reads,
+            [
+                table("staging.payments"),
+                column(Some("app.accounts"), "id"),
+                column(Some("staging.payments"), "amount"),
+                column(Some("staging.payments"), "id"),
+            ]
+        );
+        assert_eq!(
+            writes,
+            [
+                table("app.accounts"),
+                column(Some("app.accounts"), "balance"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_prepare_and_create_table() {
+        let (reads, writes) =
+            references_of("PREPARE find (INTEGER) AS SELECT * FROM users WHERE id = $1;");
+        assert_eq!(reads, [table("users"), column(Some("users"), "id")]);
+        assert!(writes.is_empty());
+
+        let (reads, writes) =
+            references_of("CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT DEFAULT 'x');");
+        assert!(reads.is_empty());
+        assert_eq!(
+            writes,
+            [
+                table("users"),
+                column(Some("users"), "id"),
+                column(Some("users"), "name"),
+            ]
+        );
+
+        let (reads, writes) = references_of("EXECUTE find (1);");
+        assert!(reads.is_empty() && writes.is_empty());
+    }
+}
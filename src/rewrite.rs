@@ -0,0 +1,476 @@
+use crate::ast::{
+    same_name, BinaryOperator, ColumnDefinition, ConflictTarget, Expression, Ident, MergeAction,
+    ObjectName, OnConflict, OrderByItem, Statement,
+};
+use crate::references::Scope;
+use crate::visitor::{walk_statement_mut, Flow, VisitorMut};
+
+// A transformation of a statement in place. Passes compose through rewrite
+pub trait Rewrite {
+    fn apply(&self, statement: &mut Statement);
+}
+
+// Returns a new statement with the passes applied in order, leaving the original untouched
+pub fn rewrite(statement: &Statement, passes: &[&dyn Rewrite]) -> Statement {
+    let mut statement = statement.clone();
+    for pass in passes {
+        pass.apply(&mut statement);
+    }
+    statement
+}
+
+// Renames a table, along with the qualifiers of its columns. The schema of the table is kept.
+// A name without a schema renames the table in every schema. Names are matched like the
+// catalog matches them, ignoring ASCII case
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RenameTable {
+    pub from: ObjectName,
+    pub to: String,
+}
+
+impl Rewrite for RenameTable {
+    fn apply(&self, statement: &mut Statement) {
+        walk_statement_mut(&mut RenameTableVisitor(self), statement);
+    }
+}
+
+struct RenameTableVisitor<'a>(&'a RenameTable);
+
+impl VisitorMut for RenameTableVisitor<'_> {
+    fn pre_visit_table(&mut self, table: &mut ObjectName) -> Flow {
+        if table.is_named(&self.0.from) {
+            table.name.value = self.0.to.clone();
+        }
+        Flow::Continue
+    }
+
+    fn pre_visit_expression(&mut self, expression: &mut Expression) -> Flow {
+        if let Expression::QualifiedColumn { table, .. } = expression {
+            if same_name(&table.value, &self.0.from.name.value) {
+                table.value = self.0.to.clone();
+            }
+        }
+        Flow::Continue
+    }
+}
+
+// Renames a column of one table wherever the statement refers to it, through an alias or
+// unqualified. The table is named as references reports it, such as users or app.users.
+// Names are matched ignoring ASCII case
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RenameColumn {
+    pub table: String,
+    pub from: String,
+    pub to: String,
+}
+
+impl Rewrite for RenameColumn {
+    fn apply(&self, statement: &mut Statement) {
+        let mut visitor = RenameColumnVisitor {
+            rename: self,
+            scopes: Vec::new(),
+        };
+        walk_statement_mut(&mut visitor, statement);
+    }
+}
+
+struct RenameColumnVisitor<'a> {
+    rename: &'a RenameColumn,
+    scopes: Vec<Scope>,
+}
+
+impl RenameColumnVisitor<'_> {
+    fn rename(&self, column: &mut Ident, table: Option<&String>) {
+        let same_table = table.is_some_and(|table| same_name(table, &self.rename.table));
+        if same_table && same_name(&column.value, &self.rename.from) {
+            column.value = self.rename.to.clone();
+        }
+    }
+
+    fn rename_unqualified(&self, column: &mut Ident) {
+        let table = self.scopes.last().and_then(|scope| scope.default.as_ref());
+        self.rename(column, table);
+    }
+
+    fn rename_target(&self, column: &mut Ident) {
+        let table = self.scopes.last().and_then(|scope| scope.target.as_ref());
+        self.rename(column, table);
+    }
+}
+
+impl VisitorMut for RenameColumnVisitor<'_> {
+    fn pre_visit_statement(&mut self, statement: &mut Statement) -> Flow {
+        self.scopes.push(Scope::of(statement));
+        if let Statement::Update { set, .. } = statement {
+            for (column, _) in set {
+                self.rename_target(column);
+            }
+        }
+        Flow::Continue
+    }
+
+    fn post_visit_statement(&mut self, _statement: &mut Statement) -> Flow {
+        self.scopes.pop();
+        Flow::Continue
+    }
+
+    fn pre_visit_expression(&mut self, expression: &mut Expression) -> Flow {
+        match expression {
+            Expression::Column(column) => self.rename_unqualified(column),
+            Expression::QualifiedColumn { table, column } => {
+                let table = self.scopes.last().map(|scope| scope.resolve(table));
+                self.rename(column, table.as_ref());
+            }
+            _ => {}
+        }
+        Flow::Continue
+    }
+
+    fn pre_visit_order_by_item(&mut self, item: &mut OrderByItem) -> Flow {
+        self.rename_unqualified(&mut item.column);
+        Flow::Continue
+    }
+
+    fn pre_visit_on_conflict(&mut self, on_conflict: &mut OnConflict) -> Flow {
+        match on_conflict {
+            OnConflict::DoUpdate { set, .. } | OnConflict::DuplicateKeyUpdate { set } => {
+                for (column, _) in set {
+                    self.rename_target(column);
+                }
+            }
+            OnConflict::DoNothing { .. } => {}
+        }
+        Flow::Continue
+    }
+
+    fn pre_visit_conflict_target(&mut self, target: &mut ConflictTarget) -> Flow {
+        if let ConflictTarget::Columns(columns) = target {
+            for column in columns {
+                self.rename_target(column);
+            }
+        }
+        Flow::Continue
+    }
+
+    fn pre_visit_merge_action(&mut self, action: &mut MergeAction) -> Flow {
+        match action {
+            MergeAction::Update { set } => {
+                for (column, _) in set {
+                    self.rename_target(column);
+                }
+            }
+            MergeAction::Insert { columns, .. } => {
+                for column in columns {
+                    self.rename_target(column);
+                }
+            }
+            MergeAction::Delete => {}
+        }
+        Flow::Continue
+    }
+
+    fn pre_visit_column_definition(&mut self, column: &mut ColumnDefinition) -> Flow {
+        self.rename_target(&mut column.name);
+        Flow::Continue
+    }
+}
+
+// Qualifies every table that has no schema with the given one
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QualifyTables {
+    pub schema: String,
+}
+
+impl Rewrite for QualifyTables {
+    fn apply(&self, statement: &mut Statement) {
+        walk_statement_mut(&mut QualifyTablesVisitor(self), statement);
+    }
+}
+
+struct QualifyTablesVisitor<'a>(&'a QualifyTables);
+
+impl VisitorMut for QualifyTablesVisitor<'_> {
+    fn pre_visit_table(&mut self, table: &mut ObjectName) -> Flow {
+        if table.schema.is_none() {
+            table.schema = Some(Ident::new(self.0.schema.as_str()));
+        }
+        Flow::Continue
+    }
+}
+
+// ANDs a predicate into the WHERE clause of every SELECT, UPDATE and DELETE, including those
+// nested in MERGE sources and PREPARE, into the ON condition of MERGE and into the WHERE of
+// ON CONFLICT DO UPDATE. A clause without WHERE gets the predicate as its WHERE. MySQL's
+// ON DUPLICATE KEY UPDATE has no condition and is left as it is
+#[derive(Debug, Clone, PartialEq)]
+pub struct InjectPredicate {
+    pub predicate: Expression,
+}
+
+impl InjectPredicate {
+    fn restrict(&self, condition: &mut Expression) {
+        let span = condition.span();
+        let left = std::mem::replace(condition, self.predicate.clone());
+        // The tree keeps the grouping, and Display parenthesizes an OR on either side
+        *condition = Expression::Binary {
+            left: Box::new(left),
+            operator: BinaryOperator::And,
+            right: Box::new(self.predicate.clone()),
+            span,
+        };
+    }
+
+    fn restrict_where(&self, where_clause: &mut Option<Expression>) {
+        match where_clause {
+            Some(condition) => self.restrict(condition),
+            None => *where_clause = Some(self.predicate.clone()),
+        }
+    }
+}
+
+impl Rewrite for InjectPredicate {
+    fn apply(&self, statement: &mut Statement) {
+        walk_statement_mut(&mut InjectPredicateVisitor(self), statement);
+    }
+}
+
+struct InjectPredicateVisitor<'a>(&'a InjectPredicate);
+
+impl VisitorMut for InjectPredicateVisitor<'_> {
+    fn pre_visit_statement(&mut self, statement: &mut Statement) -> Flow {
+        match statement {
+            Statement::Select { where_clause, .. }
+            | Statement::Update { where_clause, .. }
+            | Statement::Delete { where_clause, .. } => self.0.restrict_where(where_clause),
+            Statement::Merge { on, .. } => self.0.restrict(on),
+            _ => {}
+        }
+        Flow::Continue
+    }
+
+    fn pre_visit_on_conflict(&mut self, on_conflict: &mut OnConflict) -> Flow {
+        if let OnConflict::DoUpdate { where_clause, .. } = on_conflict {
+            self.0.restrict_where(where_clause);
+        }
+        Flow::Continue
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{parse_expression, parse_statement};
+
+    fn rewritten(sql: &str, passes: &[&dyn Rewrite]) -> String {
+        let statement = parse_statement(sql).unwrap();
+        let rewritten = rewrite(&statement, passes);
+        // The original is left as it was
+        assert_eq!(statement, parse_statement(sql).unwrap());
+        let text = rewritten.to_string();
+        assert_eq!(parse_statement(&text).unwrap(), rewritten, "{}", text);
+        text
+    }
+
+    fn tenant() -> InjectPredicate {
+        InjectPredicate {
+            predicate: parse_expression("tenant_id = $1").unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_rename_table() {
+        let rename = RenameTable {
+            from: "users".into(),
+            to: "accounts".to_string(),
+        };
+        assert_eq!(
+            rewritten(
+                "SELECT * FROM app.users WHERE users.id = 1 AND other.id = 2;",
+                &[&rename]
+            ),
+            "SELECT * FROM app.accounts WHERE accounts.id = 1 AND other.id = 2;"
+        );
+        assert_eq!(
+            rewritten(
+                "MERGE INTO orders USING users AS u ON orders.user_id = u.id WHEN MATCHED THEN DELETE;",
+                &[&rename]
+            ),
+            "MERGE INTO orders USING accounts AS u ON orders.user_id = u.id WHEN MATCHED THEN DELETE;"
+        );
+        // The catalog's rule: ASCII case is ignored
+        assert_eq!(
+            rewritten("DELETE FROM Users WHERE USERS.id = 1;", &[&rename]),
+            "DELETE FROM accounts WHERE accounts.id = 1;"
+        );
+        let qualified = RenameTable {
+            from: ObjectName::qualified("App", "users"),
+            to: "accounts".to_string(),
+        };
+        assert_eq!(
+            rewritten(
+                "MERGE INTO app.users USING staging.users AS s ON users.id = s.id WHEN MATCHED THEN DELETE;",
+                &[&qualified]
+            ),
+            "MERGE INTO app.accounts USING staging.users AS s ON accounts.id = s.id \
+             WHEN MATCHED THEN DELETE;"
+        );
+    }
+
+    #[test]
+    fn test_rename_column_follows_scopes() {
+        let rename = RenameColumn {
+            table: "users".to_string(),
+            from: "name".to_string(),
+            to: "full_name".to_string(),
+        };
+        assert_eq!(
+            rewritten(
+                "UPDATE users SET name = 'a' WHERE name = 'b' AND lower(name) = 'c';",
+                &[&rename]
+            ),
+            "UPDATE users SET full_name = 'a' WHERE full_name = 'b' AND lower(full_name) = 'c';"
+        );
+        assert_eq!(
+            rewritten(
+                "SELECT * FROM orders WHERE name = 'x' ORDER BY name;",
+                &[&rename]
+            ),
+            "SELECT * FROM orders WHERE name = 'x' ORDER BY name;"
+        );
+        assert_eq!(
+            rewritten("DELETE FROM USERS WHERE Name = 'x';", &[&rename]),
+            "DELETE FROM USERS WHERE full_name = 'x';"
+        );
+        assert_eq!(
+            rewritten(
+                "MERGE INTO users AS t USING (SELECT * FROM staging) AS s ON t.name = s.name \
+                 WHEN MATCHED THEN UPDATE SET name = s.name \
+                 WHEN NOT MATCHED THEN INSERT (name) VALUES (s.name);",
+                &[&rename]
+            ),
+            "MERGE INTO users AS t USING (SELECT * FROM staging) AS s ON t.full_name = s.name \
+             WHEN MATCHED THEN UPDATE SET full_name = s.name \
+             WHEN NOT MATCHED THEN INSERT (full_name) VALUES (s.name);"
+        );
+        assert_eq!(
+            rewritten(
+                "INSERT INTO users VALUES ('a') ON CONFLICT (name) DO UPDATE SET name = EXCLUDED.name;",
+                &[&rename]
+            ),
+            "INSERT INTO users VALUES ('a') ON CONFLICT (full_name) DO UPDATE SET full_name = EXCLUDED.full_name;"
+        );
+    }
+
+    #[test]
+    fn test_qualify_tables() {
+        let qualify = QualifyTables {
+            schema: "app".to_string(),
+        };
+        assert_eq!(
+            rewritten(
+                "MERGE INTO t USING other.s ON t.id = s.id WHEN MATCHED THEN DELETE;",
+                &[&qualify]
+            ),
+            "MERGE INTO app.t USING other.s ON t.id = s.id WHEN MATCHED THEN DELETE;"
+        );
+        assert_eq!(
+            rewritten("CREATE TABLE \"order\" (id INTEGER);", &[&qualify]),
+            "CREATE TABLE app.\"order\" (id INTEGER);"
+        );
+    }
+
+    #[test]
+    fn test_inject_predicate_keeps_precedence() {
+        assert_eq!(
+            rewritten("SELECT * FROM t WHERE a = 1 OR b = 2;", &[&tenant()]),
+            "SELECT * FROM t WHERE (a = 1 OR b = 2) AND tenant_id = $1;"
+        );
+        assert_eq!(
+            rewritten("DELETE FROM t;", &[&tenant()]),
+            "DELETE FROM t WHERE tenant_id = $1;"
+        );
+        let either = InjectPredicate {
+            predicate: parse_expression("owner = $1 OR shared").unwrap(),
+        };
+        assert_eq!(
+            rewritten("UPDATE t SET a = 'b' WHERE a = 'c';", &[&either]),
+            "UPDATE t SET a = 'b' WHERE a = 'c' AND (owner = $1 OR shared);"
+        );
+    }
+
+    #[test]
+    fn test_inject_predicate_reaches_nested_statements() {
+        assert_eq!(
+            rewritten(
+                "MERGE INTO t USING (SELECT * FROM s WHERE a = 1) AS s ON t.id = s.id \
+                 WHEN MATCHED THEN DELETE;",
+                &[&tenant()]
+            ),
+            "MERGE INTO t USING (SELECT * FROM s WHERE a = 1 AND tenant_id = $1) AS s \
+             ON t.id = s.id AND tenant_id = $1 WHEN MATCHED THEN DELETE;"
+        );
+        assert_eq!(
+            rewritten("PREPARE q AS SELECT * FROM t;", &[&tenant()]),
+            "PREPARE q AS SELECT * FROM t WHERE tenant_id = $1;"
+        );
+        // Statements without a WHERE clause are left alone
+        assert_eq!(
+            rewritten("INSERT INTO t VALUES ('a');", &[&tenant()]),
+            "INSERT INTO t VALUES ('a');"
+        );
+    }
+
+    #[test]
+    fn test_inject_predicate_restricts_merge_and_upsert_targets() {
+        assert_eq!(
+            rewritten(
+                "MERGE INTO t USING s ON t.id = s.id OR t.code = s.code WHEN MATCHED THEN DELETE;",
+                &[&tenant()]
+            ),
+            "MERGE INTO t USING s ON (t.id = s.id OR t.code = s.code) AND tenant_id = $1 \
+             WHEN MATCHED THEN DELETE;"
+        );
+        assert_eq!(
+            rewritten(
+                "INSERT INTO t VALUES ('a') ON CONFLICT (id) DO UPDATE SET a = EXCLUDED.a;",
+                &[&tenant()]
+            ),
+            "INSERT INTO t VALUES ('a') ON CONFLICT (id) DO UPDATE SET a = EXCLUDED.a \
+             WHERE tenant_id = $1;"
+        );
+        assert_eq!(
+            rewritten(
+                "INSERT INTO t VALUES ('a') ON CONFLICT (id) DO UPDATE SET a = 'b' WHERE t.a <> 'b';",
+                &[&tenant()]
+            ),
+            "INSERT INTO t VALUES ('a') ON CONFLICT (id) DO UPDATE SET a = 'b' \
+             WHERE t.a <> 'b' AND tenant_id = $1;"
+        );
+        // DO NOTHING changes no row
+        assert_eq!(
+            rewritten(
+                "INSERT INTO t VALUES ('a') ON CONFLICT DO NOTHING;",
+                &[&tenant()]
+            ),
+            "INSERT INTO t VALUES ('a') ON CONFLICT DO NOTHING;"
+        );
+    }
+
+    #[test]
+    fn test_passes_compose_in_order() {
+        let rename = RenameTable {
+            from: "users".into(),
+            to: "members".to_string(),
+        };
+        let qualify = QualifyTables {
+            schema: "tenant".to_string(),
+        };
+        assert_eq!(
+            rewritten(
+                "SELECT * FROM users WHERE users.active = TRUE;",
+                &[&rename, &qualify, &tenant()]
+            ),
+            "SELECT * FROM tenant.members WHERE members.active = TRUE AND tenant_id = $1;"
+        );
+    }
+}
@@ -0,0 +1,630 @@
+use crate::ast::{BinaryOperator, Expression, Literal, Span, Statement, UnaryOperator};
+use crate::dialect::Dialect;
+use crate::visitor::{walk_statement_mut, Flow, VisitorMut};
+use std::cmp::Ordering;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SimplifyError {
+    // Division by a literal zero, which fails whenever the expression is evaluated
+    DivisionByZero { span: Span },
+    // Literal arithmetic whose result does not fit its type
+    Overflow { span: Span },
+}
+
+impl SimplifyError {
+    pub fn span(&self) -> Span {
+        match self {
+            SimplifyError::DivisionByZero { span } | SimplifyError::Overflow { span } => *span,
+        }
+    }
+}
+
+impl fmt::Display for SimplifyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let message = match self {
+            SimplifyError::DivisionByZero { .. } => "division by zero",
+            SimplifyError::Overflow { .. } => "numeric overflow",
+        };
+        let span = self.span();
+        write!(
+            f,
+            "{} at line {}, column {}",
+            message, span.line, span.column
+        )
+    }
+}
+
+impl std::error::Error for SimplifyError {}
+
+// Folds literal arithmetic and comparisons and removes redundant boolean terms, following the
+// three-valued logic of SQL: NULL AND FALSE is FALSE, NULL OR TRUE is TRUE, and every other
+// operator yields NULL when an operand is NULL. Integer division follows the dialect: it
+// truncates except in MySQL, where it yields a decimal and is folded only when exact
+pub fn simplify(expression: &Expression, dialect: Dialect) -> Result<Expression, SimplifyError> {
+    let simplify = |expression: &Expression| simplify(expression, dialect);
+    Ok(match expression {
+        Expression::Binary {
+            left,
+            operator,
+            right,
+            span,
+        } => simplify_binary(simplify(left)?, operator, simplify(right)?, *span, dialect)?,
+        Expression::Unary {
+            operator,
+            operand,
+            span,
+        } => simplify_unary(operator, simplify(operand)?, *span)?,
+        Expression::Function { name, args, span } => Expression::Function {
+            name: name.clone(),
+            args: args.iter().map(simplify).collect::<Result<_, _>>()?,
+            span: *span,
+        },
+        Expression::Grouping { args, span } => Expression::Grouping {
+            args: args.iter().map(simplify).collect::<Result<_, _>>()?,
+            span: *span,
+        },
+        Expression::Cast {
+            expression,
+            data_type,
+            span,
+        } => Expression::Cast {
+            expression: Box::new(simplify(expression)?),
+            data_type: data_type.clone(),
+            span: *span,
+        },
+        Expression::InList {
+            expression,
+            list,
+            negated,
+            span,
+        } => simplify_in_list(
+            simplify(expression)?,
+            list.iter().map(simplify).collect::<Result<_, _>>()?,
+            *negated,
+            *span,
+            dialect,
+        )?,
+        Expression::Literal { .. }
+        | Expression::Column(_)
+        | Expression::QualifiedColumn { .. }
+        | Expression::Parameter { .. } => expression.clone(),
+    })
+}
+
+// Simplifies every expression of a statement. A WHERE clause that is always true is removed
+pub fn simplify_statement(
+    statement: &Statement,
+    dialect: Dialect,
+) -> Result<Statement, SimplifyError> {
+    let mut statement = statement.clone();
+    let mut simplifier = Simplifier {
+        dialect,
+        error: None,
+    };
+    walk_statement_mut(&mut simplifier, &mut statement);
+    match simplifier.error {
+        Some(error) => Err(error),
+        None => Ok(statement),
+    }
+}
+
+struct Simplifier {
+    dialect: Dialect,
+    error: Option<SimplifyError>,
+}
+
+impl VisitorMut for Simplifier {
+    fn pre_visit_expression(&mut self, expression: &mut Expression) -> Flow {
+        match simplify(expression, self.dialect) {
+            Ok(simplified) => {
+                *expression = simplified;
+                // simplify has already handled the children
+                Flow::SkipChildren
+            }
+            Err(error) => {
+                self.error = Some(error);
+                Flow::Stop
+            }
+        }
+    }
+
+    fn post_visit_statement(&mut self, statement: &mut Statement) -> Flow {
+        if let Statement::Select { where_clause, .. }
+        | Statement::Update { where_clause, .. }
+        | Statement::Delete { where_clause, .. } = statement
+        {
+            if where_clause.as_ref().and_then(truth) == Some(Truth::True) {
+                *where_clause = None;
+            }
+        }
+        Flow::Continue
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Truth {
+    True,
+    False,
+    Unknown,
+}
+
+// The truth value of a boolean or NULL literal
+fn truth(expression: &Expression) -> Option<Truth> {
+    match expression {
+        Expression::Literal {
+            value: Literal::Boolean(true),
+            ..
+        } => Some(Truth::True),
+        Expression::Literal {
+            value: Literal::Boolean(false),
+            ..
+        } => Some(Truth::False),
+        Expression::Literal {
+            value: Literal::Null,
+            ..
+        } => Some(Truth::Unknown),
+        _ => None,
+    }
+}
+
+fn literal(value: Literal, span: Span) -> Expression {
+    Expression::Literal { value, span }
+}
+
+fn is_zero(expression: &Expression) -> bool {
+    match expression {
+        Expression::Literal {
+            value: Literal::Number(value),
+            ..
+        } => *value == 0,
+        Expression::Literal {
+            value: Literal::Float(value),
+            ..
+        } => *value == 0.0,
+        _ => false,
+    }
+}
+
+fn simplify_binary(
+    left: Expression,
+    operator: &BinaryOperator,
+    right: Expression,
+    span: Span,
+    dialect: Dialect,
+) -> Result<Expression, SimplifyError> {
+    let (left_truth, right_truth) = (truth(&left), truth(&right));
+    match operator {
+        BinaryOperator::And => {
+            return Ok(match (left_truth, right_truth) {
+                (Some(Truth::False), _) | (_, Some(Truth::False)) => {
+                    literal(Literal::Boolean(false), span)
+                }
+                (Some(Truth::True), _) => right,
+                (_, Some(Truth::True)) => left,
+                (Some(Truth::Unknown), Some(Truth::Unknown)) => literal(Literal::Null, span),
+                _ => binary(left, operator, right, span),
+            });
+        }
+        BinaryOperator::Or => {
+            return Ok(match (left_truth, right_truth) {
+                (Some(Truth::True), _) | (_, Some(Truth::True)) => {
+                    literal(Literal::Boolean(true), span)
+                }
+                (Some(Truth::False), _) => right,
+                (_, Some(Truth::False)) => left,
+                (Some(Truth::Unknown), Some(Truth::Unknown)) => literal(Literal::Null, span),
+                _ => binary(left, operator, right, span),
+            });
+        }
+        _ => {}
+    }
+
+    if left_truth == Some(Truth::Unknown) || right_truth == Some(Truth::Unknown) {
+        return Ok(literal(Literal::Null, span));
+    }
+    if *operator == BinaryOperator::Divide && is_zero(&right) {
+        return Err(SimplifyError::DivisionByZero { span });
+    }
+    if let (Expression::Literal { value: a, .. }, Expression::Literal { value: b, .. }) =
+        (&left, &right)
+    {
+        if let Some(value) = fold(a, operator, b, span, dialect)? {
+            return Ok(literal(value, span));
+        }
+    }
+    Ok(binary(left, operator, right, span))
+}
+
+fn binary(
+    left: Expression,
+    operator: &BinaryOperator,
+    right: Expression,
+    span: Span,
+) -> Expression {
+    Expression::Binary {
+        left: Box::new(left),
+        operator: operator.clone(),
+        right: Box::new(right),
+        span,
+    }
+}
+
+fn as_float(value: &Literal) -> Option<f64> {
+    match value {
+        Literal::Number(value) => Some(*value as f64),
+        Literal::Float(value) => Some(*value),
+        _ => None,
+    }
+}
+
+// The value of an operator applied to two non-NULL literals, or None if it is not folded.
+// LIKE is left alone, as are all comparisons of strings: whether 'a' = 'A' or 'a' < 'b'
+// depends on the collation
+fn fold(
+    a: &Literal,
+    operator: &BinaryOperator,
+    b: &Literal,
+    span: Span,
+    dialect: Dialect,
+) -> Result<Option<Literal>, SimplifyError> {
+    let overflow = SimplifyError::Overflow { span };
+    let ordering = match (a, b) {
+        // MySQL divides integers into a decimal, such as 3.5000 for 7 / 2, which a float
+        // literal only matches when the division is exact and the quotient fits a float's mantissa
+        (Literal::Number(a), Literal::Number(b))
+            if dialect == Dialect::MySql && *operator == BinaryOperator::Divide =>
+        {
+            return Ok(match a.checked_rem(*b) {
+                Some(0) => Some(a / b)
+                    .filter(|quotient| quotient.unsigned_abs() <= 1 << f64::MANTISSA_DIGITS)
+                    .map(|quotient| Literal::Float(quotient as f64)),
+                _ => None,
+            });
+        }
+        (Literal::Number(a), Literal::Number(b)) => {
+            let value = match operator {
+                BinaryOperator::Add => a.checked_add(*b),
+                BinaryOperator::Subtract => a.checked_sub(*b),
+                BinaryOperator::Multiply => a.checked_mul(*b),
+                BinaryOperator::Divide => a.checked_div(*b),
+                _ => return Ok(compare(operator, a.cmp(b))),
+            };
+            return value
+                .map(|value| Some(Literal::Number(value)))
+                .ok_or(overflow);
+        }
+        (Literal::Number(_) | Literal::Float(_), Literal::Number(_) | Literal::Float(_)) => {
+            let (a, b) = (
+                as_float(a).unwrap_or_default(),
+                as_float(b).unwrap_or_default(),
+            );
+            let value = match operator {
+                BinaryOperator::Add => a + b,
+                BinaryOperator::Subtract => a - b,
+                BinaryOperator::Multiply => a * b,
+                BinaryOperator::Divide => a / b,
+                _ => {
+                    return Ok(a
+                        .partial_cmp(&b)
+                        .and_then(|ordering| compare(operator, ordering)))
+                }
+            };
+            return if value.is_finite() {
+                Ok(Some(Literal::Float(value)))
+            } else {
+                Err(overflow)
+            };
+        }
+        (Literal::Boolean(a), Literal::Boolean(b)) => a.cmp(b),
+        _ => return Ok(None),
+    };
+    Ok(match operator {
+        BinaryOperator::Equal | BinaryOperator::NotEqual => compare(operator, ordering),
+        _ => None,
+    })
+}
+
+fn compare(operator: &BinaryOperator, ordering: Ordering) -> Option<Literal> {
+    let value = match operator {
+        BinaryOperator::Equal => ordering == Ordering::Equal,
+        BinaryOperator::NotEqual => ordering != Ordering::Equal,
+        BinaryOperator::LessThan => ordering == Ordering::Less,
+        BinaryOperator::LessThanOrEqual => ordering != Ordering::Greater,
+        BinaryOperator::GreaterThan => ordering == Ordering::Greater,
+        BinaryOperator::GreaterThanOrEqual => ordering != Ordering::Less,
+        _ => return None,
+    };
+    Some(Literal::Boolean(value))
+}
+
+// An IN list of literals is folded like the OR of its equalities, so NULL IN (1, 2) is NULL
+// and 1 NOT IN (2, NULL) is NULL
+fn simplify_in_list(
+    expression: Expression,
+    list: Vec<Expression>,
+    negated: bool,
+    span: Span,
+    dialect: Dialect,
+) -> Result<Expression, SimplifyError> {
+    let is_literal = |expression: &Expression| matches!(expression, Expression::Literal { .. });
+    let unfolded = |expression, list| {
+        Ok(Expression::InList {
+            expression: Box::new(expression),
+            list,
+            negated,
+            span,
+        })
+    };
+    if !is_literal(&expression) || !list.iter().all(is_literal) {
+        return unfolded(expression, list);
+    }
+    let mut folded = literal(Literal::Boolean(false), span);
+    for item in &list {
+        let equal = simplify_binary(
+            expression.clone(),
+            &BinaryOperator::Equal,
+            item.clone(),
+            span,
+            dialect,
+        )?;
+        folded = simplify_binary(folded, &BinaryOperator::Or, equal, span, dialect)?;
+    }
+    // Equalities that are not folded, such as those of strings, leave the list as it is
+    if truth(&folded).is_none() {
+        return unfolded(expression, list);
+    }
+    if negated {
+        folded = simplify_unary(&UnaryOperator::Not, folded, span)?;
+    }
+    Ok(folded)
+}
+
+fn simplify_unary(
+    operator: &UnaryOperator,
+    operand: Expression,
+    span: Span,
+) -> Result<Expression, SimplifyError> {
+    Ok(match (operator, operand) {
+        (
+            _,
+            Expression::Literal {
+                value: Literal::Null,
+                ..
+            },
+        ) => literal(Literal::Null, span),
+        (
+            UnaryOperator::Not,
+            Expression::Literal {
+                value: Literal::Boolean(value),
+                ..
+            },
+        ) => literal(Literal::Boolean(!value), span),
+        (
+            UnaryOperator::Minus,
+            Expression::Literal {
+                value: Literal::Number(value),
+                ..
+            },
+        ) => {
+            let value = value
+                .checked_neg()
+                .ok_or(SimplifyError::Overflow { span })?;
+            literal(Literal::Number(value), span)
+        }
+        (
+            UnaryOperator::Minus,
+            Expression::Literal {
+                value: Literal::Float(value),
+                ..
+            },
+        ) => literal(Literal::Float(-value), span),
+        // NOT NOT x and -(-x) are x, including when x is NULL
+        (
+            UnaryOperator::Not,
+            Expression::Unary {
+                operator: UnaryOperator::Not,
+                operand,
+                ..
+            },
+        )
+        | (
+            UnaryOperator::Minus,
+            Expression::Unary {
+                operator: UnaryOperator::Minus,
+                operand,
+                ..
+            },
+        ) => *operand,
+        (operator, operand) => Expression::Unary {
+            operator: operator.clone(),
+            operand: Box::new(operand),
+            span,
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{parse_expression, parse_statement};
+
+    fn simplified(input: &str) -> String {
+        simplified_in(input, Dialect::Generic)
+    }
+
+    fn simplified_in(input: &str, dialect: Dialect) -> String {
+        simplify(&parse_expression(input).unwrap(), dialect)
+            .unwrap()
+            .to_string()
+    }
+
+    fn error(input: &str) -> SimplifyError {
+        simplify(&parse_expression(input).unwrap(), Dialect::Generic).unwrap_err()
+    }
+
+    #[test]
+    fn test_fold_literal_arithmetic() {
+        assert_eq!(simplified("1 + 2 * 3"), "7");
+        assert_eq!(simplified("7 / 2"), "3");
+        assert_eq!(simplified("-7 / 2"), "-3");
+        assert_eq!(simplified("1 - 3"), "-2");
+        assert_eq!(simplified("1 + 0.5"), "1.5");
+        assert_eq!(simplified("3.0 * 2"), "6.0");
+        assert_eq!(simplified("a + (1 + 2)"), "a + 3");
+        // Only literal operands are folded; a + 1 + 2 is (a + 1) + 2
+        assert_eq!(simplified("a + 1 + 2"), "a + 1 + 2");
+        assert_eq!(
+            simplified("lower(a) = upper('x') AND 1 + 1 = 2"),
+            "lower(a) = upper('x')"
+        );
+    }
+
+    #[test]
+    fn test_fold_division_per_dialect() {
+        assert_eq!(simplified_in("7 / 2", Dialect::PostgreSql), "3");
+        assert_eq!(simplified_in("7 / 2", Dialect::Sqlite), "3");
+        // MySQL divides integers into a decimal, so only exact divisions are folded
+        assert_eq!(simplified_in("7 / 2", Dialect::MySql), "7 / 2");
+        assert_eq!(simplified_in("1 / 3", Dialect::MySql), "1 / 3");
+        assert_eq!(simplified_in("6 / 2", Dialect::MySql), "3.0");
+        assert_eq!(simplified_in("-6 / 2 + 1", Dialect::MySql), "-2.0");
+        assert_eq!(simplified_in("7.0 / 2", Dialect::MySql), "3.5");
+        assert_eq!(simplified_in("a / 2 = 1 + 1", Dialect::MySql), "a / 2 = 2");
+    }
+
+    #[test]
+    fn test_fold_comparisons() {
+        assert_eq!(simplified("1 = 1"), "TRUE");
+        assert_eq!(simplified("2 < 1.5"), "FALSE");
+        assert_eq!(simplified("TRUE = FALSE"), "FALSE");
+        // Comparisons of strings depend on the collation, and LIKE is left to the database
+        assert_eq!(simplified("'a' = 'A'"), "'a' = 'A'");
+        assert_eq!(simplified("'a' <> 'b'"), "'a' <> 'b'");
+        assert_eq!(simplified("'a' < 'b'"), "'a' < 'b'");
+        assert_eq!(simplified("'abc' LIKE 'a%'"), "'abc' LIKE 'a%'");
+    }
+
+    #[test]
+    fn test_boolean_simplification() {
+        assert_eq!(simplified("NOT NOT a"), "a");
+        assert_eq!(simplified("- -a"), "a");
+        assert_eq!(simplified("-(-(-a))"), "-a");
+        assert_eq!(simplified("a AND TRUE"), "a");
+        assert_eq!(simplified("TRUE AND a"), "a");
+        assert_eq!(simplified("a OR FALSE"), "a");
+        assert_eq!(simplified("a AND FALSE"), "FALSE");
+        assert_eq!(simplified("a OR TRUE"), "TRUE");
+        assert_eq!(simplified("NOT (1 = 2)"), "TRUE");
+        // The remaining terms keep their grouping
+        assert_eq!(
+            simplified("(a OR b) AND TRUE AND (c OR 1 = 0)"),
+            "(a OR b) AND c"
+        );
+    }
+
+    #[test]
+    fn test_null_propagation() {
+        assert_eq!(simplified("NULL + 1"), "NULL");
+        assert_eq!(simplified("a = NULL"), "NULL");
+        assert_eq!(simplified("NOT NULL"), "NULL");
+        assert_eq!(simplified("-NULL"), "NULL");
+        assert_eq!(simplified("NULL / 0"), "NULL");
+        // Three-valued logic
+        assert_eq!(simplified("NULL AND FALSE"), "FALSE");
+        assert_eq!(simplified("NULL OR TRUE"), "TRUE");
+        assert_eq!(simplified("NULL AND TRUE"), "NULL");
+        assert_eq!(simplified("NULL OR FALSE"), "NULL");
+        assert_eq!(simplified("NULL AND NULL"), "NULL");
+        assert_eq!(simplified("a AND NULL"), "a AND NULL");
+    }
+
+    #[test]
+    fn test_fold_in_lists() {
+        assert_eq!(simplified("1 IN (2, 1)"), "TRUE");
+        assert_eq!(simplified("1 NOT IN (2, 3)"), "TRUE");
+        assert_eq!(simplified("'a' IN ('A')"), "'a' IN ('A')");
+        assert_eq!(simplified("'a' IN ('b', NULL)"), "'a' IN ('b', NULL)");
+        // Like the OR of the equalities, a NULL item makes a miss unknown
+        assert_eq!(simplified("1 IN (2, NULL)"), "NULL");
+        assert_eq!(simplified("1 IN (1, NULL)"), "TRUE");
+        assert_eq!(simplified("1 NOT IN (2, NULL)"), "NULL");
+        assert_eq!(simplified("a IN (1 + 1, b)"), "a IN (2, b)");
+        assert_eq!(simplified("a IN (1, 2) AND TRUE"), "a IN (1, 2)");
+    }
+
+    #[test]
+    fn test_errors_carry_the_location() {
+        let division = error("a + b / (1 - 1)");
+        assert!(matches!(division, SimplifyError::DivisionByZero { .. }));
+        assert_eq!(division.span().column, 5);
+        assert_eq!(division.to_string(), "division by zero at line 1, column 5");
+        assert!(matches!(
+            error("x / 0.0"),
+            SimplifyError::DivisionByZero { .. }
+        ));
+        assert!(matches!(
+            error("9223372036854775807 + 1"),
+            SimplifyError::Overflow { .. }
+        ));
+        let huge = Expression::Binary {
+            left: Box::new(literal(Literal::Float(f64::MAX), Span::default())),
+            operator: BinaryOperator::Multiply,
+            right: Box::new(literal(Literal::Number(2), Span::default())),
+            span: Span::default(),
+        };
+        assert!(matches!(
+            simplify(&huge, Dialect::Generic),
+            Err(SimplifyError::Overflow { .. })
+        ));
+    }
+
+    #[test]
+    fn test_simplified_expressions_parse_back() {
+        for input in [
+            "1 - 3 - a",
+            "a - (0 - 1)",
+            "-(1 + 1) * a",
+            "NOT (TRUE AND a)",
+        ] {
+            let simplified = simplify(&parse_expression(input).unwrap(), Dialect::Generic).unwrap();
+            let reparsed = parse_expression(&simplified.to_string()).unwrap();
+            assert_eq!(
+                simplify(&reparsed, Dialect::Generic).unwrap(),
+                simplified,
+                "{}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn test_simplify_statement() {
+        let statement = parse_statement(
+            "SELECT * FROM t WHERE TRUE AND (status = 'open' OR FALSE) AND NOT NOT active;",
+        )
+        .unwrap();
+        assert_eq!(
+            simplify_statement(&statement, Dialect::Generic)
+                .unwrap()
+                .to_string(),
+            "SELECT * FROM t WHERE status = 'open' AND active;"
+        );
+
+        let statement = parse_statement("DELETE FROM t WHERE 1 = 1 OR a;").unwrap();
+        assert_eq!(
+            simplify_statement(&statement, Dialect::Generic)
+                .unwrap()
+                .to_string(),
+            "DELETE FROM t;"
+        );
+
+        let statement =
+            parse_statement("MERGE INTO t USING s ON t.id = s.id / 0 WHEN MATCHED THEN DELETE;")
+                .unwrap();
+        assert!(matches!(
+            simplify_statement(&statement, Dialect::Generic),
+            Err(SimplifyError::DivisionByZero { .. })
+        ));
+    }
+}
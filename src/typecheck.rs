@@ -0,0 +1,557 @@
+use crate::ast::{BinaryOperator, DataType, Expression, Literal, Span, Statement, UnaryOperator};
+use crate::binder::Binding;
+use crate::dialect::Dialect;
+use crate::visitor::{walk_statement, Flow, Visitor};
+use std::fmt;
+
+// The families of types that the checker tells apart. NUMERIC is grouped with the floating
+// point types, since both accept integers. Unknown never causes an error
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SqlType {
+    Null,
+    Boolean,
+    Integer,
+    Float,
+    String,
+    Date,
+    Time,
+    Timestamp,
+    Unknown,
+}
+
+impl SqlType {
+    pub fn of(data_type: &DataType) -> SqlType {
+        match data_type {
+            DataType::SmallInt
+            | DataType::Integer
+            | DataType::BigInt
+            | DataType::SmallSerial
+            | DataType::Serial
+            | DataType::BigSerial => SqlType::Integer,
+            DataType::Real
+            | DataType::DoublePrecision
+            | DataType::Float(_)
+            | DataType::Decimal { .. } => SqlType::Float,
+            DataType::Char(_) | DataType::Varchar(_) | DataType::Text => SqlType::String,
+            DataType::Boolean => SqlType::Boolean,
+            DataType::Date => SqlType::Date,
+            DataType::Time => SqlType::Time,
+            DataType::Timestamp => SqlType::Timestamp,
+            DataType::Custom(_) => SqlType::Unknown,
+        }
+    }
+
+    fn is_numeric(self) -> bool {
+        matches!(self, SqlType::Integer | SqlType::Float)
+    }
+
+    fn is_temporal(self) -> bool {
+        matches!(self, SqlType::Date | SqlType::Time | SqlType::Timestamp)
+    }
+
+    // NULL and unknown operands are accepted by every operator
+    fn is_open(self) -> bool {
+        matches!(self, SqlType::Null | SqlType::Unknown)
+    }
+}
+
+impl fmt::Display for SqlType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            SqlType::Null => "NULL",
+            SqlType::Boolean => "BOOLEAN",
+            SqlType::Integer => "INTEGER",
+            SqlType::Float => "FLOAT",
+            SqlType::String => "TEXT",
+            SqlType::Date => "DATE",
+            SqlType::Time => "TIME",
+            SqlType::Timestamp => "TIMESTAMP",
+            SqlType::Unknown => "unknown",
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TypeError {
+    Binary {
+        operator: BinaryOperator,
+        left: SqlType,
+        right: SqlType,
+        span: Span,
+    },
+    Unary {
+        operator: UnaryOperator,
+        operand: SqlType,
+        span: Span,
+    },
+}
+
+impl TypeError {
+    pub fn span(&self) -> Span {
+        match self {
+            TypeError::Binary { span, .. } | TypeError::Unary { span, .. } => *span,
+        }
+    }
+}
+
+impl fmt::Display for TypeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TypeError::Binary {
+                operator,
+                left,
+                right,
+                ..
+            } => write!(f, "cannot apply {} to {} and {}", operator, left, right)?,
+            TypeError::Unary {
+                operator, operand, ..
+            } => write!(f, "cannot apply {} to {}", operator, operand)?,
+        }
+        let span = self.span();
+        write!(f, " at line {}, column {}", span.line, span.column)
+    }
+}
+
+impl std::error::Error for TypeError {}
+
+// Infers the type of an expression whose columns, parameters and functions are of unknown type.
+// Every type error is reported, and the expression that failed counts as unknown afterwards
+pub fn check_expression(
+    expression: &Expression,
+    dialect: Dialect,
+) -> Result<SqlType, Vec<TypeError>> {
+    let mut checker = Checker {
+        dialect,
+        binding: None,
+        errors: Vec::new(),
+    };
+    let sql_type = checker.infer(expression);
+    if checker.errors.is_empty() {
+        Ok(sql_type)
+    } else {
+        Err(checker.errors)
+    }
+}
+
+// Checks every expression of a statement. With a binding from `binder::bind`, columns,
+// parameters and functions take the types that the binder inferred
+pub fn check_statement(
+    statement: &Statement,
+    binding: Option<&Binding<'_>>,
+    dialect: Dialect,
+) -> Vec<TypeError> {
+    let mut checker = Checker {
+        dialect,
+        binding,
+        errors: Vec::new(),
+    };
+    walk_statement(&mut checker, statement);
+    checker.errors
+}
+
+struct Checker<'b, 'a> {
+    dialect: Dialect,
+    binding: Option<&'b Binding<'a>>,
+    errors: Vec<TypeError>,
+}
+
+impl Visitor for Checker<'_, '_> {
+    fn pre_visit_expression(&mut self, expression: &Expression) -> Flow {
+        self.infer(expression);
+        Flow::SkipChildren
+    }
+}
+
+impl Checker<'_, '_> {
+    // MySQL and SQLite convert between strings, numbers and booleans implicitly
+    fn lenient(&self) -> bool {
+        matches!(self.dialect, Dialect::MySql | Dialect::Sqlite)
+    }
+
+    fn bound(&self, expression: &Expression) -> SqlType {
+        self.binding
+            .and_then(|binding| binding.type_of(expression))
+            .map(SqlType::of)
+            .unwrap_or(SqlType::Unknown)
+    }
+
+    fn infer(&mut self, expression: &Expression) -> SqlType {
+        match expression {
+            Expression::Literal { value, .. } => match value {
+                Literal::String(_) => SqlType::String,
+                Literal::Number(_) => SqlType::Integer,
+                Literal::Float(_) => SqlType::Float,
+                Literal::Boolean(_) => SqlType::Boolean,
+                Literal::Null => SqlType::Null,
+            },
+            Expression::Column(_)
+            | Expression::QualifiedColumn { .. }
+            | Expression::Parameter { .. } => self.bound(expression),
+            Expression::Function { args, .. } => {
+                for arg in args {
+                    self.infer(arg);
+                }
+                self.bound(expression)
+            }
+            Expression::Grouping { args, .. } => {
+                for arg in args {
+                    self.infer(arg);
+                }
+                SqlType::Integer
+            }
+            Expression::Cast {
+                expression,
+                data_type,
+                ..
+            } => {
+                self.infer(expression);
+                SqlType::of(data_type)
+            }
+            Expression::Unary {
+                operator,
+                operand,
+                span,
+            } => {
+                let operand = self.infer(operand);
+                self.unary(operator, operand).unwrap_or_else(|| {
+                    self.errors.push(TypeError::Unary {
+                        operator: operator.clone(),
+                        operand,
+                        span: *span,
+                    });
+                    SqlType::Unknown
+                })
+            }
+            // Each item is compared with the expression as by =
+            Expression::InList {
+                expression,
+                list,
+                span,
+                ..
+            } => {
+                let left_type = self.infer(expression);
+                for item in list {
+                    let right_type = self.infer(item);
+                    let (left_type, right_type) =
+                        self.coerce(expression, left_type, item, right_type);
+                    if self
+                        .binary(&BinaryOperator::Equal, left_type, right_type)
+                        .is_none()
+                    {
+                        self.errors.push(TypeError::Binary {
+                            operator: BinaryOperator::Equal,
+                            left: left_type,
+                            right: right_type,
+                            span: *span,
+                        });
+                    }
+                }
+                SqlType::Boolean
+            }
+            Expression::Binary {
+                left,
+                operator,
+                right,
+                span,
+            } => {
+                let left_type = self.infer(left);
+                let right_type = self.infer(right);
+                let (left_type, right_type) = self.coerce(left, left_type, right, right_type);
+                self.binary(operator, left_type, right_type)
+                    .unwrap_or_else(|| {
+                        self.errors.push(TypeError::Binary {
+                            operator: operator.clone(),
+                            left: left_type,
+                            right: right_type,
+                            span: *span,
+                        });
+                        SqlType::Unknown
+                    })
+            }
+        }
+    }
+
+    // PostgreSQL gives a quoted literal no type until it meets the other operand
+    fn coerce(
+        &self,
+        left: &Expression,
+        left_type: SqlType,
+        right: &Expression,
+        right_type: SqlType,
+    ) -> (SqlType, SqlType) {
+        if self.dialect == Dialect::PostgreSql {
+            if is_string_literal(left) && !right_type.is_open() {
+                return (right_type, right_type);
+            } else if is_string_literal(right) && !left_type.is_open() {
+                return (left_type, left_type);
+            }
+        }
+        (left_type, right_type)
+    }
+
+    fn is_boolean(&self, sql_type: SqlType) -> bool {
+        sql_type == SqlType::Boolean
+            || sql_type.is_open()
+            || (self.lenient() && sql_type == SqlType::Integer)
+    }
+
+    fn unary(&self, operator: &UnaryOperator, operand: SqlType) -> Option<SqlType> {
+        match operator {
+            UnaryOperator::Not if operand == SqlType::Null => Some(SqlType::Null),
+            UnaryOperator::Not => self.is_boolean(operand).then_some(SqlType::Boolean),
+            UnaryOperator::Minus => match operand {
+                SqlType::String if self.lenient() => Some(SqlType::Float),
+                operand if operand.is_numeric() || operand.is_open() => Some(operand),
+                _ => None,
+            },
+        }
+    }
+
+    fn binary(&self, operator: &BinaryOperator, left: SqlType, right: SqlType) -> Option<SqlType> {
+        match operator {
+            // Three-valued logic: NULL is a valid operand and the result is still a boolean
+            BinaryOperator::And | BinaryOperator::Or => {
+                (self.is_boolean(left) && self.is_boolean(right)).then_some(SqlType::Boolean)
+            }
+            BinaryOperator::Equal
+            | BinaryOperator::NotEqual
+            | BinaryOperator::LessThan
+            | BinaryOperator::LessThanOrEqual
+            | BinaryOperator::GreaterThan
+            | BinaryOperator::GreaterThanOrEqual => {
+                self.comparable(left, right).then_some(SqlType::Boolean)
+            }
+            BinaryOperator::Like | BinaryOperator::ILike => {
+                let text = |sql_type: SqlType| {
+                    sql_type == SqlType::String
+                        || sql_type.is_open()
+                        || (self.lenient() && sql_type.is_numeric())
+                };
+                (text(left) && text(right)).then_some(SqlType::Boolean)
+            }
+            BinaryOperator::Add
+            | BinaryOperator::Subtract
+            | BinaryOperator::Multiply
+            | BinaryOperator::Divide => self.arithmetic(operator, left, right),
+        }
+    }
+
+    // Strings compare with dates and times, which are usually written as quoted literals
+    fn comparable(&self, left: SqlType, right: SqlType) -> bool {
+        let scalar = |sql_type: SqlType| {
+            sql_type.is_numeric() || matches!(sql_type, SqlType::String | SqlType::Boolean)
+        };
+        left == right
+            || left.is_open()
+            || right.is_open()
+            || (left.is_numeric() && right.is_numeric())
+            || (matches!(left, SqlType::Date | SqlType::Timestamp)
+                && matches!(right, SqlType::Date | SqlType::Timestamp))
+            || (left == SqlType::String && right.is_temporal())
+            || (left.is_temporal() && right == SqlType::String)
+            || (self.lenient() && scalar(left) && scalar(right))
+    }
+
+    // Integers are promoted to floats when the other operand is one, and arithmetic on NULL
+    // gives NULL. A date plus or minus an integer of days is a date, and two dates differ by one
+    fn arithmetic(
+        &self,
+        operator: &BinaryOperator,
+        left: SqlType,
+        right: SqlType,
+    ) -> Option<SqlType> {
+        let operand = |sql_type: SqlType| match sql_type {
+            SqlType::String if self.lenient() => SqlType::Float,
+            sql_type => sql_type,
+        };
+        let (left, right) = (operand(left), operand(right));
+        let accepts = |sql_type: SqlType| {
+            sql_type.is_open() || sql_type.is_numeric() || sql_type.is_temporal()
+        };
+        if !accepts(left) || !accepts(right) {
+            return None;
+        }
+        let adds = matches!(operator, BinaryOperator::Add | BinaryOperator::Subtract);
+        Some(match (left, right) {
+            (SqlType::Unknown, _) | (_, SqlType::Unknown) => SqlType::Unknown,
+            (SqlType::Null, _) | (_, SqlType::Null) => SqlType::Null,
+            (SqlType::Integer, SqlType::Integer) => SqlType::Integer,
+            (left, right) if left.is_numeric() && right.is_numeric() => SqlType::Float,
+            (SqlType::Date, SqlType::Integer) if adds => SqlType::Date,
+            (SqlType::Integer, SqlType::Date) if *operator == BinaryOperator::Add => SqlType::Date,
+            (SqlType::Date, SqlType::Date) if *operator == BinaryOperator::Subtract => {
+                SqlType::Integer
+            }
+            _ => return None,
+        })
+    }
+}
+
+fn is_string_literal(expression: &Expression) -> bool {
+    matches!(
+        expression,
+        Expression::Literal {
+            value: Literal::String(_),
+            ..
+        }
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::binder::bind;
+    use crate::catalog::MemoryCatalog;
+    use crate::parser::{parse_expression, parse_sql};
+
+    fn check(sql: &str, dialect: Dialect) -> Result<SqlType, Vec<TypeError>> {
+        check_expression(&parse_expression(sql).unwrap(), dialect)
+    }
+
+    fn operators(sql: &str, dialect: Dialect) -> Vec<BinaryOperator> {
+        check(sql, dialect)
+            .unwrap_err()
+            .into_iter()
+            .filter_map(|error| match error {
+                TypeError::Binary { operator, .. } => Some(operator),
+                TypeError::Unary { .. } => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_promotion_and_null() {
+        assert_eq!(check("1 + 2", Dialect::Generic), Ok(SqlType::Integer));
+        assert_eq!(check("1 / 2 * 2.5", Dialect::Generic), Ok(SqlType::Float));
+        assert_eq!(check("-1.5", Dialect::Generic), Ok(SqlType::Float));
+        assert_eq!(check("NULL + 1", Dialect::Generic), Ok(SqlType::Null));
+        assert_eq!(check("x * 2", Dialect::Generic), Ok(SqlType::Unknown));
+        assert_eq!(
+            check("1 = 1.0 AND NULL", Dialect::Generic),
+            Ok(SqlType::Boolean)
+        );
+        assert_eq!(
+            check("NOT NULL OR TRUE", Dialect::Generic),
+            Ok(SqlType::Boolean)
+        );
+        assert_eq!(
+            check("CAST(x AS DATE) + 7", Dialect::Generic),
+            Ok(SqlType::Date)
+        );
+        assert_eq!(
+            check("CAST(x AS DATE) - CAST(y AS DATE)", Dialect::Generic),
+            Ok(SqlType::Integer)
+        );
+        assert_eq!(
+            check("CAST(x AS TIMESTAMP) >= '2024-01-01'", Dialect::Generic),
+            Ok(SqlType::Boolean)
+        );
+    }
+
+    #[test]
+    fn test_reports_operator_and_span() {
+        let errors = check("'a' + 1 > TRUE", Dialect::Generic).unwrap_err();
+        assert_eq!(
+            errors,
+            [TypeError::Binary {
+                operator: BinaryOperator::Add,
+                left: SqlType::String,
+                right: SqlType::Integer,
+                span: Span::default(),
+            }]
+        );
+        assert_eq!(errors[0].span().end, 7);
+        assert_eq!(
+            errors[0].to_string(),
+            "cannot apply + to TEXT and INTEGER at line 1, column 1"
+        );
+
+        assert_eq!(
+            operators("TRUE + 1 = 'x' AND 1 LIKE 2 OR 3", Dialect::Generic),
+            [
+                BinaryOperator::Add,
+                BinaryOperator::Like,
+                BinaryOperator::Or
+            ]
+        );
+        assert_eq!(
+            check("NOT 1", Dialect::Generic).unwrap_err()[0].to_string(),
+            "cannot apply NOT to INTEGER at line 1, column 1"
+        );
+        assert!(check("CAST(x AS TIME) + 1", Dialect::Generic).is_err());
+    }
+
+    #[test]
+    fn test_in_list_items_compare_with_the_expression() {
+        assert_eq!(
+            check("1 IN (2, 3.5)", Dialect::Generic),
+            Ok(SqlType::Boolean)
+        );
+        assert_eq!(
+            operators("1 NOT IN (2, 'x', TRUE)", Dialect::Generic),
+            [BinaryOperator::Equal, BinaryOperator::Equal]
+        );
+        assert_eq!(
+            check("1 IN ('2')", Dialect::PostgreSql),
+            Ok(SqlType::Boolean)
+        );
+        assert_eq!(
+            operators("(1 IN (2)) + 1", Dialect::Generic),
+            [BinaryOperator::Add]
+        );
+    }
+
+    #[test]
+    fn test_string_and_number_per_dialect() {
+        assert_eq!(
+            operators("'1' = 1", Dialect::Generic),
+            [BinaryOperator::Equal]
+        );
+        assert_eq!(check("'1' = 1", Dialect::PostgreSql), Ok(SqlType::Boolean));
+        assert_eq!(check("'1' + 1", Dialect::PostgreSql), Ok(SqlType::Integer));
+        assert_eq!(
+            operators("CAST(x AS TEXT) = 1", Dialect::PostgreSql),
+            [BinaryOperator::Equal]
+        );
+        assert_eq!(check("'1' = 1", Dialect::MySql), Ok(SqlType::Boolean));
+        assert_eq!(check("'1' + 1", Dialect::Sqlite), Ok(SqlType::Float));
+        assert_eq!(
+            check("1 AND TRUE = 1", Dialect::MySql),
+            Ok(SqlType::Boolean)
+        );
+        assert_eq!(
+            operators("1 AND TRUE", Dialect::PostgreSql),
+            [BinaryOperator::And]
+        );
+    }
+
+    #[test]
+    fn test_statement_with_binding() {
+        let catalog = MemoryCatalog::from_sql(
+            "CREATE TABLE users (id INTEGER, name TEXT, born DATE, active BOOLEAN);",
+        )
+        .unwrap();
+        let statement = parse_sql(
+            "PREPARE q (integer) AS SELECT * FROM users \
+             WHERE name + 1 > TRUE AND born < $1 AND LOWER(name) LIKE 'a%' AND active;",
+        )
+        .unwrap();
+        let binding = bind(&statement, &catalog);
+        assert!(binding.is_ok());
+
+        let errors = check_statement(&statement, Some(&binding), Dialect::PostgreSql);
+        assert_eq!(
+            errors
+                .iter()
+                .map(|error| error.to_string())
+                .collect::<Vec<_>>(),
+            [
+                "cannot apply + to TEXT and INTEGER at line 1, column 50",
+                "cannot apply < to DATE and INTEGER at line 1, column 70",
+            ]
+        );
+
+        // Without a binding the columns are unknown
+        assert!(check_statement(&statement, None, Dialect::PostgreSql).is_empty());
+    }
+}
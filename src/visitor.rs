@@ -0,0 +1,885 @@
+use crate::ast::{
+    ColumnDefinition, ColumnOption, ConflictTarget, DataType, Expression, GroupingElement, Ident,
+    MergeAction, MergeClause, MergeSource, ObjectName, OnConflict, OrderByItem, Statement,
+};
+
+// Returned by the hooks to steer the traversal
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Flow {
+    Continue,
+    // From a pre hook: do not descend into the node. Its post hook still runs
+    SkipChildren,
+    // End the whole traversal. The walk_* function that started it returns Stop
+    Stop,
+}
+
+// Returns early from the enclosing walker once a child has stopped the traversal
+macro_rules! walk {
+    ($flow:expr) => {
+        if $flow == Flow::Stop {
+            return Flow::Stop;
+        }
+    };
+}
+
+// Calls the pre hook, the walk of the children unless skipped, and then the post hook
+macro_rules! visit {
+    ($visitor:ident, $node:ident, $pre:ident, $post:ident, $children:block) => {{
+        let flow = $visitor.$pre($node);
+        walk!(flow);
+        if flow == Flow::Continue $children
+        match $visitor.$post($node) {
+            Flow::Stop => Flow::Stop,
+            _ => Flow::Continue,
+        }
+    }};
+}
+
+// Visitor and VisitorMut share one definition that differs only in the reference type.
+// Every child node of the AST is reached from walk_statement
+macro_rules! visitor {
+    (
+        $visitor:ident,
+        [$($mutability:tt)?],
+        $walk_statement:ident,
+        $walk_expression:ident,
+        $walk_table:ident,
+        $walk_ident:ident,
+        $walk_order_by_item:ident,
+        $walk_grouping_element:ident,
+        $walk_on_conflict:ident,
+        $walk_conflict_target:ident,
+        $walk_merge_source:ident,
+        $walk_merge_clause:ident,
+        $walk_merge_action:ident,
+        $walk_column_definition:ident,
+        $walk_data_type:ident
+    ) => {
+        pub trait $visitor {
+            fn pre_visit_statement(&mut self, _statement: &$($mutability)? Statement) -> Flow {
+                Flow::Continue
+            }
+
+            fn post_visit_statement(&mut self, _statement: &$($mutability)? Statement) -> Flow {
+                Flow::Continue
+            }
+
+            fn pre_visit_expression(&mut self, _expression: &$($mutability)? Expression) -> Flow {
+                Flow::Continue
+            }
+
+            fn post_visit_expression(&mut self, _expression: &$($mutability)? Expression) -> Flow {
+                Flow::Continue
+            }
+
+            // Tables that a statement reads or writes. Their names are then visited as identifiers
+            fn pre_visit_table(&mut self, _table: &$($mutability)? ObjectName) -> Flow {
+                Flow::Continue
+            }
+
+            fn post_visit_table(&mut self, _table: &$($mutability)? ObjectName) -> Flow {
+                Flow::Continue
+            }
+
+            fn pre_visit_ident(&mut self, _ident: &$($mutability)? Ident) -> Flow {
+                Flow::Continue
+            }
+
+            fn post_visit_ident(&mut self, _ident: &$($mutability)? Ident) -> Flow {
+                Flow::Continue
+            }
+
+            fn pre_visit_order_by_item(&mut self, _item: &$($mutability)? OrderByItem) -> Flow {
+                Flow::Continue
+            }
+
+            fn post_visit_order_by_item(&mut self, _item: &$($mutability)? OrderByItem) -> Flow {
+                Flow::Continue
+            }
+
+            fn pre_visit_grouping_element(
+                &mut self,
+                _element: &$($mutability)? GroupingElement,
+            ) -> Flow {
+                Flow::Continue
+            }
+
+            fn post_visit_grouping_element(
+                &mut self,
+                _element: &$($mutability)? GroupingElement,
+            ) -> Flow {
+                Flow::Continue
+            }
+
+            fn pre_visit_on_conflict(
+                &mut self,
+                _on_conflict: &$($mutability)? OnConflict,
+            ) -> Flow {
+                Flow::Continue
+            }
+
+            fn post_visit_on_conflict(
+                &mut self,
+                _on_conflict: &$($mutability)? OnConflict,
+            ) -> Flow {
+                Flow::Continue
+            }
+
+            fn pre_visit_conflict_target(
+                &mut self,
+                _target: &$($mutability)? ConflictTarget,
+            ) -> Flow {
+                Flow::Continue
+            }
+
+            fn post_visit_conflict_target(
+                &mut self,
+                _target: &$($mutability)? ConflictTarget,
+            ) -> Flow {
+                Flow::Continue
+            }
+
+            fn pre_visit_merge_source(&mut self, _source: &$($mutability)? MergeSource) -> Flow {
+                Flow::Continue
+            }
+
+            fn post_visit_merge_source(&mut self, _source: &$($mutability)? MergeSource) -> Flow {
+                Flow::Continue
+            }
+
+            fn pre_visit_merge_clause(&mut self, _clause: &$($mutability)? MergeClause) -> Flow {
+                Flow::Continue
+            }
+
+            fn post_visit_merge_clause(&mut self, _clause: &$($mutability)? MergeClause) -> Flow {
+                Flow::Continue
+            }
+
+            fn pre_visit_merge_action(&mut self, _action: &$($mutability)? MergeAction) -> Flow {
+                Flow::Continue
+            }
+
+            fn post_visit_merge_action(&mut self, _action: &$($mutability)? MergeAction) -> Flow {
+                Flow::Continue
+            }
+
+            fn pre_visit_column_definition(
+                &mut self,
+                _column: &$($mutability)? ColumnDefinition,
+            ) -> Flow {
+                Flow::Continue
+            }
+
+            fn post_visit_column_definition(
+                &mut self,
+                _column: &$($mutability)? ColumnDefinition,
+            ) -> Flow {
+                Flow::Continue
+            }
+
+            fn pre_visit_data_type(&mut self, _data_type: &$($mutability)? DataType) -> Flow {
+                Flow::Continue
+            }
+
+            fn post_visit_data_type(&mut self, _data_type: &$($mutability)? DataType) -> Flow {
+                Flow::Continue
+            }
+        }
+
+        pub fn $walk_statement<V: $visitor + ?Sized>(
+            visitor: &mut V,
+            statement: &$($mutability)? Statement,
+        ) -> Flow {
+            visit!(visitor, statement, pre_visit_statement, post_visit_statement, {
+                match statement {
+                    Statement::Select {
+                        table,
+                        where_clause,
+                        order_by,
+                        group_by,
+                        limit,
+                        offset,
+                        ..
+                    } => {
+                        walk!($walk_table(visitor, table));
+                        if let Some(where_clause) = where_clause {
+                            walk!($walk_expression(visitor, where_clause));
+                        }
+                        if let Some(group_by) = group_by {
+                            for element in &$($mutability)? group_by.items {
+                                walk!($walk_grouping_element(visitor, element));
+                            }
+                        }
+                        if let Some(order_by) = order_by {
+                            for item in &$($mutability)? order_by.items {
+                                walk!($walk_order_by_item(visitor, item));
+                            }
+                        }
+                        if let Some(limit) = limit {
+                            walk!($walk_expression(visitor, limit));
+                        }
+                        if let Some(offset) = offset {
+                            walk!($walk_expression(visitor, offset));
+                        }
+                    }
+                    Statement::Insert {
+                        table,
+                        on_conflict,
+                        ..
+                    } => {
+                        walk!($walk_table(visitor, table));
+                        if let Some(on_conflict) = on_conflict {
+                            walk!($walk_on_conflict(visitor, on_conflict));
+                        }
+                    }
+                    Statement::Update {
+                        table,
+                        set,
+                        where_clause,
+                        ..
+                    } => {
+                        walk!($walk_table(visitor, table));
+                        for (column, _) in set {
+                            walk!($walk_ident(visitor, column));
+                        }
+                        if let Some(where_clause) = where_clause {
+                            walk!($walk_expression(visitor, where_clause));
+                        }
+                    }
+                    Statement::Delete {
+                        table,
+                        where_clause,
+                        ..
+                    } => {
+                        walk!($walk_table(visitor, table));
+                        if let Some(where_clause) = where_clause {
+                            walk!($walk_expression(visitor, where_clause));
+                        }
+                    }
+                    Statement::Merge {
+                        table,
+                        alias,
+                        source,
+                        on,
+                        clauses,
+                        ..
+                    } => {
+                        walk!($walk_table(visitor, table));
+                        if let Some(alias) = alias {
+                            walk!($walk_ident(visitor, alias));
+                        }
+                        walk!($walk_merge_source(visitor, source));
+                        walk!($walk_expression(visitor, on));
+                        for clause in clauses {
+                            walk!($walk_merge_clause(visitor, clause));
+                        }
+                    }
+                    Statement::Prepare {
+                        name,
+                        parameter_types,
+                        statement,
+                        ..
+                    } => {
+                        walk!($walk_ident(visitor, name));
+                        for data_type in parameter_types {
+                            walk!($walk_data_type(visitor, data_type));
+                        }
+                        walk!($walk_statement(visitor, statement));
+                    }
+                    Statement::Execute {
+                        name, parameters, ..
+                    } => {
+                        walk!($walk_ident(visitor, name));
+                        for parameter in parameters {
+                            walk!($walk_expression(visitor, parameter));
+                        }
+                    }
+                    Statement::Deallocate { name, .. } => {
+                        if let Some(name) = name {
+                            walk!($walk_ident(visitor, name));
+                        }
+                    }
+                    Statement::CreateTable { table, columns, .. } => {
+                        walk!($walk_table(visitor, table));
+                        for column in columns {
+                            walk!($walk_column_definition(visitor, column));
+                        }
+                    }
+                }
+            })
+        }
+
+        pub fn $walk_expression<V: $visitor + ?Sized>(
+            visitor: &mut V,
+            expression: &$($mutability)? Expression,
+        ) -> Flow {
+            visit!(visitor, expression, pre_visit_expression, post_visit_expression, {
+                match expression {
+                    Expression::Literal { .. } | Expression::Parameter { .. } => {}
+                    Expression::Column(column) => walk!($walk_ident(visitor, column)),
+                    Expression::QualifiedColumn { table, column } => {
+                        walk!($walk_ident(visitor, table));
+                        walk!($walk_ident(visitor, column));
+                    }
+                    Expression::Function { name, args, .. } => {
+                        walk!($walk_ident(visitor, name));
+                        for arg in args {
+                            walk!($walk_expression(visitor, arg));
+                        }
+                    }
+                    Expression::Grouping { args, .. } => {
+                        for arg in args {
+                            walk!($walk_expression(visitor, arg));
+                        }
+                    }
+                    Expression::Binary { left, right, .. } => {
+                        walk!($walk_expression(visitor, left));
+                        walk!($walk_expression(visitor, right));
+                    }
+                    Expression::Unary { operand, .. } => {
+                        walk!($walk_expression(visitor, operand));
+                    }
+                    Expression::Cast {
+                        expression,
+                        data_type,
+                        ..
+                    } => {
+                        walk!($walk_expression(visitor, expression));
+                        walk!($walk_data_type(visitor, data_type));
+                    }
+                    Expression::InList {
+                        expression, list, ..
+                    } => {
+                        walk!($walk_expression(visitor, expression));
+                        for item in list {
+                            walk!($walk_expression(visitor, item));
+                        }
+                    }
+                }
+            })
+        }
+
+        pub fn $walk_table<V: $visitor + ?Sized>(
+            visitor: &mut V,
+            table: &$($mutability)? ObjectName,
+        ) -> Flow {
+            visit!(visitor, table, pre_visit_table, post_visit_table, {
+                if let Some(schema) = &$($mutability)? table.schema {
+                    walk!($walk_ident(visitor, schema));
+                }
+                walk!($walk_ident(visitor, &$($mutability)? table.name));
+            })
+        }
+
+        pub fn $walk_ident<V: $visitor + ?Sized>(
+            visitor: &mut V,
+            ident: &$($mutability)? Ident,
+        ) -> Flow {
+            visit!(visitor, ident, pre_visit_ident, post_visit_ident, {})
+        }
+
+        pub fn $walk_order_by_item<V: $visitor + ?Sized>(
+            visitor: &mut V,
+            item: &$($mutability)? OrderByItem,
+        ) -> Flow {
+            visit!(visitor, item, pre_visit_order_by_item, post_visit_order_by_item, {
+                walk!($walk_ident(visitor, &$($mutability)? item.column));
+            })
+        }
+
+        pub fn $walk_grouping_element<V: $visitor + ?Sized>(
+            visitor: &mut V,
+            element: &$($mutability)? GroupingElement,
+        ) -> Flow {
+            visit!(visitor, element, pre_visit_grouping_element, post_visit_grouping_element, {
+                match element {
+                    GroupingElement::Expression(expression) => {
+                        walk!($walk_expression(visitor, expression));
+                    }
+                    GroupingElement::Set(expressions) => {
+                        for expression in expressions {
+                            walk!($walk_expression(visitor, expression));
+                        }
+                    }
+                    GroupingElement::Rollup(elements)
+                    | GroupingElement::Cube(elements)
+                    | GroupingElement::GroupingSets(elements) => {
+                        for element in elements {
+                            walk!($walk_grouping_element(visitor, element));
+                        }
+                    }
+                }
+            })
+        }
+
+        pub fn $walk_on_conflict<V: $visitor + ?Sized>(
+            visitor: &mut V,
+            on_conflict: &$($mutability)? OnConflict,
+        ) -> Flow {
+            visit!(visitor, on_conflict, pre_visit_on_conflict, post_visit_on_conflict, {
+                match on_conflict {
+                    OnConflict::DoNothing { target } => {
+                        if let Some(target) = target {
+                            walk!($walk_conflict_target(visitor, target));
+                        }
+                    }
+                    OnConflict::DoUpdate {
+                        target,
+                        set,
+                        where_clause,
+                    } => {
+                        walk!($walk_conflict_target(visitor, target));
+                        for (column, value) in set {
+                            walk!($walk_ident(visitor, column));
+                            walk!($walk_expression(visitor, value));
+                        }
+                        if let Some(where_clause) = where_clause {
+                            walk!($walk_expression(visitor, where_clause));
+                        }
+                    }
+                    OnConflict::DuplicateKeyUpdate { set } => {
+                        for (column, value) in set {
+                            walk!($walk_ident(visitor, column));
+                            walk!($walk_expression(visitor, value));
+                        }
+                    }
+                }
+            })
+        }
+
+        pub fn $walk_conflict_target<V: $visitor + ?Sized>(
+            visitor: &mut V,
+            target: &$($mutability)? ConflictTarget,
+        ) -> Flow {
+            visit!(visitor, target, pre_visit_conflict_target, post_visit_conflict_target, {
+                match target {
+                    ConflictTarget::Columns(columns) => {
+                        for column in columns {
+                            walk!($walk_ident(visitor, column));
+                        }
+                    }
+                    ConflictTarget::Constraint(name) => walk!($walk_ident(visitor, name)),
+                }
+            })
+        }
+
+        pub fn $walk_merge_source<V: $visitor + ?Sized>(
+            visitor: &mut V,
+            source: &$($mutability)? MergeSource,
+        ) -> Flow {
+            visit!(visitor, source, pre_visit_merge_source, post_visit_merge_source, {
+                let alias = match source {
+                    MergeSource::Table { name, alias } => {
+                        walk!($walk_table(visitor, name));
+                        alias
+                    }
+                    MergeSource::Subquery { query, alias } => {
+                        walk!($walk_statement(visitor, query));
+                        alias
+                    }
+                };
+                if let Some(alias) = alias {
+                    walk!($walk_ident(visitor, alias));
+                }
+            })
+        }
+
+        pub fn $walk_merge_clause<V: $visitor + ?Sized>(
+            visitor: &mut V,
+            clause: &$($mutability)? MergeClause,
+        ) -> Flow {
+            visit!(visitor, clause, pre_visit_merge_clause, post_visit_merge_clause, {
+                if let Some(condition) = &$($mutability)? clause.condition {
+                    walk!($walk_expression(visitor, condition));
+                }
+                walk!($walk_merge_action(visitor, &$($mutability)? clause.action));
+            })
+        }
+
+        pub fn $walk_merge_action<V: $visitor + ?Sized>(
+            visitor: &mut V,
+            action: &$($mutability)? MergeAction,
+        ) -> Flow {
+            visit!(visitor, action, pre_visit_merge_action, post_visit_merge_action, {
+                match action {
+                    MergeAction::Update { set } => {
+                        for (column, value) in set {
+                            walk!($walk_ident(visitor, column));
+                            walk!($walk_expression(visitor, value));
+                        }
+                    }
+                    MergeAction::Delete => {}
+                    MergeAction::Insert { columns, values } => {
+                        for column in columns {
+                            walk!($walk_ident(visitor, column));
+                        }
+                        for value in values {
+                            walk!($walk_expression(visitor, value));
+                        }
+                    }
+                }
+            })
+        }
+
+        pub fn $walk_column_definition<V: $visitor + ?Sized>(
+            visitor: &mut V,
+            column: &$($mutability)? ColumnDefinition,
+        ) -> Flow {
+            visit!(visitor, column, pre_visit_column_definition, post_visit_column_definition, {
+                walk!($walk_ident(visitor, &$($mutability)? column.name));
+                walk!($walk_data_type(visitor, &$($mutability)? column.data_type));
+                for option in &$($mutability)? column.options {
+                    if let ColumnOption::Default(expression) = option {
+                        walk!($walk_expression(visitor, expression));
+                    }
+                }
+            })
+        }
+
+        pub fn $walk_data_type<V: $visitor + ?Sized>(
+            visitor: &mut V,
+            data_type: &$($mutability)? DataType,
+        ) -> Flow {
+            visit!(visitor, data_type, pre_visit_data_type, post_visit_data_type, {})
+        }
+    };
+}
+
+visitor!(
+    Visitor,
+    [],
+    walk_statement,
+    walk_expression,
+    walk_table,
+    walk_ident,
+    walk_order_by_item,
+    walk_grouping_element,
+    walk_on_conflict,
+    walk_conflict_target,
+    walk_merge_source,
+    walk_merge_clause,
+    walk_merge_action,
+    walk_column_definition,
+    walk_data_type
+);
+
+visitor!(
+    VisitorMut,
+    [mut],
+    walk_statement_mut,
+    walk_expression_mut,
+    walk_table_mut,
+    walk_ident_mut,
+    walk_order_by_item_mut,
+    walk_grouping_element_mut,
+    walk_on_conflict_mut,
+    walk_conflict_target_mut,
+    walk_merge_source_mut,
+    walk_merge_clause_mut,
+    walk_merge_action_mut,
+    walk_column_definition_mut,
+    walk_data_type_mut
+);
+
+#[cfg(test)]
+mod tests {
+    use super::{walk_expression, walk_statement, walk_statement_mut, Flow, Visitor, VisitorMut};
+    use crate::ast::{
+        ColumnDefinition, ConflictTarget, DataType, Expression, GroupingElement, Ident, Literal,
+        MergeAction, MergeClause, MergeSource, ObjectName, OnConflict, OrderByItem, Span,
+        Statement,
+    };
+    use crate::parser::{parse_expression, parse_sql};
+
+    // Records every hook call
+    #[derive(Default)]
+    struct Recorder {
+        events: Vec<String>,
+    }
+
+    impl Recorder {
+        fn record(&mut self, event: String) -> Flow {
+            self.events.push(event);
+            Flow::Continue
+        }
+    }
+
+    impl Visitor for Recorder {
+        fn pre_visit_statement(&mut self, _: &Statement) -> Flow {
+            self.record("statement".to_string())
+        }
+
+        fn post_visit_statement(&mut self, _: &Statement) -> Flow {
+            self.record("/statement".to_string())
+        }
+
+        fn pre_visit_expression(&mut self, expression: &Expression) -> Flow {
+            self.record(format!("expression {}", expression))
+        }
+
+        fn post_visit_expression(&mut self, _: &Expression) -> Flow {
+            self.record("/expression".to_string())
+        }
+
+        fn pre_visit_table(&mut self, table: &ObjectName) -> Flow {
+            self.record(format!("table {}", table))
+        }
+
+        fn pre_visit_ident(&mut self, ident: &Ident) -> Flow {
+            self.record(format!("ident {}", ident))
+        }
+
+        fn pre_visit_order_by_item(&mut self, _: &OrderByItem) -> Flow {
+            self.record("order by item".to_string())
+        }
+
+        fn pre_visit_grouping_element(&mut self, element: &GroupingElement) -> Flow {
+            self.record(format!("grouping element {}", element))
+        }
+
+        fn pre_visit_on_conflict(&mut self, _: &OnConflict) -> Flow {
+            self.record("on conflict".to_string())
+        }
+
+        fn pre_visit_conflict_target(&mut self, target: &ConflictTarget) -> Flow {
+            self.record(format!("conflict target {}", target))
+        }
+
+        fn pre_visit_merge_source(&mut self, source: &MergeSource) -> Flow {
+            self.record(format!("merge source {}", source))
+        }
+
+        fn pre_visit_merge_clause(&mut self, _: &MergeClause) -> Flow {
+            self.record("merge clause".to_string())
+        }
+
+        fn pre_visit_merge_action(&mut self, _: &MergeAction) -> Flow {
+            self.record("merge action".to_string())
+        }
+
+        fn pre_visit_column_definition(&mut self, column: &ColumnDefinition) -> Flow {
+            self.record(format!("column {}", column))
+        }
+
+        fn pre_visit_data_type(&mut self, data_type: &DataType) -> Flow {
+            self.record(format!("data type {}", data_type))
+        }
+    }
+
+    fn events(sql: &str) -> Vec<String> {
+        let mut recorder = Recorder::default();
+        assert_eq!(
+            walk_statement(&mut recorder, &parse_sql(sql).unwrap()),
+            Flow::Continue
+        );
+        recorder.events
+    }
+
+    #[test]
+    fn test_pre_and_post_order() {
+        assert_eq!(
+            events("SELECT * FROM users WHERE id = f(1) ORDER BY name;"),
+            [
+                "statement",
+                "table users",
+                "ident users",
+                "expression id = f(1)",
+                "expression id",
+                "ident id",
+                "/expression",
+                "expression f(1)",
+                "ident f",
+                "expression 1",
+                "/expression",
+                "/expression",
+                "/expression",
+                "order by item",
+                "ident name",
+                "/statement",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_visit_every_node_kind() {
+        let recorded = events(
+            "MERGE INTO target AS t USING (SELECT * FROM source GROUP BY ROLLUP(a)) AS s ON t.id = s.id \
+             WHEN MATCHED THEN UPDATE SET name = s.name \
+             WHEN NOT MATCHED THEN INSERT (id) VALUES (s.id);",
+        );
+        for expected in [
+            "table target",
+            "ident t",
+            "merge source (SELECT * FROM source GROUP BY ROLLUP(a)) AS s",
+            "table source",
+            "grouping element ROLLUP(a)",
+            "grouping element a",
+            "ident s",
+            "merge clause",
+            "merge action",
+            "ident name",
+            "expression s.name",
+        ] {
+            assert!(recorded.contains(&expected.to_string()), "{}", expected);
+        }
+        // The subquery is a statement of its own
+        assert_eq!(recorded.iter().filter(|e| *e == "statement").count(), 2);
+
+        let recorded = events(
+            "INSERT INTO users VALUES ('1') ON CONFLICT (id) DO UPDATE SET name = EXCLUDED.name;",
+        );
+        for expected in [
+            "on conflict",
+            "conflict target (id)",
+            "ident id",
+            "ident EXCLUDED",
+        ] {
+            assert!(recorded.contains(&expected.to_string()), "{}", expected);
+        }
+
+        let recorded = events("PREPARE find (integer) AS DELETE FROM users WHERE id = $1;");
+        for expected in [
+            "ident find",
+            "data type INTEGER",
+            "table users",
+            "expression $1",
+        ] {
+            assert!(recorded.contains(&expected.to_string()), "{}", expected);
+        }
+
+        let recorded = events("CREATE TABLE t (id INTEGER DEFAULT CAST(0 AS BIGINT));");
+        for expected in [
+            "table t",
+            "column id INTEGER DEFAULT CAST(0 AS BIGINT)",
+            "ident id",
+            "data type INTEGER",
+            "expression CAST(0 AS BIGINT)",
+            "expression 0",
+            "data type BIGINT",
+        ] {
+            assert!(recorded.contains(&expected.to_string()), "{}", expected);
+        }
+    }
+
+    // Collects table names, but not from inside expressions
+    #[derive(Default)]
+    struct Tables {
+        tables: Vec<String>,
+        identifiers: usize,
+    }
+
+    impl Visitor for Tables {
+        fn pre_visit_expression(&mut self, _: &Expression) -> Flow {
+            Flow::SkipChildren
+        }
+
+        fn pre_visit_table(&mut self, table: &ObjectName) -> Flow {
+            self.tables.push(table.name.value.clone());
+            Flow::Continue
+        }
+
+        fn pre_visit_ident(&mut self, _: &Ident) -> Flow {
+            self.identifiers += 1;
+            Flow::Continue
+        }
+    }
+
+    #[test]
+    fn test_skip_children() {
+        let statement = parse_sql(
+            "MERGE INTO target USING source ON target.id = source.id WHEN MATCHED THEN DELETE;",
+        )
+        .unwrap();
+        let mut tables = Tables::default();
+        walk_statement(&mut tables, &statement);
+        assert_eq!(tables.tables, ["target", "source"]);
+        // Only the table names: the identifiers in the ON condition are skipped
+        assert_eq!(tables.identifiers, 2);
+    }
+
+    // Stops at the first parameter placeholder
+    #[derive(Default)]
+    struct FirstParameter {
+        found: Option<String>,
+        visited: usize,
+    }
+
+    impl Visitor for FirstParameter {
+        fn pre_visit_expression(&mut self, expression: &Expression) -> Flow {
+            self.visited += 1;
+            match expression {
+                Expression::Parameter { name, .. } => {
+                    self.found = Some(name.clone());
+                    Flow::Stop
+                }
+                _ => Flow::Continue,
+            }
+        }
+
+        fn post_visit_expression(&mut self, _: &Expression) -> Flow {
+            assert!(self.found.is_none(), "post hook called after stopping");
+            Flow::Continue
+        }
+    }
+
+    #[test]
+    fn test_stop_early() {
+        let expression = parse_expression("a = $1 AND b = $2").unwrap();
+        let mut visitor = FirstParameter::default();
+        assert_eq!(walk_expression(&mut visitor, &expression), Flow::Stop);
+        assert_eq!(visitor.found.as_deref(), Some("$1"));
+        // a = $1 AND b = $2, a = $1, a, $1
+        assert_eq!(visitor.visited, 4);
+    }
+
+    // Renames tables and binds parameters in place
+    struct Rewriter;
+
+    impl VisitorMut for Rewriter {
+        fn pre_visit_table(&mut self, table: &mut ObjectName) -> Flow {
+            table.name.value = format!("archive_{}", table.name.value);
+            // Leave the renamed identifier alone
+            Flow::SkipChildren
+        }
+
+        fn pre_visit_ident(&mut self, ident: &mut Ident) -> Flow {
+            ident.value = ident.value.to_uppercase();
+            Flow::Continue
+        }
+
+        fn post_visit_expression(&mut self, expression: &mut Expression) -> Flow {
+            if let Expression::Parameter { span, .. } = expression {
+                *expression = Expression::Literal {
+                    value: Literal::Number(42),
+                    span: *span,
+                };
+            }
+            Flow::Continue
+        }
+    }
+
+    #[test]
+    fn test_visitor_mut_rewrites_in_place() {
+        let mut statement =
+            parse_sql("DELETE FROM users WHERE id = $1 AND users.active = TRUE;").unwrap();
+        assert_eq!(
+            walk_statement_mut(&mut Rewriter, &mut statement),
+            Flow::Continue
+        );
+        assert_eq!(
+            statement.to_string(),
+            "DELETE FROM archive_users WHERE ID = 42 AND USERS.ACTIVE = TRUE;"
+        );
+    }
+
+    #[test]
+    fn test_walk_constructed_ast() {
+        let statement = Statement::Deallocate {
+            name: Some("plan".into()),
+            span: Span::default(),
+        };
+        let mut recorder = Recorder::default();
+        walk_statement(&mut recorder, &statement);
+        assert_eq!(recorder.events, ["statement", "ident plan", "/statement"]);
+    }
+}
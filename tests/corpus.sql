@@ -0,0 +1,68 @@
+-- Statements shared by the parser, renderer, formatter and JSON round-trip tests.
+-- Every statement must parse with the default options.
+
+SELECT * FROM users;
+INSERT INTO users VALUES ('test_user', 'test_password');
+UPDATE users SET name = 'new_name', password = 'new_password';
+DELETE FROM users;
+SELECT   *   FROM   users  ;
+select * from users;
+INSERT INTO users VALUES ('only_one');
+UPDATE users SET name = 'foo';
+SELECT * FROM user_01;
+INSERT INTO users VALUES ('foo_1', 'bar2');
+UPDATE users SET name_1 = 'foo', pass2 = 'bar';
+INSERT INTO users VALUES ('ユーザー', 'パスワード');
+SELECT * FROM users WHERE name = 'John';
+UPDATE users SET name = 'Jane' WHERE id = '1';
+DELETE FROM users WHERE active = 'false';
+SELECT * FROM users ORDER BY name ASC;
+SELECT * FROM users ORDER BY name DESC;
+SELECT * FROM users ORDER BY name ASC, created_at DESC;
+SELECT * FROM users GROUP BY department;
+SELECT * FROM users GROUP BY department, status;
+SELECT * FROM users LIMIT 10;
+SELECT * FROM users WHERE active = 'true' GROUP BY department ORDER BY name ASC LIMIT 10;
+SELECT * FROM users WHERE 1 + 2 * 3 = 7;
+SELECT * FROM users WHERE (1 + 2) * 3 = 9;
+SELECT * FROM users WHERE -1 + 2 = 1;
+INSERT INTO users VALUES ('foo') ON CONFLICT DO NOTHING;
+INSERT INTO users VALUES ('foo') ON CONFLICT ON CONSTRAINT users_pkey DO NOTHING;
+INSERT INTO users VALUES ('1', 'foo') ON CONFLICT (id) DO UPDATE SET name = EXCLUDED.name WHERE users.active = TRUE;
+INSERT INTO users VALUES ('1', 'foo') ON DUPLICATE KEY UPDATE name = VALUES(name), hits = hits + 1;
+MERGE INTO customers AS t USING staging_customers s ON t.id = s.id WHEN MATCHED AND s.deleted = TRUE THEN DELETE WHEN MATCHED THEN UPDATE SET name = s.name WHEN NOT MATCHED THEN INSERT (id, name) VALUES (s.id, s.name);
+MERGE INTO customers USING (SELECT * FROM staging WHERE active = TRUE) AS s ON customers.id = s.id WHEN NOT MATCHED THEN INSERT VALUES (s.id);
+SELECT * FROM sales GROUP BY amount / 100;
+SELECT * FROM sales GROUP BY ROLLUP(region, (country, city)), CUBE(product);
+SELECT * FROM sales GROUP BY GROUPING SETS ((region, product), (region), ());
+SELECT * FROM sales WHERE GROUPING(region, product) = 0 GROUP BY CUBE(region, product);
+SELECT * FROM users WHERE id = 1 ORDER BY name;
+SELECT * FROM users WHERE nullable = TRUE AND trueish = falsey;
+DELETE FROM app."user data" WHERE "user data".id = 1;
+MERGE INTO app.t USING staging.s ON t.id = s.id WHEN MATCHED THEN DELETE;
+PREPARE find_user (integer, varchar(20)) AS SELECT * FROM users WHERE id = $1 AND name = $2;
+PREPARE purge AS DELETE FROM sessions WHERE expires_at < ?;
+EXECUTE find_user (42, 'foo');
+CREATE TABLE users (id INTEGER PRIMARY KEY AUTO_INCREMENT, name VARCHAR(20) NOT NULL DEFAULT 'x', note TEXT NULL UNIQUE);
+SELECT * FROM users WHERE name LIKE 'a%' AND CAST(age AS TEXT) ILIKE '1%' AND id::BIGINT > 0;
+SELECT * FROM users ORDER BY id LIMIT 10 OFFSET 20;
+SELECT * FROM users LIMIT 20, 10;
+SELECT * FROM users WHERE id IN (1, 2, 3) AND name NOT IN ('a') ORDER BY id LIMIT ? OFFSET $1;
+UPDATE 商品 SET 価格 = '100';
+SELECT * FROM users WHERE NOT NOT active AND - -1 = 1;
+SELECT * FROM users
+WHERE age > 1 + 2
+ORDER BY name;
+DELETE FROM sessions;
+INSERT INTO logs VALUES ('x');
+SELECT * FROM selected_items;
+SELECT * FROM t;
+DEALLOCATE PREPARE find_user;
+DEALLOCATE prepared_query;
+DEALLOCATE ALL;
+SELECT * FROM users WHERE id = 1;
+SELECT   *   FROM users
+WHERE id   =   1;
+-- Comments between and inside statements
+SELECT * FROM orders -- trailing comment
+WHERE total > 100; -- after the semicolon
@@ -0,0 +1,46 @@
+-- Expressions shared by the parser and renderer round-trip tests, one per line.
+-- Lines starting with -- are comments.
+
+name LIKE 'a%' AND b
+id::BIGINT
+CAST(id AS BIGINT)
+id = 1
+'hello'
+42
+3.14
+TRUE
+FALSE
+NULL
+name
+name = 'John'
+age != 25
+score < 100
+age > 18 AND score < 100
+status = 'active' OR status = 'pending'
+NOT active
+price + tax
+total - discount
+quantity * price
+total / "count"
+-amount
+score >= 80
+(age > 18 AND score >= 80) OR status = 'vip'
+users.name
+coalesce(nickname, 'anonymous')
+NOT - x
+t.id
+a OR b AND c = d + e * f - g / h
+a - b - c < d
+NOT a = - b * c
+NOT (a = b)
+x = -9223372036854775808
+-1
+f((1))
+f(((1)))
+f('(((')
+"café" = 'café'
+a IN (1, 2, 3)
+a NOT IN (b + 1, 'x') AND c
+(a IN (1)) = b
+NOT a IN (1, 2)
+a + 1 IN (2)